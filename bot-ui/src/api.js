@@ -74,6 +74,123 @@ export class Api {
     });
   }
 
+  /**
+   * Mark an after stream as done or not done.
+   *
+   * @param {number} id id of after stream to update.
+   * @param {boolean} done if the after stream is done.
+   */
+  afterStreamDone(id, done) {
+    return this.fetch(`after-stream/${id}/done`, {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({done}),
+    });
+  }
+
+  /**
+   * Get the URL to export after streams from.
+   *
+   * @param {string} format format to export in, either `markdown` or `json`.
+   * @param {boolean | null} done only export after streams which are or
+   * aren't done.
+   */
+  afterStreamsExportUrl(format, done = null) {
+    let query = `format=${format}`;
+
+    if (done !== null) {
+      query = `${query}&done=${done}`;
+    }
+
+    return `${this.url}/after-streams/export?${query}`;
+  }
+
+  /**
+   * List moderation log entries, newest first.
+   *
+   * @param {object} filter filter to apply, with any of the fields `channel`,
+   * `user`, `action`, `automated`, `source`, `before` and `limit`.
+   */
+  moderationLog(filter = {}) {
+    let queries = [];
+
+    for (let key of Object.keys(filter)) {
+      if (filter[key] !== undefined && filter[key] !== null && filter[key] !== "") {
+        queries.push(`${key}=${encodeURIComponent(filter[key])}`);
+      }
+    }
+
+    let query = "";
+
+    if (queries.length > 0) {
+      query = queries.join("&");
+      query = `?${query}`;
+    }
+
+    return this.fetch(`moderation-log${query}`);
+  }
+
+  /**
+   * List all bad words along with their exceptions.
+   */
+  badWords() {
+    return this.fetch("bad-words");
+  }
+
+  /**
+   * Insert or replace a bad word.
+   *
+   * @param {string} word the word to edit.
+   * @param {object} data the `why`, `severity`, `action`, `duration` and
+   * `only_live` fields of the word.
+   */
+  badWordsEdit(word, data) {
+    return this.fetch(["bad-words", word], {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify(data),
+    });
+  }
+
+  /**
+   * Delete a bad word.
+   *
+   * @param {string} word the word to delete.
+   */
+  badWordsDelete(word) {
+    return this.fetch(["bad-words", word], {
+      method: "DELETE",
+    });
+  }
+
+  /**
+   * Add an exception which should never match the given bad word.
+   *
+   * @param {string} word the bad word.
+   * @param {string} exception the exception to add.
+   */
+  badWordsInsertException(word, exception) {
+    return this.fetch(["bad-words", word, "exceptions", exception], {
+      method: "PUT",
+    });
+  }
+
+  /**
+   * Remove an exception from the given bad word.
+   *
+   * @param {string} word the bad word.
+   * @param {string} exception the exception to remove.
+   */
+  badWordsDeleteException(word, exception) {
+    return this.fetch(["bad-words", word, "exceptions", exception], {
+      method: "DELETE",
+    });
+  }
+
   /**
    * Get the list of settings.
    */
@@ -231,6 +348,263 @@ export class Api {
     });
   }
 
+  /**
+   * List all triggers from a channel.
+   */
+  triggers(channel) {
+    return this.fetch(["triggers", channel]);
+  }
+
+  /**
+   * Insert or edit a trigger.
+   *
+   * @param {object} key key of the trigger to edit
+   * @param {object} data kind, pattern, template and options of the trigger
+   */
+  triggersEdit(key, data) {
+    return this.fetch(["triggers", key.channel, key.name], {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify(data),
+    });
+  }
+
+  /**
+   * Delete a trigger.
+   *
+   * @param {object} key key of the trigger to delete
+   */
+  triggersDelete(key) {
+    return this.fetch(["triggers", key.channel, key.name], {
+      method: "DELETE",
+    });
+  }
+
+  /**
+   * Edit the disabled state of a trigger.
+   *
+   * @param {object} key key of the trigger to edit
+   * @param {bool} disabled set the trigger disabled or not
+   */
+  triggersEditDisabled(key, disabled) {
+    return this.fetch(["triggers", key.channel, key.name, "disabled"], {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({disabled}),
+    });
+  }
+
+  /**
+   * Edit the cooldowns, cost and requirement of a command.
+   *
+   * @param {object} key key of the command to edit
+   * @param {object} restrictions the restrictions to set, where missing ones are cleared
+   */
+  commandsEditRestrictions(key, restrictions) {
+    return this.fetch(["commands", key.channel, key.name, "restrictions"], {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify(restrictions),
+    });
+  }
+
+  /**
+   * List quotes from a channel.
+   *
+   * @param {string} channel channel to list quotes for
+   * @param {string} q optional query to search quotes for
+   */
+  quotes(channel, q) {
+    let query = "";
+
+    if (q) {
+      query = `?q=${encodeURIComponent(q)}`;
+    }
+
+    return this.fetch(`${encodePath(["quotes", channel])}${query}`);
+  }
+
+  /**
+   * Add a quote.
+   *
+   * @param {string} channel channel to add the quote to
+   * @param {object} data author, text and optionally game of the quote
+   */
+  quotesAdd(channel, data) {
+    return this.fetch(["quotes", channel], {
+      method: "POST",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify(data),
+    });
+  }
+
+  /**
+   * Edit the author or text of a quote.
+   *
+   * @param {string} channel channel of the quote
+   * @param {number} id id of the quote to edit
+   * @param {object} data author and/or text to set
+   */
+  quotesEdit(channel, id, data) {
+    return this.fetch(["quotes", channel, String(id)], {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify(data),
+    });
+  }
+
+  /**
+   * Delete a quote.
+   *
+   * @param {string} channel channel of the quote
+   * @param {number} id id of the quote to delete
+   */
+  quotesDelete(channel, id) {
+    return this.fetch(["quotes", channel, String(id)], {
+      method: "DELETE",
+    });
+  }
+
+  /**
+   * List named counters in a channel.
+   *
+   * @param {string} channel channel to list counters for
+   */
+  counters(channel) {
+    return this.fetch(["counters", channel]);
+  }
+
+  /**
+   * Set the value of a named counter.
+   *
+   * @param {string} channel channel of the counter
+   * @param {string} name name of the counter
+   * @param {number} count value to set
+   */
+  countersEdit(channel, name, count) {
+    return this.fetch(["counters", channel, name], {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({ count }),
+    });
+  }
+
+  /**
+   * Delete a named counter.
+   *
+   * @param {string} channel channel of the counter
+   * @param {string} name name of the counter to delete
+   */
+  countersDelete(channel, name) {
+    return this.fetch(["counters", channel, name], {
+      method: "DELETE",
+    });
+  }
+
+  /**
+   * List the state of all loaded scripts.
+   */
+  scripts() {
+    return this.fetch("scripts");
+  }
+
+  /**
+   * List data stored by scripts.
+   *
+   * @param {string} channel only list data in the given channel, if set
+   * @param {string} namespace only list data in the given namespace, if set
+   */
+  scriptData(channel, namespace) {
+    let query = [];
+
+    if (channel) {
+      query.push(`channel=${encodeURIComponent(channel)}`);
+    }
+
+    if (namespace) {
+      query.push(`namespace=${encodeURIComponent(namespace)}`);
+    }
+
+    query = query.length > 0 ? `?${query.join("&")}` : "";
+    return this.fetch(`script-data${query}`);
+  }
+
+  /**
+   * Set the value of a key stored by a script.
+   *
+   * @param {string} channel channel of the key
+   * @param {string} namespace namespace of the key
+   * @param {string} id hex id of the key
+   * @param {any} value value to set
+   * @param {string} ttl expire the key after the given duration, if set
+   */
+  scriptDataEdit(channel, namespace, id, value, ttl) {
+    return this.fetch(["script-data", channel, namespace, id], {
+      method: "PUT",
+      headers: {
+        "Content-Type": "application/json",
+      },
+      body: JSON.stringify({ value, ttl: ttl || null }),
+    });
+  }
+
+  /**
+   * Delete a key stored by a script.
+   *
+   * @param {string} channel channel of the key
+   * @param {string} namespace namespace of the key
+   * @param {string} id hex id of the key
+   */
+  scriptDataDelete(channel, namespace, id) {
+    return this.fetch(["script-data", channel, namespace, id], {
+      method: "DELETE",
+    });
+  }
+
+  /**
+   * List everyone in the viewer queue, in order.
+   *
+   * @param {string} channel channel of the queue
+   */
+  queue(channel) {
+    return this.fetch(["queue", channel]);
+  }
+
+  /**
+   * Remove everyone from the viewer queue.
+   *
+   * @param {string} channel channel of the queue
+   */
+  queueClear(channel) {
+    return this.fetch(["queue", channel], {
+      method: "DELETE",
+    });
+  }
+
+  /**
+   * Remove a single user from the viewer queue.
+   *
+   * @param {string} channel channel of the queue
+   * @param {string} user login of the user to remove
+   */
+  queueRemove(channel, user) {
+    return this.fetch(["queue", channel, user], {
+      method: "DELETE",
+    });
+  }
+
   promotions(channel) {
     return this.fetch(["promotions", channel]);
   }
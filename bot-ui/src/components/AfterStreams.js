@@ -46,6 +46,24 @@ export default class AfterStreams extends React.Component {
     }
   }
 
+  /**
+   * Mark the given afterstream as done or not done.
+   *
+   * @param {number} id afterstream id to update
+   * @param {boolean} done if the afterstream is done
+   */
+  async setDone(id, done) {
+    try {
+      await this.api.afterStreamDone(id, done);
+      await this.list();
+    } catch(e) {
+      this.setState({
+        loading: false,
+        error: `failed to update after stream: ${e}`,
+      });
+    }
+  }
+
   /**
    * Delete the given afterstream.
    *
@@ -79,6 +97,7 @@ export default class AfterStreams extends React.Component {
             <thead>
               <tr>
                 <th>User</th>
+                <th>Category</th>
                 <th className="table-fill">Message</th>
                 <th></th>
               </tr>
@@ -94,8 +113,12 @@ export default class AfterStreams extends React.Component {
                         <span className="afterstream-datetime datetime">{a.added_at}</span>
                       </span>
                     </td>
-                    <td><code>{a.text}</code></td>
+                    <td>{a.category}</td>
+                    <td className={a.done_at ? "afterstream-done" : null}><code>{a.text}</code></td>
                     <td>
+                      <Button size="sm" variant={a.done_at ? "secondary" : "success"} className="action" title={a.done_at ? "Mark as not done" : "Mark as done"} onClick={() => this.setDone(a.id, !a.done_at)}>
+                        <FontAwesomeIcon icon={a.done_at ? "undo" : "check"} />
+                      </Button>
                       <Button size="sm" variant="danger" className="action" onClick={() => this.delete(a.id)}>
                         <FontAwesomeIcon icon="trash" />
                       </Button>
@@ -118,6 +141,15 @@ export default class AfterStreams extends React.Component {
         onError={error => this.setState({configLoading: false, error})}
       />
 
+      <div className="mb-3">
+        <Button size="sm" variant="primary" className="action" href={this.api.afterStreamsExportUrl("markdown", false)}>
+          Export as Markdown
+        </Button>
+        <Button size="sm" variant="primary" className="action" href={this.api.afterStreamsExportUrl("json", false)}>
+          Export as JSON
+        </Button>
+      </div>
+
       {content}
     </>;
   }
@@ -0,0 +1,152 @@
+import React from "react";
+import {Alert, Badge, Table} from "react-bootstrap";
+import Websocket from "react-websocket";
+import {websocketUrl} from "../utils.js";
+import {Loading, Error} from 'shared-ui/components';
+
+/**
+ * Test if the given script is broken, either because it failed to load or
+ * because it has been disabled.
+ *
+ * @param {object} script state of the script
+ */
+export function isBroken(script) {
+  return !!script.load_error || script.disabled;
+}
+
+/**
+ * Parse a message from the scripts websocket, returning the states of all
+ * scripts or null if the message is not recognized.
+ *
+ * @param {string} d the raw message
+ */
+export function parseScripts(d) {
+  let data = null;
+
+  try {
+    data = JSON.parse(d);
+  } catch(e) {
+    console.log("failed to deserialize message");
+    return null;
+  }
+
+  switch (data.type) {
+    case "script/all":
+      return data.scripts;
+    default:
+      return null;
+  }
+}
+
+export default class Scripts extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = this.props.api;
+
+    this.state = {
+      loading: false,
+      error: null,
+      data: null,
+    };
+  }
+
+  async componentDidMount() {
+    await this.list();
+  }
+
+  /**
+   * Refresh the list of scripts.
+   */
+  async list() {
+    this.setState({
+      loading: true,
+    });
+
+    try {
+      let data = await this.api.scripts();
+
+      this.setState({
+        loading: false,
+        error: null,
+        data,
+      });
+    } catch(e) {
+      this.setState({
+        loading: false,
+        error: `failed to request scripts: ${e}`,
+        data: null,
+      });
+    }
+  }
+
+  handleData(d) {
+    let data = parseScripts(d);
+
+    if (data !== null) {
+      this.setState({data});
+    }
+  }
+
+  render() {
+    let content = null;
+
+    if (this.state.data) {
+      if (this.state.data.length === 0) {
+        content = (
+          <Alert variant="info">
+            No scripts loaded!
+          </Alert>
+        );
+      } else {
+        content = (
+          <Table responsive="sm">
+            <thead>
+              <tr>
+                <th>Script</th>
+                <th>Commands</th>
+                <th>Loaded At</th>
+                <th className="table-fill">Status</th>
+              </tr>
+            </thead>
+            <tbody>
+              {this.state.data.map((s, id) => {
+                let status = <Badge variant="success">OK</Badge>;
+
+                if (s.load_error) {
+                  status = <Badge variant="danger">Failed to load</Badge>;
+                } else if (s.disabled) {
+                  status = <Badge variant="danger">Disabled</Badge>;
+                } else if (s.warnings.length > 0) {
+                  status = <Badge variant="warning">Warnings</Badge>;
+                }
+
+                let errors = [s.load_error, s.last_error].filter(e => !!e);
+
+                return (
+                  <tr key={id}>
+                    <td><code>{s.path}</code> <small>in {s.channel}</small></td>
+                    <td>{s.commands.map(c => <code key={c} className="mr-1">!{c}</code>)}</td>
+                    <td className="datetime">{s.loaded_at}</td>
+                    <td>
+                      {status}
+                      {s.warnings.map((w, i) => <div key={`w${i}`}><small>{w}</small></div>)}
+                      {errors.map((e, i) => <pre key={`e${i}`} className="mb-0"><small>{e}</small></pre>)}
+                    </td>
+                  </tr>
+                );
+              })}
+            </tbody>
+          </Table>
+        );
+      }
+    }
+
+    return <>
+      <h1 className='oxi-page-title'>Scripts</h1>
+      <Websocket url={websocketUrl("ws/scripts")} onMessage={this.handleData.bind(this)} />
+      <Loading isLoading={this.state.loading} />
+      <Error error={this.state.error} />
+      {content}
+    </>;
+  }
+}
@@ -4,13 +4,15 @@ import {Api} from "./api.js";
 import React from "react";
 import ReactDOM from "react-dom";
 import {BrowserRouter as Router, Route, Link, withRouter} from "react-router-dom";
-import {Container, Row, Col, Navbar, Nav, NavDropdown, Alert, Button, Form} from "react-bootstrap";
+import {Container, Row, Col, Navbar, Nav, NavDropdown, Alert, Badge, Button, Form} from "react-bootstrap";
+import Websocket from "react-websocket";
 import Connections from "./components/Connections.js";
 import Devices from "./components/Devices.js";
 import AfterStreams from "./components/AfterStreams.js";
 import Overlay from "./components/Overlay.js";
 import Settings from "./components/Settings.js";
 import Cache from "./components/Cache";
+import Scripts, {isBroken, parseScripts} from "./components/Scripts";
 import Modules from "./components/Modules.js";
 import ImportExport from "./components/ImportExport.js";
 import Commands from "./components/Commands.js";
@@ -86,6 +88,21 @@ class CachePage extends React.Component {
   }
 }
 
+class ScriptsPage extends React.Component {
+  constructor(props) {
+    super(props);
+    this.api = new Api(utils.apiUrl());
+  }
+
+  render() {
+    return (
+      <RouteLayout>
+        <Scripts api={this.api} {...this.props} />
+      </RouteLayout>
+    );
+  }
+}
+
 class ModulesPage extends React.Component {
   constructor(props) {
     super(props);
@@ -288,6 +305,22 @@ class IndexPage extends React.Component {
 class Layout extends React.Component {
   constructor(props) {
     super(props)
+
+    this.state = {
+      brokenScripts: 0,
+    };
+  }
+
+  /**
+   * Keep track of the number of broken scripts, so that they can be
+   * highlighted.
+   */
+  handleScripts(d) {
+    let scripts = parseScripts(d);
+
+    if (scripts !== null) {
+      this.setState({brokenScripts: scripts.filter(isBroken).length});
+    }
   }
 
   goToWebsite() {
@@ -297,7 +330,15 @@ class Layout extends React.Component {
   render() {
     let path = this.props.location.pathname;
 
+    let brokenScripts = null;
+
+    if (this.state.brokenScripts > 0) {
+      brokenScripts = <Badge variant="danger" className="ml-1" title="Scripts which are broken">{this.state.brokenScripts}</Badge>;
+    }
+
     return <>
+      <Websocket url={utils.websocketUrl("ws/scripts")} onMessage={this.handleScripts.bind(this)} />
+
       <Navbar className="mb-3" bg="dark" variant="dark" expand="md">
         <Container>
           <Navbar.Brand as={Link} to="/" >
@@ -331,13 +372,16 @@ class Layout extends React.Component {
                 </NavDropdown.Item>
               </NavDropdown>
 
-              <NavDropdown title="Advanced">
+              <NavDropdown title={<>Advanced{brokenScripts}</>}>
                 <NavDropdown.Item as={Link} active={path === "/settings"} to="/settings">
                   Settings
                 </NavDropdown.Item>
                 <NavDropdown.Item as={Link} active={path === "/cache"} to="/cache">
                   Cache
                 </NavDropdown.Item>
+                <NavDropdown.Item as={Link} active={path === "/scripts"} to="/scripts">
+                  Scripts{brokenScripts}
+                </NavDropdown.Item>
               </NavDropdown>
 
               <NavDropdown title="Misc">
@@ -386,6 +430,7 @@ function AppRouter() {
       <Route path="/after-streams" exact component={AfterStreamsPage} />
       <Route path="/settings" exact component={SettingsPage} />
       <Route path="/cache" exact component={CachePage} />
+      <Route path="/scripts" exact component={ScriptsPage} />
       <Route path="/modules" component={ModulesPage} />
       <Route path="/authorization" exact component={props => (
         <AuthorizedPage><Authorization {...props} /></AuthorizedPage>
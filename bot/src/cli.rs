@@ -1,9 +1,10 @@
+use std::collections::BTreeMap;
 use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::pin::{pin, Pin};
 use std::time;
 
-use anyhow::{anyhow, Context, Result};
+use anyhow::{anyhow, bail, Context, Result};
 use async_fuse::Fuse;
 use async_injector::{Injector, Key};
 use common::backoff;
@@ -36,6 +37,10 @@ argwerk::define! {
         config: Option<PathBuf>,
         log: Vec<String>,
         stack_size: Option<usize>,
+        script: Option<(String, PathBuf)>,
+        test_user: Option<String>,
+        test_roles: Vec<String>,
+        test_scopes: Vec<String>,
     }
     /// Show this help.
     ["--help" | "-h"] => {
@@ -66,6 +71,22 @@ argwerk::define! {
     ["--stack-size", size] => {
         stack_size = Some(str::parse(&size)?);
     }
+    /// Run the tests registered by all scripts in the given directory, as in `script test <dir>`.
+    ["script", command, #[os] dir] => {
+        script = Some((command, PathBuf::from(dir)));
+    }
+    /// Name of the user invoking scripts under test.
+    ["--test-user", name] => {
+        test_user = Some(name);
+    }
+    /// Add a role to the user invoking scripts under test. Example: --test-role @moderator
+    ["--test-role", role] => {
+        test_roles.push(role);
+    }
+    /// Add a scope to the user invoking scripts under test. Example: --test-scope song
+    ["--test-scope", scope] => {
+        test_scopes.push(scope);
+    }
 }
 
 /// Configure logging.
@@ -133,6 +154,10 @@ pub fn main() -> Result<()> {
         runtime.build()?
     };
 
+    if let Some((command, dir)) = &args.script {
+        return runtime.block_on(script_command(command, dir, &args));
+    }
+
     if let Some(size) = args.stack_size {
         let thread = std::thread::Builder::new()
             .name(format!("main-with-stack-{}", size))
@@ -144,6 +169,63 @@ pub fn main() -> Result<()> {
     }
 }
 
+/// Run a `script` command.
+async fn script_command(command: &str, dir: &Path, args: &Args) -> Result<()> {
+    if command != "test" {
+        bail!("unsupported script command `{}`, expected `test`", command);
+    }
+
+    let db = db::Database::memory()?;
+    let settings_schema = settings::Schema::load_bytes(crate::SETTINGS_SCHEMA)?;
+    let settings = settings::Settings::new(db, settings_schema);
+
+    let user = chat::TestUser {
+        name: args
+            .test_user
+            .clone()
+            .unwrap_or_else(|| String::from("tester")),
+        roles: args.test_roles.clone(),
+        scopes: args.test_scopes.clone(),
+    };
+
+    let outcomes = chat::test_dir(settings, user, [dir]).await?;
+
+    let mut failed = 0;
+
+    for outcome in &outcomes {
+        let name = match &outcome.name {
+            Some(name) => format!("{}::{}", outcome.path.display(), name),
+            None => outcome.path.display().to_string(),
+        };
+
+        let Some(error) = &outcome.error else {
+            println!("test {} ... ok", name);
+            continue;
+        };
+
+        failed += 1;
+        println!("test {} ... FAILED", name);
+
+        for line in &outcome.output {
+            println!("  > {}", line);
+        }
+
+        println!("{}", error);
+    }
+
+    println!(
+        "test result: {} passed; {} failed",
+        outcomes.len() - failed,
+        failed
+    );
+
+    if failed > 0 {
+        bail!("{} script test(s) failed", failed);
+    }
+
+    Ok(())
+}
+
 async fn inner_main(args: Args) -> Result<()> {
     let (old_root, root) = match args.root {
         Some(root) => (None, root),
@@ -338,6 +420,21 @@ async fn try_main(
         .update(db::Promotions::load(db.clone()).await?)
         .await;
     injector.update(db::Themes::load(db.clone()).await?).await;
+    injector.update(db::Quotes::load(db.clone()).await?).await;
+    injector.update(db::Counters::load(db.clone()).await?).await;
+    #[cfg(feature = "scripting")]
+    injector
+        .update(db::ScriptKeys::load(db.clone()).await?)
+        .await;
+    injector.update(db::Raffles::load(db.clone()).await?).await;
+    injector.update(db::Queue::load(db.clone()).await?).await;
+    injector.update(db::Polls::load(db.clone()).await?).await;
+    injector
+        .update(db::Triggers::load(db.clone()).await?)
+        .await;
+    injector
+        .update(db::ModerationLog::load(db.clone()).await?)
+        .await;
 
     let message_bus = bus::Bus::new();
     injector.update(message_bus.clone()).await;
@@ -345,6 +442,10 @@ async fn try_main(
     injector.update(global_bus.clone()).await;
     let youtube_bus = bus::Bus::new();
     injector.update(youtube_bus.clone()).await;
+    let countdown_bus = bus::Bus::new();
+    injector.update(countdown_bus.clone()).await;
+    let script_bus = bus::Bus::new();
+    injector.update(script_bus.clone()).await;
     let command_bus = bus::Bus::new();
     injector.update(command_bus.clone()).await;
 
@@ -367,6 +468,8 @@ async fn try_main(
         message_bus.clone(),
         global_bus.clone(),
         youtube_bus.clone(),
+        countdown_bus.clone(),
+        script_bus.clone(),
         command_bus.clone(),
         auth.clone(),
         latest.clone(),
@@ -413,7 +516,7 @@ async fn try_main(
     );
     let twitch_token_future = token!("twitch-bot", tags::Token::Twitch(tags::Twitch::Bot));
 
-    let twitch_pubsub_future = api::twitch::pubsub::connect(&settings, &injector);
+    let twitch_eventsub_future = api::twitch::eventsub::connect(&settings, &injector);
 
     let twitch_streamer_client_future = api::provider::twitch_and_user(
         crate::USER_AGENT,
@@ -476,6 +579,7 @@ async fn try_main(
     chat.module(module::countdown::Module);
     chat.module(module::gtav::Module);
     chat.module(module::water::Module);
+    chat.module(module::reminder::Module);
     chat.module(module::misc::Module);
     chat.module(module::after_stream::Module);
     chat.module(module::clip::Module);
@@ -485,6 +589,14 @@ async fn try_main(
     chat.module(module::poll::Module);
     chat.module(module::weather::Module);
     chat.module(module::help::Module);
+    chat.module(module::nuke::Module);
+    chat.module(module::bad_words::Module);
+    chat.module(module::trigger_admin::Module);
+    chat.module(module::quote::Module);
+    chat.module(module::counter::Module);
+    chat.module(module::raffle::Module);
+    chat.module(module::bet::Module);
+    chat.module(module::queue::Module);
 
     let notify_after_streams = notify_after_streams(&injector, stream_state_rx, system.clone());
 
@@ -501,7 +613,7 @@ async fn try_main(
         nightbot_token_future,
         streamer_token_future,
         twitch_token_future,
-        twitch_pubsub_future,
+        twitch_eventsub_future,
         twitch_streamer_client_future,
         twitch_bot_client_future,
         weather_future,
@@ -562,12 +674,30 @@ async fn notify_after_streams(
                             None => continue,
                         };
 
-                        let list = after_streams.list().await?;
+                        let filter = db::after_streams::Filter {
+                            done: Some(false),
+                            ..Default::default()
+                        };
+
+                        let list = after_streams.list_filtered(filter).await?;
 
                         if !list.is_empty() {
+                            let mut categories = BTreeMap::<_, usize>::new();
+
+                            for a in &list {
+                                let category = a.category.as_deref().unwrap_or("uncategorized");
+                                *categories.entry(category).or_default() += 1;
+                            }
+
+                            let categories = categories
+                                .into_iter()
+                                .map(|(category, count)| format!("{} {}", count, category))
+                                .collect::<Vec<_>>();
+
                             let reminder = sys::Notification::new(format!(
-                                "You have {} afterstream messages.\nClick to open...",
-                                list.len()
+                                "You have {} afterstream messages ({}).\nClick to open...",
+                                list.len(),
+                                categories.join(", ")
                             ));
 
                             let reminder = reminder.on_click(|| {
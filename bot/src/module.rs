@@ -4,19 +4,28 @@ pub(crate) mod admin;
 pub(crate) mod after_stream;
 pub(crate) mod alias_admin;
 pub(crate) mod auth;
+pub(crate) mod bad_words;
+pub(crate) mod bet;
 pub(crate) mod clip;
 pub(crate) mod command_admin;
 pub(crate) mod countdown;
+pub(crate) mod counter;
 pub(crate) mod eight_ball;
 pub(crate) mod gtav;
 pub(crate) mod help;
 pub(crate) mod misc;
+pub(crate) mod nuke;
 pub(crate) mod poll;
 pub(crate) mod promotions;
+pub(crate) mod queue;
+pub(crate) mod quote;
+pub(crate) mod raffle;
+pub(crate) mod reminder;
 pub(crate) mod song;
 pub(crate) mod speedrun;
 pub(crate) mod swearjar;
 pub(crate) mod theme_admin;
 pub(crate) mod time;
+pub(crate) mod trigger_admin;
 pub(crate) mod water;
 pub(crate) mod weather;
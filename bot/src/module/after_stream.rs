@@ -5,12 +5,15 @@ use common::Duration;
 
 use chat::command;
 use chat::module;
+use chat::stream_info;
 
 /// Handler for the `!afterstream` command.
 pub(crate) struct AfterStream {
     pub(crate) enabled: settings::Var<bool>,
     pub(crate) cooldown: settings::Var<Cooldown>,
+    pub(crate) categories: settings::Var<Vec<String>>,
     pub(crate) after_streams: async_injector::Ref<db::AfterStreams>,
+    pub(crate) stream_info: stream_info::StreamInfo,
 }
 
 #[async_trait]
@@ -51,10 +54,52 @@ impl command::Handler for AfterStream {
             return Ok(());
         }
 
+        let categories = self.categories.load().await;
+        let mut it = ctx.rest().trim().splitn(2, char::is_whitespace);
+        let first = it.next().unwrap_or_default();
+
+        let (category, text) = match categories.iter().find(|c| c.eq_ignore_ascii_case(first)) {
+            Some(category) => (
+                Some(category.as_str()),
+                it.next().unwrap_or_default().trim(),
+            ),
+            None => (None, ctx.rest().trim()),
+        };
+
+        if text.is_empty() {
+            chat::respond_bail!("Expected: !afterstream {} <reminder>", first);
+        }
+
+        let session =
+            self.stream_info
+                .data
+                .read()
+                .stream
+                .as_ref()
+                .map(|s| db::after_streams::Session {
+                    id: s.id.clone(),
+                    started_at: s.started_at,
+                });
+
         after_streams
-            .push(ctx.channel(), user.login(), ctx.rest())
+            .push(
+                ctx.channel(),
+                user.login(),
+                category,
+                text,
+                session.as_ref(),
+            )
             .await?;
-        chat::respond!(ctx, "Reminder added.");
+
+        match category {
+            Some(category) => {
+                chat::respond!(ctx, "Reminder added to {}.", category);
+            }
+            None => {
+                chat::respond!(ctx, "Reminder added.");
+            }
+        }
+
         Ok(())
     }
 }
@@ -74,6 +119,7 @@ impl chat::Module for Module {
             injector,
             handlers,
             settings,
+            stream_info,
             ..
         }: module::HookContext<'_, '_>,
     ) -> Result<()> {
@@ -86,7 +132,9 @@ impl chat::Module for Module {
                 cooldown: settings
                     .var("cooldown", Cooldown::from_duration(Duration::seconds(30)))
                     .await?,
+                categories: settings.var("categories", Vec::new()).await?,
                 after_streams: injector.var().await,
+                stream_info: stream_info.clone(),
             },
         );
 
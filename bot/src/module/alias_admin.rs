@@ -74,6 +74,10 @@ impl chat::Module for Module {
         "alias"
     }
 
+    fn per_channel(&self) -> bool {
+        true
+    }
+
     async fn hook(
         &self,
         module::HookContext {
@@ -0,0 +1,196 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use common::Duration;
+use db::words::Action;
+
+/// Handler for the `!badword` command.
+pub(crate) struct Handler {
+    bad_words: async_injector::Ref<db::Words>,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::BadWordsEdit)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        let bad_words = match self.bad_words.load().await {
+            Some(bad_words) => bad_words,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            Some("add") => {
+                let word = ctx.next_str("<word> [why]")?;
+
+                let why = match ctx.rest().trim() {
+                    "" => None,
+                    why => Some(why),
+                };
+
+                bad_words.edit(&word, why).await?;
+                chat::respond!(ctx, "Added bad word `{}`", word);
+            }
+            Some("delete") => {
+                let word = ctx.next_str("<word>")?;
+
+                if !bad_words.delete(&word).await? {
+                    chat::respond!(ctx, "No bad word `{}`", word);
+                    return Ok(());
+                }
+
+                chat::respond!(ctx, "Deleted bad word `{}`", word);
+            }
+            Some("show") => {
+                let word = ctx.next_str("<word>")?;
+
+                let entry = bad_words
+                    .list()
+                    .await?
+                    .into_iter()
+                    .find(|e| e.word.word == word);
+
+                let Some(entry) = entry else {
+                    chat::respond!(ctx, "No bad word `{}`", word);
+                    return Ok(());
+                };
+
+                let mut parts = vec![format!("action: {}", describe_action(&entry.word))];
+                parts.push(format!("severity: {}", entry.word.severity));
+
+                if entry.word.only_live {
+                    parts.push(String::from("only when live"));
+                }
+
+                if !entry.exceptions.is_empty() {
+                    parts.push(format!("exceptions: {}", entry.exceptions.join(", ")));
+                }
+
+                chat::respond!(ctx, "`{}` -> {}", word, parts.join(", "));
+            }
+            Some("action") => {
+                let word = ctx.next_str("<word> <action> [duration]")?;
+                let action = ctx.next_parse::<Action, _>("<word> <action> [duration]")?;
+                let duration = ctx.next_parse_optional::<Duration>()?;
+
+                let Some(mut bad_word) = bad_words.get(&word).await? else {
+                    chat::respond!(ctx, "No bad word `{}`", word);
+                    return Ok(());
+                };
+
+                bad_word.action = action.as_str().to_owned();
+                bad_word.duration = match (action, duration) {
+                    (Action::Timeout, Some(duration)) => {
+                        Some(i32::try_from(duration.num_seconds()).unwrap_or(i32::MAX))
+                    }
+                    _ => None,
+                };
+
+                let description = describe_action(&bad_word);
+                bad_words.update(bad_word).await?;
+                chat::respond!(ctx, "Action for `{}` set to {}", word, description);
+            }
+            Some("severity") => {
+                let word = ctx.next_str("<word> <severity>")?;
+                let severity = ctx.next_parse::<i32, _>("<word> <severity>")?;
+
+                let Some(mut bad_word) = bad_words.get(&word).await? else {
+                    chat::respond!(ctx, "No bad word `{}`", word);
+                    return Ok(());
+                };
+
+                bad_word.severity = severity;
+                bad_words.update(bad_word).await?;
+                chat::respond!(ctx, "Severity for `{}` set to {}", word, severity);
+            }
+            Some("live") => {
+                let word = ctx.next_str("<word> <true|false>")?;
+                let only_live = ctx.next_parse::<bool, _>("<word> <true|false>")?;
+
+                let Some(mut bad_word) = bad_words.get(&word).await? else {
+                    chat::respond!(ctx, "No bad word `{}`", word);
+                    return Ok(());
+                };
+
+                bad_word.only_live = only_live;
+                bad_words.update(bad_word).await?;
+
+                if only_live {
+                    chat::respond!(ctx, "`{}` now only applies while live", word);
+                } else {
+                    chat::respond!(ctx, "`{}` now always applies", word);
+                }
+            }
+            Some("except") => {
+                let word = ctx.next_str("<word> <exception>")?;
+                let exception = ctx.next_str("<word> <exception>")?;
+
+                if bad_words.get(&word).await?.is_none() {
+                    chat::respond!(ctx, "No bad word `{}`", word);
+                    return Ok(());
+                }
+
+                bad_words.insert_exception(&word, &exception).await?;
+                chat::respond!(ctx, "`{}` no longer matches `{}`", exception, word);
+            }
+            Some("unexcept") => {
+                let word = ctx.next_str("<word> <exception>")?;
+                let exception = ctx.next_str("<word> <exception>")?;
+
+                if !bad_words.delete_exception(&word, &exception).await? {
+                    chat::respond!(ctx, "No exception `{}` for `{}`", exception, word);
+                    return Ok(());
+                }
+
+                chat::respond!(ctx, "Removed exception `{}` for `{}`", exception, word);
+            }
+            _ => {
+                chat::respond!(
+                    ctx,
+                    "Expected: add, delete, show, action, severity, live, except, or unexcept."
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Describe the action taken for a bad word.
+fn describe_action(bad_word: &db::models::BadWord) -> String {
+    match (bad_word.action.as_str(), bad_word.duration) {
+        ("timeout", Some(duration)) => {
+            let duration = Duration::seconds(u64::try_from(duration).unwrap_or_default());
+            format!("timeout for {}", duration)
+        }
+        (action, _) => action.to_owned(),
+    }
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "bad-words"
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector, handlers, ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        handlers.insert(
+            "badword",
+            Handler {
+                bad_words: injector.var().await,
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,498 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use chrono::{DateTime, Utc};
+use common::{Duration, OwnedChannel};
+use tokio::sync::Mutex;
+
+/// Subcommands of `!bet`, which can't be used as names of outcomes.
+const RESERVED: &[&str] = &["open", "lock", "resolve", "cancel"];
+
+/// A bet which is currently running.
+struct Bet {
+    title: String,
+    /// Names of the outcomes that can be bet on.
+    outcomes: Vec<String>,
+    /// When the bet stops accepting stakes.
+    locks_at: DateTime<Utc>,
+    /// If the bet has been locked.
+    locked: bool,
+    /// Stakes, by login.
+    stakes: HashMap<String, Stake>,
+}
+
+impl Bet {
+    /// Find the index of the outcome with the given name or number.
+    fn outcome(&self, name: &str) -> Option<usize> {
+        if let Ok(n) = str::parse::<usize>(name) {
+            return n.checked_sub(1).filter(|n| *n < self.outcomes.len());
+        }
+
+        self.outcomes
+            .iter()
+            .position(|o| o.eq_ignore_ascii_case(name))
+    }
+
+    /// The total amount staked on the given outcome.
+    fn pool(&self, outcome: usize) -> i64 {
+        self.stakes
+            .values()
+            .filter(|s| s.outcome == outcome)
+            .map(|s| s.amount)
+            .sum()
+    }
+
+    /// The total amount staked on all outcomes.
+    fn total(&self) -> i64 {
+        self.stakes.values().map(|s| s.amount).sum()
+    }
+
+    /// Convert into a message for the bus.
+    fn to_bus(&self) -> bus::Bet {
+        let outcomes = self
+            .outcomes
+            .iter()
+            .enumerate()
+            .map(|(index, name)| bus::BetOutcome {
+                name: name.clone(),
+                pool: self.pool(index),
+                users: self.stakes.values().filter(|s| s.outcome == index).count() as u32,
+            })
+            .collect();
+
+        bus::Bet {
+            title: self.title.clone(),
+            outcomes,
+            locks_at: self.locks_at.timestamp(),
+            locked: self.locked,
+        }
+    }
+}
+
+struct Stake {
+    outcome: usize,
+    amount: i64,
+}
+
+/// State shared between the command handler and the task locking bets.
+struct State {
+    channel: OwnedChannel,
+    bet: Mutex<Option<Bet>>,
+    global_bus: async_injector::Ref<bus::Bus<bus::Global>>,
+}
+
+impl State {
+    /// Notify overlays of the current state of the bet.
+    async fn publish(&self, bet: Option<&Bet>) {
+        if let Some(global_bus) = self.global_bus.load().await {
+            global_bus
+                .send(bus::Global::Bet {
+                    channel: self.channel.clone(),
+                    bet: bet.map(Bet::to_bus),
+                })
+                .await;
+        }
+    }
+}
+
+/// Handler for the `!bet` command.
+pub(crate) struct Handler {
+    enabled: settings::Var<bool>,
+    state: Arc<State>,
+    currency: async_injector::Ref<currency::Currency>,
+}
+
+impl Handler {
+    /// Give every user back what they staked, either refunding all of them
+    /// or none of them.
+    async fn refund(&self, currency: &currency::Currency, bet: &Bet) -> Result<()> {
+        let amounts = bet
+            .stakes
+            .iter()
+            .map(|(login, stake)| (login.clone(), stake.amount))
+            .collect::<Vec<_>>();
+
+        currency.balances_add(&self.state.channel, amounts).await
+    }
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Bet)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let Some(currency) = self.currency.load().await else {
+            chat::respond_bail!("No currency configured for stream, sorry :(");
+        };
+
+        match ctx.next().as_deref() {
+            None => {
+                let bet = self.state.bet.lock().await;
+
+                let Some(bet) = bet.as_ref() else {
+                    chat::respond!(ctx, "No bet is running.");
+                    return Ok(());
+                };
+
+                let outcomes = bet
+                    .outcomes
+                    .iter()
+                    .enumerate()
+                    .map(|(index, name)| format!("{}. {} ({})", index + 1, name, bet.pool(index)))
+                    .collect::<Vec<_>>();
+
+                let state = if bet.locked {
+                    "Bets are locked"
+                } else {
+                    "Bet with !bet <outcome> <amount>"
+                };
+
+                chat::respond!(ctx, "{}: {}. {}.", bet.title, outcomes.join(", "), state);
+            }
+            Some("open") => {
+                ctx.check_scope(auth::Scope::BetEdit).await?;
+
+                let usage = "<duration> <title> <outcome> <outcome> [outcome..]";
+                let duration = ctx.next_parse::<Duration, _>(usage)?;
+                let title = ctx.next_str(usage)?;
+                let outcomes = ctx.by_ref().collect::<Vec<_>>();
+
+                if outcomes.len() < 2 {
+                    chat::respond_bail!("Expected {}", usage);
+                }
+
+                for (index, outcome) in outcomes.iter().enumerate() {
+                    if RESERVED.contains(&outcome.to_lowercase().as_str())
+                        || str::parse::<usize>(outcome).is_ok()
+                    {
+                        chat::respond_bail!("`{}` can't be used as an outcome", outcome);
+                    }
+
+                    if outcomes[..index]
+                        .iter()
+                        .any(|o| o.eq_ignore_ascii_case(outcome))
+                    {
+                        chat::respond_bail!("Outcome `{}` was specified twice", outcome);
+                    }
+                }
+
+                let mut bet = self.state.bet.lock().await;
+
+                if bet.is_some() {
+                    chat::respond_bail!(
+                        "A bet is already running, resolve or cancel it before starting a new one"
+                    );
+                }
+
+                let new = Bet {
+                    title,
+                    outcomes,
+                    locks_at: Utc::now() + duration.as_chrono(),
+                    locked: false,
+                    stakes: HashMap::new(),
+                };
+
+                let outcomes = new
+                    .outcomes
+                    .iter()
+                    .enumerate()
+                    .map(|(index, name)| format!("{}. {}", index + 1, name))
+                    .collect::<Vec<_>>();
+
+                chat::respond!(
+                    ctx,
+                    "Betting on {} is open for {}: {}. Stake {} with !bet <outcome> <amount>.",
+                    new.title,
+                    duration,
+                    outcomes.join(", "),
+                    currency.name
+                );
+
+                self.state.publish(Some(&new)).await;
+                *bet = Some(new);
+            }
+            Some("lock") => {
+                ctx.check_scope(auth::Scope::BetEdit).await?;
+
+                let mut bet = self.state.bet.lock().await;
+
+                let Some(bet) = bet.as_mut() else {
+                    chat::respond_bail!("No bet is running");
+                };
+
+                bet.locked = true;
+                self.state.publish(Some(&*bet)).await;
+                chat::respond!(ctx, "Bets are locked!");
+            }
+            Some("resolve") => {
+                ctx.check_scope(auth::Scope::BetEdit).await?;
+
+                let name = ctx.next_str("<outcome>")?;
+
+                let mut guard = self.state.bet.lock().await;
+
+                let Some(bet) = guard.as_ref() else {
+                    chat::respond_bail!("No bet is running");
+                };
+
+                let Some(outcome) = bet.outcome(&name) else {
+                    chat::respond_bail!("No outcome named `{}`", name);
+                };
+
+                let total = bet.total();
+                let pool = bet.pool(outcome);
+                let winner = bet.outcomes[outcome].clone();
+
+                // No one staked on the winning outcome, so there is no one to
+                // pay out to.
+                if pool == 0 {
+                    if let Err(e) = self.refund(&currency, bet).await {
+                        common::log_error!(e, "Failed to refund bets");
+                        chat::respond_bail!("Failed to refund bets, the bet is still running");
+                    }
+
+                    *guard = None;
+                    self.state.publish(None).await;
+                    drop(guard);
+
+                    chat::respond!(
+                        ctx,
+                        "{} won, but no one bet on it! All bets have been refunded.",
+                        winner
+                    );
+
+                    return Ok(());
+                }
+
+                let payouts = bet
+                    .stakes
+                    .iter()
+                    .filter(|(_, stake)| stake.outcome == outcome)
+                    .map(|(login, stake)| (login.clone(), payout(total, pool, stake.amount)))
+                    .collect::<Vec<_>>();
+
+                let winners = payouts.len();
+
+                if let Err(e) = currency.balances_add(&self.state.channel, payouts).await {
+                    common::log_error!(e, "Failed to pay out bets");
+                    chat::respond_bail!("Failed to pay out bets, the bet is still running");
+                }
+
+                *guard = None;
+                self.state.publish(None).await;
+                drop(guard);
+
+                chat::respond!(
+                    ctx,
+                    "{} won! {} {} was paid out to {} users.",
+                    winner,
+                    total,
+                    currency.name,
+                    winners
+                );
+            }
+            Some("cancel") => {
+                ctx.check_scope(auth::Scope::BetEdit).await?;
+
+                let mut guard = self.state.bet.lock().await;
+
+                let Some(bet) = guard.as_ref() else {
+                    chat::respond_bail!("No bet is running");
+                };
+
+                if let Err(e) = self.refund(&currency, bet).await {
+                    common::log_error!(e, "Failed to refund bets");
+                    chat::respond_bail!("Failed to refund bets, the bet is still running");
+                }
+
+                *guard = None;
+                self.state.publish(None).await;
+                drop(guard);
+
+                chat::respond!(
+                    ctx,
+                    "The bet was cancelled and all bets have been refunded."
+                );
+            }
+            Some(name) => {
+                let name = name.to_string();
+                let amount = ctx.next_parse::<i64, _>("<outcome> <amount>")?;
+
+                if amount <= 0 {
+                    chat::respond_bail!("You have to bet a positive amount");
+                }
+
+                let Some(user) = ctx.user.real() else {
+                    chat::respond_bail!("Only real users can bet");
+                };
+
+                let mut bet = self.state.bet.lock().await;
+
+                let Some(bet) = bet.as_mut() else {
+                    chat::respond_bail!("No bet is running");
+                };
+
+                if bet.locked || bet.locks_at <= Utc::now() {
+                    chat::respond_bail!("Bets are locked");
+                }
+
+                let Some(outcome) = bet.outcome(&name) else {
+                    chat::respond_bail!("No outcome named `{}`", name);
+                };
+
+                if let Some(stake) = bet.stakes.get(user.login()) {
+                    if stake.outcome != outcome {
+                        chat::respond_bail!("You already bet on {}", bet.outcomes[stake.outcome]);
+                    }
+                }
+
+                match currency
+                    .balance_take(ctx.channel(), user.login(), amount)
+                    .await
+                {
+                    Ok(()) => {}
+                    Err(currency::BalanceTransferError::NoBalance) => {
+                        let balance = currency
+                            .balance_of(ctx.channel(), user.login())
+                            .await?
+                            .unwrap_or_default()
+                            .balance;
+
+                        chat::respond_bail!(
+                            "You don't have enough {currency} to bet {amount}, you currently have {balance} {currency}.",
+                            currency = currency.name,
+                            amount = amount,
+                            balance = balance,
+                        );
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+
+                let stake = bet
+                    .stakes
+                    .entry(user.login().to_string())
+                    .or_insert(Stake { outcome, amount: 0 });
+
+                stake.amount += amount;
+                let staked = stake.amount;
+
+                self.state.publish(Some(&*bet)).await;
+
+                chat::respond!(
+                    ctx,
+                    "You have bet {} {} on {}.",
+                    staked,
+                    currency.name,
+                    bet.outcomes[outcome]
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Calculate what a winning stake pays out, which is its share of the
+/// winning pool applied to everything that was staked.
+fn payout(total: i64, pool: i64, stake: i64) -> i64 {
+    (i128::from(total) * i128::from(stake) / i128::from(pool)) as i64
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "bet"
+    }
+
+    fn per_channel(&self) -> bool {
+        true
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            tasks,
+            sender,
+            settings,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("bet");
+
+        let state = Arc::new(State {
+            channel: sender.channel().to_owned(),
+            bet: Mutex::new(None),
+            global_bus: injector.var().await,
+        });
+
+        handlers.insert(
+            "bet",
+            Handler {
+                enabled: settings.var("enabled", false).await?,
+                state: state.clone(),
+                currency: injector.var().await,
+            },
+        );
+
+        let sender = sender.clone();
+
+        // Lock bets once their timer runs out.
+        let future = async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+
+            loop {
+                interval.tick().await;
+
+                let mut bet = state.bet.lock().await;
+
+                let Some(bet) = bet.as_mut() else {
+                    continue;
+                };
+
+                if bet.locked || bet.locks_at > Utc::now() {
+                    continue;
+                }
+
+                bet.locked = true;
+                state.publish(Some(&*bet)).await;
+                sender
+                    .privmsg(format!("Bets are locked for {}!", bet.title))
+                    .await;
+            }
+        };
+
+        tasks.push(Box::pin(future));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::payout;
+
+    #[test]
+    fn test_payout() {
+        // 300 staked in total, 100 of it on the winning outcome.
+        assert_eq!(payout(300, 100, 100), 300);
+        assert_eq!(payout(300, 100, 25), 75);
+        assert_eq!(payout(300, 100, 75), 225);
+        // Rounds down.
+        assert_eq!(payout(100, 30, 10), 33);
+        assert_eq!(payout(i64::MAX, i64::MAX, i64::MAX), i64::MAX);
+    }
+}
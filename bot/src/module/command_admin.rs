@@ -1,7 +1,9 @@
 use anyhow::Result;
 use async_trait::async_trait;
+use auth::ScopeOrRole;
 use chat::command;
 use chat::module;
+use common::Duration;
 
 pub(crate) struct Handler {
     pub(crate) enabled: settings::Var<bool>,
@@ -61,10 +63,74 @@ impl command::Handler for Handler {
 
                 chat::respond!(ctx, "Edited pattern for command.");
             }
+            Some("cooldown") => {
+                ctx.check_scope(auth::Scope::CommandEdit).await?;
+
+                let name = ctx.next_str("<name> [duration]")?;
+                let cooldown = ctx.next_parse_optional::<Duration>()?;
+
+                let Some(mut restrictions) = restrictions(ctx, &commands, &name).await else {
+                    return Ok(());
+                };
+
+                restrictions.cooldown = cooldown;
+                commands
+                    .edit_restrictions(ctx.channel(), &name, restrictions)
+                    .await?;
+                chat::respond!(ctx, "Edited cooldown for command.");
+            }
+            Some("user-cooldown") => {
+                ctx.check_scope(auth::Scope::CommandEdit).await?;
+
+                let name = ctx.next_str("<name> [duration]")?;
+                let cooldown = ctx.next_parse_optional::<Duration>()?;
+
+                let Some(mut restrictions) = restrictions(ctx, &commands, &name).await else {
+                    return Ok(());
+                };
+
+                restrictions.user_cooldown = cooldown;
+                commands
+                    .edit_restrictions(ctx.channel(), &name, restrictions)
+                    .await?;
+                chat::respond!(ctx, "Edited user cooldown for command.");
+            }
+            Some("cost") => {
+                ctx.check_scope(auth::Scope::CommandEdit).await?;
+
+                let name = ctx.next_str("<name> [amount]")?;
+                let cost = ctx.next_parse_optional::<u32>()?;
+
+                let Some(mut restrictions) = restrictions(ctx, &commands, &name).await else {
+                    return Ok(());
+                };
+
+                restrictions.cost = cost.filter(|cost| *cost > 0).map(i64::from);
+                commands
+                    .edit_restrictions(ctx.channel(), &name, restrictions)
+                    .await?;
+                chat::respond!(ctx, "Edited cost for command.");
+            }
+            Some("requires") => {
+                ctx.check_scope(auth::Scope::CommandEdit).await?;
+
+                let name = ctx.next_str("<name> [scope|@role]")?;
+                let requires = ctx.next_parse_optional::<ScopeOrRole>()?;
+
+                let Some(mut restrictions) = restrictions(ctx, &commands, &name).await else {
+                    return Ok(());
+                };
+
+                restrictions.requires = requires.map(|requires| requires.to_string());
+                commands
+                    .edit_restrictions(ctx.channel(), &name, restrictions)
+                    .await?;
+                chat::respond!(ctx, "Edited requirement for command.");
+            }
             None | Some(..) => {
                 chat::respond!(
                     ctx,
-                    "Expected: show, list, edit, delete, enable, disable, or group."
+                    "Expected: show, list, edit, pattern, cooldown, user-cooldown, cost, requires, delete, enable, disable, or group."
                 );
             }
         }
@@ -73,6 +139,22 @@ impl command::Handler for Handler {
     }
 }
 
+/// Get the current restrictions of the given command, responding if it
+/// doesn't exist.
+async fn restrictions(
+    ctx: &command::Context<'_>,
+    commands: &db::Commands,
+    name: &str,
+) -> Option<db::commands::Restrictions> {
+    match commands.get(ctx.channel(), name).await {
+        Some(command) => Some(command.restrictions.clone()),
+        None => {
+            chat::respond!(ctx, "No such command: `{}`", name);
+            None
+        }
+    }
+}
+
 pub(crate) struct Module;
 
 #[async_trait]
@@ -81,6 +163,10 @@ impl chat::Module for Module {
         "command"
     }
 
+    fn per_channel(&self) -> bool {
+        true
+    }
+
     async fn hook(
         &self,
         module::HookContext {
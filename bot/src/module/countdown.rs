@@ -1,32 +1,50 @@
+use std::collections::{BTreeMap, HashSet};
 use std::fs;
-use std::path::PathBuf;
-use std::pin::pin;
-use std::pin::Pin;
-use std::task::{Context, Poll};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time;
 
 use anyhow::Result;
-use async_fuse::Fuse;
 use async_trait::async_trait;
 use chat::command;
 use chat::module;
-use common::stream::Stream;
 use common::Duration;
-use serde::Serialize;
-use tokio::sync::mpsc;
-
-enum Event {
-    /// Set the countdown.
-    Set(Duration, template::Template),
-    /// Clear the countdown.
-    Clear,
-}
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Mutex};
+
+/// The name of the countdown used when no name is specified.
+const DEFAULT: &str = "default";
+
+/// Countdowns by name.
+type Countdowns = Arc<Mutex<BTreeMap<String, Countdown>>>;
 
 pub(crate) struct Handler {
-    sender: mpsc::UnboundedSender<Event>,
+    countdowns: Countdowns,
+    /// Notify the background task that countdowns have been modified.
+    refresh: mpsc::UnboundedSender<()>,
     enabled: settings::Var<bool>,
 }
 
+impl Handler {
+    /// Parse the name of a countdown.
+    fn name(ctx: &mut command::Context<'_>) -> Result<String> {
+        parse_name(&ctx.next_str("<name>")?)
+    }
+
+    /// Modify the countdown with the given name.
+    async fn modify<T>(&self, name: &str, f: impl FnOnce(&mut Countdown) -> T) -> Result<T> {
+        let mut countdowns = self.countdowns.lock().await;
+
+        let Some(countdown) = countdowns.get_mut(name) else {
+            chat::respond_bail!("No countdown named `{}`", name);
+        };
+
+        let out = f(countdown);
+        let _ = self.refresh.send(());
+        Ok(out)
+    }
+}
+
 #[async_trait]
 impl command::Handler for Handler {
     fn scope(&self) -> Option<auth::Scope> {
@@ -40,32 +58,122 @@ impl command::Handler for Handler {
 
         match ctx.next().as_deref() {
             Some("set") => {
-                let duration = ctx.next_parse("<duration> <template>")?;
-                let template = ctx.rest_parse("<duration> <template>")?;
+                let first = ctx.next_str("<name> <duration> <template>")?;
+
+                // Support the form without a name, which sets the default
+                // countdown.
+                let (name, duration) = match str::parse::<Duration>(&first) {
+                    Ok(duration) => (String::from(DEFAULT), duration),
+                    Err(..) => (
+                        parse_name(&first)?,
+                        ctx.next_parse("<name> <duration> <template>")?,
+                    ),
+                };
+
+                let template = ctx.rest_parse("<name> <duration> <template>")?;
+
+                let countdown = Countdown {
+                    duration,
+                    elapsed: Default::default(),
+                    template,
+                    paused: false,
+                    finish: None,
+                };
+
+                self.countdowns.lock().await.insert(name.clone(), countdown);
+
+                let _ = self.refresh.send(());
+                chat::respond!(ctx, "Countdown `{}` set!", name);
+            }
+            Some("pause") => {
+                let name = Self::name(ctx)?;
+
+                if !self
+                    .modify(&name, |c| !std::mem::replace(&mut c.paused, true))
+                    .await?
+                {
+                    chat::respond_bail!("Countdown `{}` is already paused", name);
+                }
 
-                match self.sender.send(Event::Set(duration, template)) {
-                    Ok(()) => {
-                        chat::respond!(ctx, "Countdown set!");
+                chat::respond!(ctx, "Countdown `{}` paused!", name);
+            }
+            Some("resume") => {
+                let name = Self::name(ctx)?;
+
+                if !self
+                    .modify(&name, |c| std::mem::replace(&mut c.paused, false))
+                    .await?
+                {
+                    chat::respond_bail!("Countdown `{}` is not paused", name);
+                }
+
+                chat::respond!(ctx, "Countdown `{}` resumed!", name);
+            }
+            Some("finish") => {
+                let name = Self::name(ctx)?;
+                let message = ctx.rest().trim().to_string();
+                let message = if message.is_empty() {
+                    None
+                } else {
+                    Some(message)
+                };
+                let cleared = message.is_none();
+
+                self.modify(&name, move |c| c.finish = message).await?;
+
+                if cleared {
+                    chat::respond!(ctx, "Cleared finish message of countdown `{}`!", name);
+                } else {
+                    chat::respond!(ctx, "Set finish message of countdown `{}`!", name);
+                }
+            }
+            Some("clear") => {
+                let mut countdowns = self.countdowns.lock().await;
+
+                match ctx.next() {
+                    Some(name) => {
+                        let name = name.to_lowercase();
+
+                        if countdowns.remove(&name).is_none() {
+                            chat::respond_bail!("No countdown named `{}`", name);
+                        }
+
+                        chat::respond!(ctx, "Countdown `{}` cleared!", name);
                     }
-                    Err(_) => {
-                        chat::respond!(ctx, "Could not set countdown :(");
-                        return Ok(());
+                    None => {
+                        countdowns.clear();
+                        chat::respond!(ctx, "All countdowns cleared!");
                     }
                 }
+
+                let _ = self.refresh.send(());
             }
-            Some("clear") => match self.sender.send(Event::Clear) {
-                Ok(()) => {
-                    chat::respond!(ctx, "Countdown cleared!");
-                }
-                Err(_) => {
-                    chat::respond!(ctx, "Could not clear countdown :(");
-                    return Ok(());
+            Some("list") => {
+                let countdowns = self.countdowns.lock().await;
+
+                if countdowns.is_empty() {
+                    chat::respond_bail!("No active countdowns");
                 }
-            },
+
+                let list = countdowns
+                    .iter()
+                    .map(|(name, c)| {
+                        let remaining = c.duration.saturating_sub(c.elapsed).as_digital();
+
+                        if c.paused {
+                            format!("{} ({}, paused)", name, remaining)
+                        } else {
+                            format!("{} ({})", name, remaining)
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+                chat::respond!(ctx, "Countdowns: {}.", list.join(", "));
+            }
             _ => {
                 chat::respond!(
                     ctx,
-                    "Expected: !countdown set <duration> <template>, or !countdown clear"
+                    "Expected: !countdown set <name> <duration> <template>, pause <name>, resume <name>, finish <name> [message], clear [name], or list"
                 );
                 return Ok(());
             }
@@ -87,8 +195,10 @@ impl chat::Module for Module {
     async fn hook(
         &self,
         module::HookContext {
+            injector,
             handlers,
             tasks,
+            sender,
             settings,
             ..
         }: module::HookContext<'_, '_>,
@@ -99,64 +209,102 @@ impl chat::Module for Module {
         let enabled = settings::Var::new(enabled);
 
         let (mut path_stream, path) = settings.stream::<PathBuf>("path").optional().await?;
+        let (mut directory_stream, directory) =
+            settings.stream::<PathBuf>("directory").optional().await?;
+        let (mut files_stream, files) = settings.stream("files").or_with(Vec::new()).await?;
+
+        let mut writer = FileWriter {
+            path,
+            directory,
+            files,
+            written: HashSet::new(),
+        };
 
-        let mut writer = FileWriter::default();
-        writer.path = path;
-
-        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let countdowns = Countdowns::default();
+        let (refresh, mut refresh_rx) = mpsc::unbounded_channel();
 
         handlers.insert(
             "countdown",
             Handler {
-                sender,
+                countdowns: countdowns.clone(),
+                refresh,
                 enabled: enabled.clone(),
             },
         );
 
+        let (mut countdown_bus_stream, mut countdown_bus) =
+            injector.stream::<bus::Bus<bus::Countdown>>().await;
+
+        let sender = sender.clone();
+
         let future = async move {
-            let mut timer = pin!(Fuse::empty());
+            let mut interval = tokio::time::interval(time::Duration::from_secs(1));
 
             loop {
                 tokio::select! {
+                    update = countdown_bus_stream.recv() => {
+                        countdown_bus = update;
+                    }
                     update = path_stream.recv() => {
+                        writer.clear_all();
                         writer.path = update;
                     }
+                    update = directory_stream.recv() => {
+                        writer.clear_all();
+                        writer.directory = update;
+                    }
+                    update = files_stream.recv() => {
+                        writer.clear_all();
+                        writer.files = update;
+                    }
                     update = enabled_stream.recv() => {
                         if !update {
-                            timer.set(Fuse::empty());
-                            writer.clear_log();
+                            let mut countdowns = countdowns.lock().await;
+                            countdowns.clear();
+                            writer.update(&countdowns);
+                            publish(countdown_bus.as_ref(), &countdowns).await;
                         }
 
                         *enabled.write().await = update;
                     }
-                    out = timer.as_mut().poll_stream(Stream::poll_next) => {
-                        match out {
-                            Some(()) => if let Some(timer) = timer.as_inner_ref() {
-                                writer.write_log(timer);
-                            },
-                            None => {
-                                writer.clear_log();
-                            },
+                    _ = interval.tick() => {
+                        let mut countdowns = countdowns.lock().await;
+
+                        if countdowns.is_empty() {
+                            continue;
                         }
-                    },
-                    Some(event) = receiver.recv() => {
-                        match event {
-                            Event::Set(duration, template) => {
-                                let t = Timer {
-                                    duration,
-                                    elapsed: Default::default(),
-                                    interval: tokio::time::interval(time::Duration::from_secs(1)),
-                                };
-
-                                writer.template = Some(template);
-                                writer.write_log(&t);
-                                timer.set(Fuse::new(t));
+
+                        let mut finished = Vec::new();
+
+                        for (name, countdown) in countdowns.iter_mut() {
+                            if countdown.paused {
+                                continue;
+                            }
+
+                            countdown.elapsed += Duration::seconds(1);
+
+                            if countdown.elapsed >= countdown.duration {
+                                finished.push(name.clone());
                             }
-                            Event::Clear => {
-                                timer.set(Fuse::empty());
-                                writer.clear_log();
+                        }
+
+                        for name in finished {
+                            let Some(countdown) = countdowns.remove(&name) else {
+                                continue;
+                            };
+
+                            if let Some(finish) = countdown.finish {
+                                sender.privmsg(finish).await;
                             }
                         }
+
+                        writer.update(&countdowns);
+                        publish(countdown_bus.as_ref(), &countdowns).await;
+                    }
+                    Some(()) = refresh_rx.recv() => {
+                        let countdowns = countdowns.lock().await;
+                        writer.update(&countdowns);
+                        publish(countdown_bus.as_ref(), &countdowns).await;
                     }
                 }
             }
@@ -167,103 +315,222 @@ impl chat::Module for Module {
     }
 }
 
-#[derive(Default)]
-struct FileWriter {
-    path: Option<PathBuf>,
-    template: Option<template::Template>,
+/// Publish the state of all countdowns to overlays.
+async fn publish(
+    countdown_bus: Option<&bus::Bus<bus::Countdown>>,
+    countdowns: &BTreeMap<String, Countdown>,
+) {
+    let Some(countdown_bus) = countdown_bus else {
+        return;
+    };
+
+    let mut states = Vec::with_capacity(countdowns.len());
+
+    for (name, countdown) in countdowns {
+        let text = match countdown.render(name) {
+            Ok(text) => text,
+            Err(e) => {
+                common::log_error!(e, "Failed to render countdown `{}`", name);
+                continue;
+            }
+        };
+
+        states.push(bus::CountdownState {
+            name: name.clone(),
+            text,
+            duration: countdown.duration.num_seconds(),
+            elapsed: countdown.elapsed.num_seconds(),
+            remaining: countdown
+                .duration
+                .saturating_sub(countdown.elapsed)
+                .num_seconds(),
+            paused: countdown.paused,
+        });
+    }
+
+    countdown_bus
+        .send(bus::Countdown::Countdowns { countdowns: states })
+        .await;
 }
 
-impl FileWriter {
-    fn write(&self, timer: &Timer) -> Result<()> {
-        let path = match &self.path {
-            Some(path) => path,
-            None => return Ok(()),
-        };
+/// Parse and validate the name of a countdown.
+///
+/// Names are used as file names, so they are restricted.
+fn parse_name(name: &str) -> Result<String> {
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_');
+
+    if !valid {
+        chat::respond_bail!(
+            "Countdown names may only contain letters, numbers, dashes and underscores"
+        );
+    }
 
-        let template = match &self.template {
-            Some(template) => template,
-            None => return Ok(()),
-        };
+    Ok(name.to_lowercase())
+}
 
-        tracing::trace!("Writing to log: {}", path.display());
+/// A file a single countdown is written to, as configured in settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CountdownFile {
+    name: String,
+    path: PathBuf,
+}
 
-        let mut f = fs::File::create(path)?;
-        let remaining = timer.duration.saturating_sub(timer.elapsed);
-        let remaining = remaining.as_digital();
-        let elapsed = timer.elapsed.as_digital();
-        let duration = timer.duration.as_digital();
+struct FileWriter {
+    /// Path to write the default countdown to.
+    path: Option<PathBuf>,
+    /// Directory to write every named countdown to.
+    directory: Option<PathBuf>,
+    /// Files to write individual countdowns to.
+    files: Vec<CountdownFile>,
+    /// Files which have been written and need to be cleared.
+    written: HashSet<PathBuf>,
+}
 
-        template.render(
-            &mut f,
-            Data {
-                remaining,
-                elapsed,
-                duration,
-            },
-        )?;
+impl FileWriter {
+    /// Get all the paths the given countdown should be written to.
+    fn paths(&self, name: &str) -> Vec<PathBuf> {
+        let mut paths = Vec::new();
 
-        return Ok(());
+        if name == DEFAULT {
+            if let Some(path) = &self.path {
+                paths.push(path.clone());
+            }
+        }
 
-        #[derive(Serialize)]
-        struct Data {
-            remaining: String,
-            elapsed: String,
-            duration: String,
+        if let Some(directory) = &self.directory {
+            paths.push(directory.join(format!("{}.txt", name)));
+        }
+
+        for file in &self.files {
+            if file.name.to_lowercase() == name {
+                paths.push(file.path.clone());
+            }
         }
+
+        paths
     }
 
-    fn clear(&self) -> Result<()> {
-        let path = match &self.path {
-            Some(path) => path,
-            None => return Ok(()),
-        };
+    /// Write all countdowns, and clear files for countdowns which are no
+    /// longer active.
+    fn update(&mut self, countdowns: &BTreeMap<String, Countdown>) {
+        let mut written = HashSet::new();
 
-        tracing::trace!("Clearing log: {}", path.display());
+        for (name, countdown) in countdowns {
+            for path in self.paths(name) {
+                if let Err(e) = write(&path, name, countdown) {
+                    common::log_error!(e, "Failed to write: {}", path.display());
+                }
 
-        if !path.is_file() {
-            return Ok(());
+                written.insert(path);
+            }
         }
 
-        fs::remove_file(path)?;
-        Ok(())
+        for path in self.written.difference(&written) {
+            clear_log(path);
+        }
+
+        self.written = written;
     }
 
-    /// Attempt to write an update and log on errors.
-    fn write_log(&self, timer: &Timer) {
-        if let Err(e) = self.write(timer) {
-            common::log_error!(e, "Failed to write");
+    /// Clear all written files.
+    fn clear_all(&mut self) {
+        for path in self.written.drain() {
+            clear_log(&path);
         }
     }
+}
 
-    /// Attempt to clear the file and log on errors.
-    fn clear_log(&self) {
-        if let Err(e) = self.clear() {
-            common::log_error!(e, "Failed to clear");
-        }
+fn write(path: &Path, name: &str, countdown: &Countdown) -> Result<()> {
+    tracing::trace!("Writing to log: {}", path.display());
+    fs::write(path, countdown.render(name)?)?;
+    Ok(())
+}
+
+fn clear(path: &Path) -> Result<()> {
+    tracing::trace!("Clearing log: {}", path.display());
+
+    if !path.is_file() {
+        return Ok(());
     }
+
+    fs::remove_file(path)?;
+    Ok(())
 }
 
-struct Timer {
+/// Attempt to clear the file and log on errors.
+fn clear_log(path: &Path) {
+    if let Err(e) = clear(path) {
+        common::log_error!(e, "Failed to clear");
+    }
+}
+
+struct Countdown {
     duration: Duration,
     elapsed: Duration,
-    interval: tokio::time::Interval,
+    template: template::Template,
+    paused: bool,
+    /// Message to send to chat when the countdown finishes.
+    finish: Option<String>,
 }
 
-impl Stream for Timer {
-    type Item = ();
+impl Countdown {
+    /// Render the countdown using its template.
+    fn render(&self, name: &str) -> Result<String> {
+        let remaining = self.duration.saturating_sub(self.elapsed);
 
-    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<Option<Self::Item>> {
-        match Pin::new(&mut self.as_mut().interval).poll_tick(cx) {
-            Poll::Pending => return Poll::Pending,
-            Poll::Ready(..) => (),
+        return self.template.render_to_string(Data {
+            name,
+            remaining: remaining.as_digital(),
+            elapsed: self.elapsed.as_digital(),
+            duration: self.duration.as_digital(),
+        });
+
+        #[derive(Serialize)]
+        struct Data<'a> {
+            name: &'a str,
+            remaining: String,
+            elapsed: String,
+            duration: String,
         }
+    }
+}
 
-        self.as_mut().elapsed += Duration::seconds(1);
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    use super::{CountdownFile, FileWriter};
+
+    #[test]
+    fn test_paths() {
+        let writer = FileWriter {
+            path: Some(PathBuf::from("countdown.txt")),
+            directory: Some(PathBuf::from("countdowns")),
+            files: vec![CountdownFile {
+                name: String::from("Break"),
+                path: PathBuf::from("break.txt"),
+            }],
+            written: HashSet::new(),
+        };
 
-        if self.as_ref().elapsed >= self.as_ref().duration {
-            return Poll::Ready(None);
-        }
+        assert_eq!(
+            vec![
+                PathBuf::from("countdown.txt"),
+                PathBuf::from("countdowns").join("default.txt")
+            ],
+            writer.paths("default")
+        );
 
-        Poll::Ready(Some(()))
+        assert_eq!(
+            vec![
+                PathBuf::from("countdowns").join("break.txt"),
+                PathBuf::from("break.txt")
+            ],
+            writer.paths("break")
+        );
     }
 }
@@ -0,0 +1,182 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use common::Channel;
+
+/// Handler for the `!counter` command.
+pub(crate) struct Handler {
+    enabled: settings::Var<bool>,
+    counters: async_injector::Ref<db::Counters>,
+    global_bus: async_injector::Ref<bus::Bus<bus::Global>>,
+}
+
+impl Handler {
+    /// Notify overlays that a counter changed.
+    async fn publish(&self, channel: &Channel, name: &str, count: Option<i64>) {
+        if let Some(global_bus) = self.global_bus.load().await {
+            global_bus
+                .send(bus::Global::Counter {
+                    channel: channel.to_owned(),
+                    name: name.to_lowercase(),
+                    count,
+                })
+                .await;
+        }
+    }
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Counter)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let counters = match self.counters.load().await {
+            Some(counters) => counters,
+            None => return Ok(()),
+        };
+
+        let (name, delta) = match ctx.next().as_deref() {
+            None | Some("list") => {
+                let counters = counters.snapshot(ctx.channel()).await;
+
+                if counters.is_empty() {
+                    chat::respond!(ctx, "There are no counters.");
+                    return Ok(());
+                }
+
+                let counters = counters
+                    .iter()
+                    .map(|(name, count)| format!("{} = {}", name, count))
+                    .collect::<Vec<_>>();
+
+                chat::respond!(ctx, "Counters: {}.", counters.join(", "));
+                return Ok(());
+            }
+            Some("inc") => {
+                ctx.check_scope(auth::Scope::CounterEdit).await?;
+                let name = counter_name(ctx, "<name> [amount]")?;
+                let amount = ctx.next_parse_optional::<i64>()?.unwrap_or(1);
+                (name, amount)
+            }
+            Some("dec") => {
+                ctx.check_scope(auth::Scope::CounterEdit).await?;
+                let name = counter_name(ctx, "<name> [amount]")?;
+                let amount = ctx.next_parse_optional::<i64>()?.unwrap_or(1);
+                (name, amount.saturating_neg())
+            }
+            Some("set") => {
+                ctx.check_scope(auth::Scope::CounterEdit).await?;
+                let name = counter_name(ctx, "<name> <value>")?;
+                let count = ctx.next_parse("<name> <value>")?;
+                counters.set(ctx.channel(), &name, count).await?;
+                self.publish(ctx.channel(), &name, Some(count)).await;
+                chat::respond!(ctx, "{} = {}", name, count);
+                return Ok(());
+            }
+            Some("reset") => {
+                ctx.check_scope(auth::Scope::CounterEdit).await?;
+                let name = counter_name(ctx, "<name>")?;
+                counters.set(ctx.channel(), &name, 0).await?;
+                self.publish(ctx.channel(), &name, Some(0)).await;
+                chat::respond!(ctx, "{} = 0", name);
+                return Ok(());
+            }
+            Some("delete") => {
+                ctx.check_scope(auth::Scope::CounterEdit).await?;
+                let name = ctx.next_str("<name>")?;
+
+                if !counters.delete(ctx.channel(), &name).await? {
+                    chat::respond!(ctx, "No counter named `{}`.", name);
+                    return Ok(());
+                }
+
+                self.publish(ctx.channel(), &name, None).await;
+                chat::respond!(ctx, "Deleted counter `{}`.", name);
+                return Ok(());
+            }
+            Some(name) => {
+                let name = name.to_string();
+
+                // `!counter deaths +1` is a shorthand for `!counter inc deaths`.
+                let Some(delta) = ctx.next_parse_optional::<i64>()? else {
+                    match counters.get(ctx.channel(), &name).await {
+                        Some(count) => chat::respond!(ctx, "{} = {}", name, count),
+                        None => chat::respond!(ctx, "No counter named `{}`.", name),
+                    }
+
+                    return Ok(());
+                };
+
+                ctx.check_scope(auth::Scope::CounterEdit).await?;
+
+                if !db::counters::is_valid_name(&name) {
+                    chat::respond_bail!(
+                        "Counter names may only contain letters, digits, and underscores"
+                    );
+                }
+
+                (name, delta)
+            }
+        };
+
+        let count = counters.add(ctx.channel(), &name, delta).await?;
+        self.publish(ctx.channel(), &name, Some(count)).await;
+        chat::respond!(ctx, "{} = {}", name, count);
+        Ok(())
+    }
+}
+
+/// Parse the name of a counter which is about to be modified.
+fn counter_name(ctx: &mut command::Context<'_>, usage: &str) -> Result<String> {
+    let name = ctx.next_str(usage)?;
+
+    if !db::counters::is_valid_name(&name) {
+        chat::respond_bail!("Counter names may only contain letters, digits, and underscores");
+    }
+
+    Ok(name)
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "counter"
+    }
+
+    fn per_channel(&self) -> bool {
+        true
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("counter");
+
+        handlers.insert(
+            "counter",
+            Handler {
+                enabled: settings.var("enabled", true).await?,
+                counters: injector.var().await,
+                global_bus: injector.var().await,
+            },
+        );
+
+        Ok(())
+    }
+}
@@ -69,6 +69,10 @@ impl chat::Module for Module {
         "8ball"
     }
 
+    fn per_channel(&self) -> bool {
+        true
+    }
+
     /// Set up command handlers for this module.
     async fn hook(
         &self,
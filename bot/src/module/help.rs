@@ -48,6 +48,10 @@ impl chat::Module for Module {
         "help"
     }
 
+    fn per_channel(&self) -> bool {
+        true
+    }
+
     async fn hook(
         &self,
         module::HookContext {
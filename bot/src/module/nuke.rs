@@ -0,0 +1,466 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use api::twitch::moderation::ModerationError;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use common::{Channel, Duration};
+use db::moderation_log::{Action, Entry};
+
+const USAGE: &str = "Usage: !nuke <phrase|/regex/> [lookback] [timeout]";
+
+/// Handler for the `!nuke` command.
+pub(crate) struct Nuke {
+    enabled: settings::Var<bool>,
+    lookback: settings::Var<Duration>,
+    message_log: async_injector::Ref<messagelog::MessageLog>,
+}
+
+#[async_trait]
+impl command::Handler for Nuke {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Nuke)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let Some((pattern, rest)) = parse_pattern(ctx.rest()) else {
+            chat::respond_bail!(USAGE);
+        };
+
+        let pattern = pattern.compile()?;
+        let mut args = rest.split_whitespace();
+
+        let lookback = match args.next() {
+            Some(arg) => parse_duration(arg)?,
+            None => self.lookback.load().await,
+        };
+
+        let timeout = match args.next() {
+            Some(arg) => Some(parse_duration(arg)?),
+            None => None,
+        };
+
+        let Some(message_log) = self.message_log.load().await else {
+            chat::respond_bail!("No chat log available");
+        };
+
+        let targets = find_targets(ctx, &message_log, &pattern, lookback).await;
+
+        if targets.is_empty() {
+            chat::respond!(ctx, "No recent messages matched");
+            return Ok(());
+        }
+
+        let reason = match ctx.user.name() {
+            Some(name) => format!("nuked by {}", name),
+            None => String::from("nuked"),
+        };
+
+        let mut users = 0;
+        let mut messages = 0;
+
+        for target in targets.values() {
+            let result = match timeout {
+                Some(timeout) => timeout_user(ctx, target, timeout, &reason, pattern.source).await,
+                None => delete_messages(ctx, target, &reason, pattern.source).await,
+            };
+
+            match result {
+                Ok(0) => {}
+                Ok(n) => {
+                    users += 1;
+                    messages += n;
+                }
+                Err(e @ ModerationError::MissingScope(..)) => {
+                    chat::respond_bail!("Failed to nuke: {}", e);
+                }
+                Err(e) => {
+                    common::log_error!(e, "Failed to nuke user: {}", target.login);
+                }
+            }
+        }
+
+        match timeout {
+            Some(timeout) => {
+                chat::respond!(ctx, "Nuked {} user(s), timed out for {}", users, timeout);
+            }
+            None => {
+                chat::respond!(
+                    ctx,
+                    "Nuked {} user(s), deleted {} message(s)",
+                    users,
+                    messages
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// A user hit by a nuke.
+struct Target {
+    user_id: String,
+    login: String,
+    /// Ids and texts of matching messages.
+    messages: Vec<(String, String)>,
+}
+
+/// Find all users who sent a matching message within the lookback window,
+/// skipping moderators and the streamer.
+async fn find_targets(
+    ctx: &command::Context<'_>,
+    message_log: &messagelog::MessageLog,
+    pattern: &Pattern<'_>,
+    lookback: Duration,
+) -> HashMap<String, Target> {
+    let since = chrono::Utc::now() - lookback.as_chrono();
+    let messages = message_log.messages().await;
+
+    collect_targets(
+        messages.iter(),
+        ctx.channel(),
+        ctx.user.streamer_login(),
+        pattern,
+        since,
+    )
+}
+
+/// Collect targets from the given messages, newest last.
+///
+/// Moderators are detected through the badges the message was sent with,
+/// since the bot only keeps track of the moderators of its own channel.
+fn collect_targets<'m, I>(
+    messages: I,
+    channel: &Channel,
+    streamer: &str,
+    pattern: &Pattern<'_>,
+    since: chrono::DateTime<chrono::Utc>,
+) -> HashMap<String, Target>
+where
+    I: DoubleEndedIterator<Item = &'m messagelog::Message>,
+{
+    let mut targets = HashMap::<String, Target>::new();
+
+    for m in messages.rev() {
+        if *m.timestamp() < since {
+            break;
+        }
+
+        let login = m.user().name();
+
+        if m.is_deleted()
+            || m.channel() != channel
+            || login == streamer
+            || m.user().is_moderator()
+            || !pattern.is_match(m.text())
+        {
+            continue;
+        }
+
+        let target = targets
+            .entry(m.user().user_id().to_owned())
+            .or_insert_with(|| Target {
+                user_id: m.user().user_id().to_owned(),
+                login: login.to_owned(),
+                messages: Vec::new(),
+            });
+
+        target
+            .messages
+            .push((m.id().to_owned(), m.text().to_owned()));
+    }
+
+    targets
+}
+
+/// Time out a single user, returning the number of their messages which were
+/// removed.
+async fn timeout_user(
+    ctx: &command::Context<'_>,
+    target: &Target,
+    timeout: Duration,
+    reason: &str,
+    source: &str,
+) -> Result<usize, ModerationError> {
+    ctx.moderation()
+        .timeout(&target.user_id, timeout.as_std(), Some(reason))
+        .await?;
+
+    let (_, message) = &target.messages[0];
+
+    let mut entry = Entry::new(ctx.channel(), true, Action::Timeout);
+    entry.user = Some(&target.login);
+    entry.duration = Some(timeout.num_seconds());
+    entry.reason = Some(reason);
+    entry.source = Some(source);
+    entry.message = Some(message);
+    ctx.log_moderation(entry).await;
+
+    Ok(target.messages.len())
+}
+
+/// Delete all matching messages by a single user, returning how many were
+/// deleted.
+async fn delete_messages(
+    ctx: &command::Context<'_>,
+    target: &Target,
+    reason: &str,
+    source: &str,
+) -> Result<usize, ModerationError> {
+    let mut count = 0;
+
+    for (id, message) in &target.messages {
+        ctx.moderation().delete_message(id).await?;
+        count += 1;
+
+        let mut entry = Entry::new(ctx.channel(), true, Action::Delete);
+        entry.user = Some(&target.login);
+        entry.reason = Some(reason);
+        entry.source = Some(source);
+        entry.message = Some(message);
+        entry.message_id = Some(id);
+        ctx.log_moderation(entry).await;
+    }
+
+    Ok(count)
+}
+
+fn parse_duration(arg: &str) -> Result<Duration> {
+    match str::parse::<Duration>(arg) {
+        Ok(duration) => Ok(duration),
+        Err(e) => {
+            chat::respond_bail!("Bad duration `{}`: {}", arg, e);
+        }
+    }
+}
+
+/// A pattern to nuke, as written by the user.
+#[derive(Debug, PartialEq, Eq)]
+enum PatternSource<'a> {
+    /// A case-insensitive phrase.
+    Phrase(&'a str),
+    /// A case-insensitive regular expression.
+    Regex(&'a str),
+}
+
+impl<'a> PatternSource<'a> {
+    /// Compile the pattern.
+    fn compile(self) -> Result<Pattern<'a>> {
+        let (source, kind) = match self {
+            PatternSource::Phrase(phrase) => (phrase, PatternKind::Phrase(phrase.to_lowercase())),
+            PatternSource::Regex(regex) => {
+                let compiled = match regex::RegexBuilder::new(regex)
+                    .case_insensitive(true)
+                    .build()
+                {
+                    Ok(compiled) => compiled,
+                    Err(e) => {
+                        chat::respond_bail!("Bad regex: {}", e);
+                    }
+                };
+
+                (regex, PatternKind::Regex(compiled))
+            }
+        };
+
+        Ok(Pattern { source, kind })
+    }
+}
+
+/// A compiled pattern to nuke.
+struct Pattern<'a> {
+    source: &'a str,
+    kind: PatternKind,
+}
+
+enum PatternKind {
+    Phrase(String),
+    Regex(regex::Regex),
+}
+
+impl Pattern<'_> {
+    /// Test if the pattern matches the given message.
+    fn is_match(&self, text: &str) -> bool {
+        match &self.kind {
+            PatternKind::Phrase(phrase) => text.to_lowercase().contains(phrase.as_str()),
+            PatternKind::Regex(regex) => regex.is_match(text),
+        }
+    }
+}
+
+/// Parse the pattern at the start of the arguments, returning it and the
+/// remaining arguments.
+///
+/// The pattern is either `/regex/`, a `"quoted phrase"`, or a single word.
+fn parse_pattern(rest: &str) -> Option<(PatternSource<'_>, &str)> {
+    let rest = rest.trim_start();
+
+    if let Some(tail) = rest.strip_prefix('/') {
+        let mut escaped = false;
+
+        for (i, c) in tail.char_indices() {
+            match c {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                '/' if i > 0 => return Some((PatternSource::Regex(&tail[..i]), &tail[i + 1..])),
+                _ => {}
+            }
+        }
+    }
+
+    if let Some(tail) = rest.strip_prefix('"') {
+        if let Some((phrase, rest)) = tail.split_once('"') {
+            if !phrase.is_empty() {
+                return Some((PatternSource::Phrase(phrase), rest));
+            }
+        }
+    }
+
+    let (word, rest) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+
+    if word.is_empty() {
+        return None;
+    }
+
+    Some((PatternSource::Phrase(word), rest))
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "nuke"
+    }
+
+    fn per_channel(&self) -> bool {
+        true
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("nuke");
+
+        handlers.insert(
+            "nuke",
+            Nuke {
+                enabled: settings.var("enabled", true).await?,
+                lookback: settings.var("lookback", Duration::seconds(60)).await?,
+                message_log: injector.var().await,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collect_targets, parse_pattern, PatternSource};
+    use common::irc::Tags;
+    use common::Channel;
+
+    #[test]
+    fn test_parse_pattern() {
+        assert_eq!(
+            Some((PatternSource::Phrase("bigfollows"), " 5m 10m")),
+            parse_pattern("bigfollows 5m 10m")
+        );
+
+        assert_eq!(
+            Some((PatternSource::Phrase("buy followers"), " 5m")),
+            parse_pattern("\"buy followers\" 5m")
+        );
+
+        assert_eq!(
+            Some((PatternSource::Regex(r"big\s*follows\/com"), " 5m")),
+            parse_pattern(r"/big\s*follows\/com/ 5m")
+        );
+
+        assert_eq!(
+            Some((PatternSource::Phrase("/unterminated"), "")),
+            parse_pattern("/unterminated")
+        );
+
+        assert_eq!(None, parse_pattern("   "));
+    }
+
+    #[test]
+    fn test_collect_targets_in_other_channel() -> anyhow::Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+
+        runtime.block_on(async {
+            let log = messagelog::MessageLog::builder().build();
+            let channel = Channel::new("#costreamer");
+
+            let tags = |id: &str, user_id: &str, badges: &str| Tags {
+                id: Some(id.to_owned()),
+                user_id: Some(user_id.to_owned()),
+                display_name: Some(user_id.to_owned()),
+                badges: Some(badges.to_owned()),
+                ..Tags::default()
+            };
+
+            log.push_back(
+                channel,
+                &tags("1", "spammer", "subscriber/1"),
+                "spammer",
+                "bigfollows com",
+                None,
+            )
+            .await;
+            log.push_back(
+                channel,
+                &tags("2", "moderator", "moderator/1"),
+                "moderator",
+                "don't click bigfollows",
+                None,
+            )
+            .await;
+            log.push_back(
+                channel,
+                &tags("3", "costreamer", "broadcaster/1"),
+                "costreamer",
+                "bigfollows is a scam",
+                None,
+            )
+            .await;
+            log.push_back(
+                Channel::new("#streamer"),
+                &tags("4", "other", ""),
+                "other",
+                "bigfollows",
+                None,
+            )
+            .await;
+
+            let pattern = PatternSource::Phrase("bigfollows").compile()?;
+            let since = chrono::Utc::now() - chrono::Duration::minutes(1);
+            let messages = log.messages().await;
+            let targets = collect_targets(messages.iter(), channel, "streamer", &pattern, since);
+
+            assert_eq!(1, targets.len());
+            let target = &targets["spammer"];
+            assert_eq!("spammer", target.login);
+            assert_eq!(
+                vec![(String::from("1"), String::from("bigfollows com"))],
+                target.messages
+            );
+            Ok(())
+        })
+    }
+}
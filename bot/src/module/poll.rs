@@ -3,13 +3,101 @@ use async_trait::async_trait;
 use chat::command;
 use chat::module;
 use chrono::{DateTime, Utc};
-use std::collections::{HashMap, HashSet};
+use common::{Duration, OwnedChannel};
+use db::polls::Tally;
+use std::collections::HashMap;
+use std::sync::Arc;
 use tokio::sync::Mutex;
 
 /// Handler for the !poll command.
 pub(crate) struct Poll {
     enabled: settings::Var<bool>,
-    polls: Mutex<HashMap<command::HookId, ActivePoll>>,
+    weighted: settings::Var<bool>,
+    polls: Arc<Polls>,
+    currency: async_injector::Ref<currency::Currency>,
+    global_bus: async_injector::Ref<bus::Bus<bus::Global>>,
+}
+
+impl Poll {
+    /// Start a new poll.
+    async fn run(&self, ctx: &mut command::Context<'_>, duration: Option<Duration>) -> Result<()> {
+        let question = ctx.next_str("<question> <options...>")?;
+
+        let mut options = Vec::<PollOption>::new();
+
+        for option in ctx.by_ref() {
+            let (key, description) = match option.find('=') {
+                Some(i) => {
+                    let (keyword, description) = option.split_at(i);
+                    (keyword.to_string(), Some(description[1..].to_string()))
+                }
+                None => (option, None),
+            };
+
+            let key = key.to_lowercase();
+
+            if options.iter().any(|o| o.key == key) {
+                chat::respond_bail!("Option `{}` was specified twice", key);
+            }
+
+            options.push(PollOption { key, description });
+        }
+
+        if options.is_empty() {
+            chat::respond_bail!("Expected <question> <options...>");
+        }
+
+        let currency = if self.weighted.load().await {
+            match self.currency.load().await {
+                Some(currency) => Some(currency),
+                None => {
+                    chat::respond_bail!("Weighted polls require a currency to be configured");
+                }
+            }
+        } else {
+            None
+        };
+
+        let poll = ActivePoll {
+            shared: Arc::new(Shared {
+                channel: ctx.channel().to_owned(),
+                question: question.clone(),
+                created_at: Utc::now(),
+                closes_at: duration.map(|d| Utc::now() + d.as_chrono()),
+                options,
+                currency,
+                global_bus: self.global_bus.load().await,
+                hooks: ctx.hooks(),
+            }),
+            inner: Arc::new(Mutex::new(Inner {
+                id: None,
+                closed: false,
+                votes: HashMap::new(),
+            })),
+        };
+
+        let hook_id = ctx.insert_hook(poll.clone()).await;
+        poll.inner.lock().await.id = Some(hook_id);
+        self.polls.polls.lock().await.insert(hook_id, poll.clone());
+        poll.publish().await;
+
+        match duration {
+            Some(duration) => {
+                chat::respond!(
+                    ctx,
+                    "Started poll `{}` (id: {}), closing in {}",
+                    question,
+                    hook_id,
+                    duration
+                );
+            }
+            None => {
+                chat::respond!(ctx, "Started poll `{}` (id: {})", question, hook_id);
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -25,142 +113,329 @@ impl command::Handler for Poll {
 
         match ctx.next().as_deref() {
             Some("run") => {
-                let question = ctx.next_str("<question> <options...>")?;
-
-                let mut options = HashMap::new();
-
-                for option in ctx.by_ref() {
-                    let (key, description) = match option.find('=') {
-                        Some(i) => {
-                            let (keyword, description) = option.split_at(i);
-                            (keyword.to_string(), Some(description[1..].to_string()))
-                        }
-                        None => (option, None),
-                    };
-
-                    options.insert(key.to_lowercase(), description);
-                }
-
-                let poll = ActivePoll {
-                    question: question.clone(),
-                    created_at: Utc::now(),
-                    options,
-                    inner: settings::Var::new(Inner {
-                        voted: Default::default(),
-                        votes: Default::default(),
-                    }),
-                };
-
-                let hook_id = ctx.insert_hook(poll.clone()).await;
-                self.polls.lock().await.insert(hook_id, poll);
-                ctx.respond(format!("Started poll `{}` (id: {})", question, hook_id))
-                    .await;
+                self.run(ctx, None).await?;
+            }
+            Some("timed") => {
+                let duration = ctx.next_parse("<duration> <question> <options...>")?;
+                self.run(ctx, Some(duration)).await?;
             }
             Some("close") => {
-                let mut polls = self.polls.lock().await;
-
                 let id = match ctx.next() {
                     Some(id) => str::parse::<command::HookId>(&id)
                         .map_err(|_| chat::respond_err!("Bad id `{}`", id))?,
-                    None => {
-                        *polls
-                            .iter()
-                            .max_by_key(|e| e.1.created_at)
-                            .ok_or(chat::respond_err!("No running polls"))?
-                            .0
-                    }
+                    None => self
+                        .polls
+                        .latest()
+                        .await
+                        .ok_or(chat::respond_err!("No running polls"))?,
                 };
 
-                let poll = polls
-                    .remove(&id)
+                let results = self
+                    .polls
+                    .close(id)
+                    .await?
                     .ok_or(chat::respond_err!("No poll with id `{}`!", id))?;
 
-                ctx.remove_hook(id).await;
-                let results = poll.close().await;
+                chat::respond!(ctx, "{}", results);
+            }
+            Some("last") => {
+                let Some(polls) = self.polls.db.load().await else {
+                    return Ok(());
+                };
 
-                let total = results.iter().map(|(_, c)| c).sum::<u32>();
+                let Some((poll, options)) = polls.latest(ctx.channel()).await? else {
+                    chat::respond_bail!("No polls have been closed yet");
+                };
 
-                let mut formatted = Vec::new();
+                let tallies = options
+                    .into_iter()
+                    .map(|o| Tally {
+                        option: o.option,
+                        description: o.description,
+                        votes: o.votes.max(0) as u32,
+                        weight: o.weight,
+                    })
+                    .collect::<Vec<_>>();
+
+                chat::respond!(
+                    ctx,
+                    "{}",
+                    format_results(&poll.question, &tallies, poll.weighted)
+                );
+            }
+            _ => {
+                ctx.respond("Expected: run, timed, close, or last.").await;
+            }
+        }
 
-                for (key, votes) in results {
-                    let p = common::percentage(votes, total);
+        Ok(())
+    }
+}
 
-                    let votes = match votes {
-                        0 => "no votes".to_string(),
-                        1 => "one vote".to_string(),
-                        n => format!("{} votes", n),
-                    };
+/// Polls which are currently running in a channel.
+struct Polls {
+    polls: Mutex<HashMap<command::HookId, ActivePoll>>,
+    db: async_injector::Ref<db::Polls>,
+}
 
-                    formatted.push(format!("{} = {} ({})", key, votes, p));
-                }
+impl Polls {
+    /// Get the id of the most recently started poll.
+    async fn latest(&self) -> Option<command::HookId> {
+        let polls = self.polls.lock().await;
 
-                chat::respond!(ctx, "{} -> {}.", poll.question, formatted.join(", "));
-            }
-            _ => {
-                ctx.respond("Expected: run, close.").await;
+        polls
+            .iter()
+            .max_by_key(|e| e.1.shared.created_at)
+            .map(|e| *e.0)
+    }
+
+    /// Close the poll with the given id, returning the formatted results.
+    async fn close(&self, id: command::HookId) -> Result<Option<String>> {
+        let Some(poll) = self.polls.lock().await.remove(&id) else {
+            return Ok(None);
+        };
+
+        poll.shared.hooks.remove(id).await;
+        let tallies = poll.close().await;
+        let shared = &poll.shared;
+        let weighted = shared.currency.is_some();
+
+        // NB: the poll is already closed, so a failure to store it shouldn't
+        // stop the results from being announced.
+        if let Some(db) = self.db.load().await {
+            if let Err(e) = db
+                .insert(
+                    &shared.channel,
+                    &shared.question,
+                    shared.created_at,
+                    weighted,
+                    &tallies,
+                )
+                .await
+            {
+                common::log_error!(e, "Failed to store poll results");
             }
         }
 
-        Ok(())
+        Ok(Some(format_results(&shared.question, &tallies, weighted)))
     }
+
+    /// Get the ids of all polls whose timer has run out.
+    async fn expired(&self) -> Vec<command::HookId> {
+        let now = Utc::now();
+        let polls = self.polls.lock().await;
+
+        polls
+            .iter()
+            .filter(|(_, p)| p.shared.closes_at.is_some_and(|at| at <= now))
+            .map(|(id, _)| *id)
+            .collect()
+    }
+}
+
+/// Format the results of a poll for chat.
+fn format_results(question: &str, tallies: &[Tally], weighted: bool) -> String {
+    let mut tallies = tallies.iter().collect::<Vec<_>>();
+
+    let total = if weighted {
+        tallies.sort_by(|a, b| b.weight.cmp(&a.weight));
+        tallies
+            .iter()
+            .fold(0u64, |n, t| n.saturating_add(t.weight.max(0) as u64))
+    } else {
+        tallies.sort_by(|a, b| b.votes.cmp(&a.votes));
+        tallies
+            .iter()
+            .fold(0u64, |n, t| n.saturating_add(u64::from(t.votes)))
+    };
+
+    let mut formatted = Vec::new();
+
+    for tally in tallies {
+        let key = tally.description.as_deref().unwrap_or(&tally.option);
+
+        let votes = match tally.votes {
+            0 => "no votes".to_string(),
+            1 => "one vote".to_string(),
+            n => format!("{} votes", n),
+        };
+
+        if weighted {
+            let p = common::percentage(tally.weight.max(0) as u64, total);
+            formatted.push(format!(
+                "{} = {} weighing {} ({})",
+                key, votes, tally.weight, p
+            ));
+        } else {
+            let p = common::percentage(u64::from(tally.votes), total);
+            formatted.push(format!("{} = {} ({})", key, votes, p));
+        }
+    }
+
+    format!("{} -> {}.", question, formatted.join(", "))
+}
+
+struct PollOption {
+    key: String,
+    description: Option<String>,
+}
+
+struct Vote {
+    /// Index of the option voted for.
+    option: usize,
+    weight: i64,
 }
 
 struct Inner {
-    voted: HashSet<String>,
-    votes: HashMap<String, u32>,
+    /// The id of the hook, once installed.
+    id: Option<command::HookId>,
+    closed: bool,
+    /// Votes by login.
+    votes: HashMap<String, Vote>,
 }
 
-#[derive(Clone)]
-struct ActivePoll {
+/// State of a poll which doesn't change while it's running.
+struct Shared {
+    channel: OwnedChannel,
     question: String,
     created_at: DateTime<Utc>,
-    options: HashMap<String, Option<String>>,
-    inner: settings::Var<Inner>,
+    closes_at: Option<DateTime<Utc>>,
+    options: Vec<PollOption>,
+    /// Currency to weigh votes by, if the poll is weighted.
+    currency: Option<currency::Currency>,
+    global_bus: Option<bus::Bus<bus::Global>>,
+    hooks: command::Hooks,
+}
+
+#[derive(Clone)]
+struct ActivePoll {
+    shared: Arc<Shared>,
+    inner: Arc<Mutex<Inner>>,
 }
 
 impl ActivePoll {
     /// Close the poll.
-    pub(crate) async fn close(&self) -> Vec<(String, u32)> {
-        let inner = self.inner.read().await;
-
-        let mut results = Vec::new();
+    async fn close(&self) -> Vec<Tally> {
+        let mut inner = self.inner.lock().await;
+        inner.closed = true;
+        let tallies = self.tally(&inner);
+        self.publish_with(&inner, &tallies).await;
+        tallies
+    }
 
-        for (o, description) in &self.options {
-            results.push((
-                description.clone().unwrap_or_else(|| o.to_string()),
-                inner.votes.get(o).cloned().unwrap_or_default(),
-            ));
+    /// Count the votes for each option.
+    fn tally(&self, inner: &Inner) -> Vec<Tally> {
+        let mut tallies = self
+            .shared
+            .options
+            .iter()
+            .map(|o| Tally {
+                option: o.key.clone(),
+                description: o.description.clone(),
+                votes: 0,
+                weight: 0,
+            })
+            .collect::<Vec<_>>();
+
+        for vote in inner.votes.values() {
+            if let Some(tally) = tallies.get_mut(vote.option) {
+                tally.votes = tally.votes.saturating_add(1);
+                tally.weight = tally.weight.saturating_add(vote.weight);
+            }
         }
 
-        results.sort_by(|a, b| b.1.cmp(&a.1));
-        results
+        tallies
+    }
+
+    /// Publish the current tally of the poll.
+    async fn publish(&self) {
+        let inner = self.inner.lock().await;
+        let tallies = self.tally(&inner);
+        self.publish_with(&inner, &tallies).await;
+    }
+
+    async fn publish_with(&self, inner: &Inner, tallies: &[Tally]) {
+        let Some(global_bus) = &self.shared.global_bus else {
+            return;
+        };
+
+        let Some(id) = inner.id else {
+            return;
+        };
+
+        let options = tallies
+            .iter()
+            .map(|t| bus::PollOption {
+                key: t.option.clone(),
+                description: t.description.clone(),
+                votes: t.votes,
+                weight: t.weight,
+            })
+            .collect();
+
+        global_bus
+            .send(bus::Global::Poll {
+                channel: self.shared.channel.clone(),
+                poll: bus::Poll {
+                    id: id.to_string(),
+                    question: self.shared.question.clone(),
+                    options,
+                    weighted: self.shared.currency.is_some(),
+                    closes_at: self.shared.closes_at.map(|at| at.timestamp()),
+                    closed: inner.closed,
+                },
+            })
+            .await;
     }
 }
 
 #[async_trait]
 impl command::MessageHook for ActivePoll {
     async fn peek(&self, user: &chat::User, m: &str) -> Result<()> {
-        let mut inner = self.inner.write().await;
-
         let user = match user.real() {
             Some(user) => user,
             None => return Ok(()),
         };
 
-        if inner.voted.contains(user.login()) {
+        let option = common::words::trimmed(m).find_map(|word| {
+            let word = word.to_lowercase();
+            self.shared.options.iter().position(|o| o.key == word)
+        });
+
+        let Some(option) = option else {
+            return Ok(());
+        };
+
+        let weight = match &self.shared.currency {
+            Some(currency) => currency
+                .balance_of(&self.shared.channel, user.login())
+                .await?
+                .unwrap_or_default()
+                .balance
+                .max(0),
+            None => 1,
+        };
+
+        let mut inner = self.inner.lock().await;
+
+        if inner.closed {
             return Ok(());
         }
 
-        for word in common::words::trimmed(m) {
-            if !self.options.contains_key(&word.to_lowercase()) {
-                continue;
+        // Users can change their vote, but voting for the same option again
+        // does nothing.
+        if let Some(vote) = inner.votes.get(user.login()) {
+            if vote.option == option {
+                return Ok(());
             }
-
-            *inner.votes.entry(word.to_string()).or_default() += 1;
-            inner.voted.insert(user.login().to_string());
-            break;
         }
 
+        inner
+            .votes
+            .insert(user.login().to_string(), Vote { option, weight });
+
+        let tallies = self.tally(&inner);
+        self.publish_with(&inner, &tallies).await;
         Ok(())
     }
 }
@@ -173,21 +448,62 @@ impl chat::Module for Module {
         "poll"
     }
 
+    fn per_channel(&self) -> bool {
+        true
+    }
+
     /// Set up command handlers for this module.
     async fn hook(
         &self,
         module::HookContext {
-            handlers, settings, ..
+            injector,
+            handlers,
+            tasks,
+            sender,
+            settings,
+            ..
         }: module::HookContext<'_, '_>,
     ) -> Result<()> {
+        let polls = Arc::new(Polls {
+            polls: Mutex::new(Default::default()),
+            db: injector.var().await,
+        });
+
         handlers.insert(
             "poll",
             Poll {
-                polls: Mutex::new(Default::default()),
                 enabled: settings.var("poll/enabled", false).await?,
+                weighted: settings.var("poll/weighted", false).await?,
+                polls: polls.clone(),
+                currency: injector.var().await,
+                global_bus: injector.var().await,
             },
         );
 
+        let sender = sender.clone();
+
+        // Close timed polls once their timer runs out.
+        let future = async move {
+            let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+
+            loop {
+                interval.tick().await;
+
+                for id in polls.expired().await {
+                    match polls.close(id).await {
+                        Ok(Some(results)) => {
+                            sender.privmsg(format!("Poll closed: {}", results)).await;
+                        }
+                        Ok(None) => (),
+                        Err(e) => {
+                            common::log_error!(e, "Failed to close poll");
+                        }
+                    }
+                }
+            }
+        };
+
+        tasks.push(Box::pin(future));
         Ok(())
     }
 }
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use anyhow::Result;
@@ -60,6 +61,10 @@ impl chat::Module for Module {
         "promotions"
     }
 
+    fn per_channel(&self) -> bool {
+        true
+    }
+
     async fn hook(
         &self,
         module::HookContext {
@@ -89,6 +94,7 @@ impl chat::Module for Module {
         );
 
         let (mut promotions_stream, mut promotions) = injector.stream::<db::Promotions>().await;
+        let counters = injector.var::<db::Counters>().await;
         let sender = sender.clone();
         let mut interval = tokio::time::interval(frequency.as_std());
         let idle = idle.clone();
@@ -117,9 +123,10 @@ impl chat::Module for Module {
                             tracing::trace!("Channel is too idle to send a promotion");
                         } else {
                             let promotions = promotions.clone();
+                            let counters = counters.load().await;
                             let sender = sender.clone();
 
-                            if let Err(e) = promote(promotions, sender).await {
+                            if let Err(e) = promote(promotions, counters, sender).await {
                                 tracing::error!("Failed to send promotion: {}", e);
                             }
                         }
@@ -134,11 +141,20 @@ impl chat::Module for Module {
 }
 
 /// Run the next promotion.
-async fn promote(promotions: db::Promotions, sender: chat::Sender) -> Result<()> {
+async fn promote(
+    promotions: db::Promotions,
+    counters: Option<db::Counters>,
+    sender: chat::Sender,
+) -> Result<()> {
     let channel = sender.channel();
 
     if let Some(p) = pick(promotions.list(channel).await) {
-        let text = p.render(&PromoData { channel })?;
+        let counters = match counters {
+            Some(counters) => counters.snapshot(channel).await,
+            None => BTreeMap::new(),
+        };
+
+        let text = p.render(&PromoData { channel, counters })?;
         promotions.bump_promoted_at(&p).await?;
         sender.privmsg(text).await;
     }
@@ -149,6 +165,7 @@ async fn promote(promotions: db::Promotions, sender: chat::Sender) -> Result<()>
 #[derive(Debug, serde::Serialize)]
 struct PromoData<'a> {
     channel: &'a Channel,
+    counters: BTreeMap<String, i64>,
 }
 
 /// Pick the best promo.
@@ -0,0 +1,331 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use chat::stream_info;
+use common::Channel;
+use db::queue::Priority;
+
+/// The maximum number of users to show with `!queue list`.
+const LIST_LIMIT: usize = 10;
+
+/// Handler for the `!queue` command.
+pub(crate) struct Handler {
+    settings: settings::Settings<::auth::Scope>,
+    enabled: settings::Var<bool>,
+    open: settings::Var<bool>,
+    subscriber_priority: settings::Var<bool>,
+    skip_cost: settings::Var<Option<i64>>,
+    queue: async_injector::Ref<db::Queue>,
+    currency: async_injector::Ref<currency::Currency>,
+    global_bus: async_injector::Ref<bus::Bus<bus::Global>>,
+    stream_info: stream_info::StreamInfo,
+}
+
+impl Handler {
+    /// Notify overlays of the current state of the queue.
+    async fn publish(&self, queue: &db::Queue, channel: &Channel) -> Result<()> {
+        let Some(global_bus) = self.global_bus.load().await else {
+            return Ok(());
+        };
+
+        let users = queue
+            .list(channel)
+            .await?
+            .into_iter()
+            .map(|e| bus::QueueUser {
+                user: e.user,
+                display_name: e.display_name,
+            })
+            .collect();
+
+        global_bus
+            .send(bus::Global::Queue {
+                channel: channel.to_owned(),
+                users,
+            })
+            .await;
+
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Queue)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let queue = match self.queue.load().await {
+            Some(queue) => queue,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            Some("join") => {
+                if !self.open.load().await {
+                    chat::respond_bail!("The queue is closed");
+                }
+
+                let Some(user) = ctx.user.real() else {
+                    chat::respond_bail!("Only real users can join the queue");
+                };
+
+                let priority = if self.subscriber_priority.load().await
+                    && self.stream_info.is_subscriber(user.login())
+                {
+                    Priority::Subscriber
+                } else {
+                    Priority::Normal
+                };
+
+                if !queue
+                    .join(ctx.channel(), user.login(), user.display_name(), priority)
+                    .await?
+                {
+                    chat::respond_bail!("You are already in the queue");
+                }
+
+                let position = queue
+                    .position(ctx.channel(), user.login())
+                    .await?
+                    .unwrap_or_default();
+
+                self.publish(&queue, ctx.channel()).await?;
+                chat::respond!(ctx, "You joined the queue at position #{}.", position);
+            }
+            Some("leave") => {
+                let Some(user) = ctx.user.real() else {
+                    return Ok(());
+                };
+
+                if !queue.leave(ctx.channel(), user.login()).await? {
+                    chat::respond_bail!("You are not in the queue");
+                }
+
+                self.publish(&queue, ctx.channel()).await?;
+                chat::respond!(ctx, "You left the queue.");
+            }
+            None | Some("position") => {
+                let Some(user) = ctx.user.real() else {
+                    return Ok(());
+                };
+
+                match queue.position(ctx.channel(), user.login()).await? {
+                    Some(position) => {
+                        chat::respond!(ctx, "You are at position #{} in the queue.", position);
+                    }
+                    None => {
+                        chat::respond!(ctx, "You are not in the queue, join it with !queue join.");
+                    }
+                }
+            }
+            Some("list") => {
+                let entries = queue.list(ctx.channel()).await?;
+
+                if entries.is_empty() {
+                    chat::respond!(ctx, "The queue is empty.");
+                    return Ok(());
+                }
+
+                let mut names = entries
+                    .iter()
+                    .take(LIST_LIMIT)
+                    .enumerate()
+                    .map(|(index, e)| format!("#{} {}", index + 1, e.display_name))
+                    .collect::<Vec<_>>();
+
+                if entries.len() > LIST_LIMIT {
+                    names.push(format!("and {} more", entries.len() - LIST_LIMIT));
+                }
+
+                chat::respond!(ctx, "Queue: {}.", names.join(", "));
+            }
+            Some("skip") => {
+                let Some(cost) = self.skip_cost.load().await else {
+                    chat::respond_bail!("Skipping ahead in the queue is not enabled");
+                };
+
+                if cost < 0 {
+                    chat::respond_bail!(
+                        "Skipping ahead is misconfigured, its cost can't be negative"
+                    );
+                }
+
+                let Some(currency) = self.currency.load().await else {
+                    chat::respond_bail!("No currency configured for stream, sorry :(");
+                };
+
+                let Some(user) = ctx.user.real() else {
+                    return Ok(());
+                };
+
+                if queue.position(ctx.channel(), user.login()).await?.is_none() {
+                    chat::respond_bail!("You are not in the queue, join it with !queue join");
+                }
+
+                match currency
+                    .balance_take(ctx.channel(), user.login(), cost)
+                    .await
+                {
+                    Ok(()) => {}
+                    Err(currency::BalanceTransferError::NoBalance) => {
+                        let balance = currency
+                            .balance_of(ctx.channel(), user.login())
+                            .await?
+                            .unwrap_or_default()
+                            .balance;
+
+                        chat::respond_bail!(
+                            "You need {cost} {currency} to skip ahead, you currently have {balance} {currency}.",
+                            cost = cost,
+                            currency = currency.name,
+                            balance = balance,
+                        );
+                    }
+                    Err(e) => return Err(e.into()),
+                }
+
+                // NB: the cost is charged first, so refund it if the user
+                // couldn't be moved ahead.
+                let raised = queue
+                    .raise_priority(ctx.channel(), user.login(), Priority::Skipped)
+                    .await;
+
+                if !matches!(raised, Ok(true)) {
+                    if let Err(e) = currency
+                        .balance_add(ctx.channel(), user.login(), cost)
+                        .await
+                    {
+                        common::log_error!(e, "Failed to refund {} to {}", cost, user.login());
+                    }
+
+                    raised?;
+                    chat::respond_bail!("You already skipped ahead");
+                }
+
+                let position = queue
+                    .position(ctx.channel(), user.login())
+                    .await?
+                    .unwrap_or_default();
+
+                self.publish(&queue, ctx.channel()).await?;
+
+                chat::respond!(
+                    ctx,
+                    "You skipped ahead to position #{} for {} {}.",
+                    position,
+                    cost,
+                    currency.name
+                );
+            }
+            Some("next") => {
+                ctx.check_scope(auth::Scope::QueueEdit).await?;
+
+                let count = ctx.next_parse_optional::<usize>()?.unwrap_or(1);
+                let entries = queue.take(ctx.channel(), count).await?;
+
+                if entries.is_empty() {
+                    chat::respond_bail!("The queue is empty");
+                }
+
+                self.publish(&queue, ctx.channel()).await?;
+
+                let names = entries
+                    .iter()
+                    .map(|e| format!("@{}", e.display_name))
+                    .collect::<Vec<_>>();
+
+                chat::respond!(ctx, "Up next: {}!", names.join(", "));
+            }
+            Some("random") => {
+                use rand::seq::SliceRandom as _;
+
+                ctx.check_scope(auth::Scope::QueueEdit).await?;
+
+                let entries = queue.list(ctx.channel()).await?;
+
+                let Some(entry) = entries.choose(&mut rand::thread_rng()) else {
+                    chat::respond_bail!("The queue is empty");
+                };
+
+                queue.leave(ctx.channel(), &entry.user).await?;
+                self.publish(&queue, ctx.channel()).await?;
+                chat::respond!(ctx, "Randomly picked @{}!", entry.display_name);
+            }
+            Some("open") => {
+                ctx.check_scope(auth::Scope::QueueEdit).await?;
+                self.settings.set("open", true).await?;
+                chat::respond!(ctx, "The queue is open, join it with !queue join!");
+            }
+            Some("close") => {
+                ctx.check_scope(auth::Scope::QueueEdit).await?;
+                self.settings.set("open", false).await?;
+                chat::respond!(ctx, "The queue is closed.");
+            }
+            Some("clear") => {
+                ctx.check_scope(auth::Scope::QueueEdit).await?;
+                let count = queue.clear(ctx.channel()).await?;
+                self.publish(&queue, ctx.channel()).await?;
+                chat::respond!(ctx, "Removed {} users from the queue.", count);
+            }
+            Some(..) => {
+                chat::respond!(
+                    ctx,
+                    "Expected: join, leave, position, list, skip, next, random, open, close, or clear."
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "queue"
+    }
+
+    fn per_channel(&self) -> bool {
+        true
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            stream_info,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("queue");
+
+        handlers.insert(
+            "queue",
+            Handler {
+                settings: settings.clone(),
+                enabled: settings.var("enabled", false).await?,
+                open: settings.var("open", true).await?,
+                subscriber_priority: settings.var("subscriber-priority", false).await?,
+                skip_cost: settings.optional("skip-cost").await?,
+                queue: injector.var().await,
+                currency: injector.var().await,
+                global_bus: injector.var().await,
+                stream_info: stream_info.clone(),
+            },
+        );
+
+        Ok(())
+    }
+}
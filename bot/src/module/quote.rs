@@ -0,0 +1,225 @@
+use std::fmt;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use chat::stream_info;
+
+/// The maximum number of search results to list.
+const SEARCH_LIMIT: usize = 10;
+
+/// Handler for the `!quote` command.
+pub(crate) struct Handler {
+    enabled: settings::Var<bool>,
+    quotes: async_injector::Ref<db::Quotes>,
+    stream_info: stream_info::StreamInfo,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Quote)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let quotes = match self.quotes.load().await {
+            Some(quotes) => quotes,
+            None => return Ok(()),
+        };
+
+        match ctx.next().as_deref() {
+            None | Some("random") => {
+                let Some(quote) = quotes.random(ctx.channel()).await? else {
+                    chat::respond!(ctx, "There are no quotes yet.");
+                    return Ok(());
+                };
+
+                chat::respond!(ctx, "{}", Display(&quote));
+            }
+            Some("get") => {
+                let id = ctx.next_parse("<id>")?;
+                show(ctx, &quotes, id).await?;
+            }
+            Some("search") => {
+                let query = ctx.rest().trim();
+
+                if query.is_empty() {
+                    chat::respond_bail!("Expected <query>");
+                }
+
+                let found = quotes.search(ctx.channel(), query).await?;
+
+                match found.as_slice() {
+                    [] => {
+                        chat::respond!(ctx, "No quotes matching `{}`.", query);
+                    }
+                    [quote] => {
+                        chat::respond!(ctx, "{}", Display(quote));
+                    }
+                    found => {
+                        let mut ids = found
+                            .iter()
+                            .take(SEARCH_LIMIT)
+                            .map(|q| format!("#{}", q.id))
+                            .collect::<Vec<_>>();
+
+                        if found.len() > SEARCH_LIMIT {
+                            ids.push(String::from("..."));
+                        }
+
+                        chat::respond!(
+                            ctx,
+                            "Found {} quotes: {}. Use !quote get <id> to see one.",
+                            found.len(),
+                            ids.join(", ")
+                        );
+                    }
+                }
+            }
+            Some("add") => {
+                ctx.check_scope(auth::Scope::QuoteEdit).await?;
+
+                let author = ctx.next_str("<author> <text..>")?;
+                let author = author.trim_start_matches('@');
+                let text = ctx.rest().trim();
+
+                if text.is_empty() {
+                    chat::respond_bail!("Expected <author> <text..>");
+                }
+
+                let Some(user) = ctx.user.real() else {
+                    chat::respond_bail!("Only real users can add quotes");
+                };
+
+                let game = self.stream_info.data.read().game.clone();
+
+                let id = quotes
+                    .insert(ctx.channel(), user.login(), author, game.as_deref(), text)
+                    .await?;
+
+                chat::respond!(ctx, "Added quote #{}.", id);
+            }
+            Some("edit") => {
+                ctx.check_scope(auth::Scope::QuoteEdit).await?;
+
+                let id = ctx.next_parse("<id> <text..>")?;
+                let text = ctx.rest().trim();
+
+                if text.is_empty() {
+                    chat::respond_bail!("Expected <id> <text..>");
+                }
+
+                if !quotes.edit(ctx.channel(), id, None, Some(text)).await? {
+                    chat::respond!(ctx, "No quote #{}.", id);
+                    return Ok(());
+                }
+
+                chat::respond!(ctx, "Edited quote #{}.", id);
+            }
+            Some("delete") => {
+                ctx.check_scope(auth::Scope::QuoteEdit).await?;
+
+                let id = ctx.next_parse("<id>")?;
+
+                if !quotes.delete(ctx.channel(), id).await? {
+                    chat::respond!(ctx, "No quote #{}.", id);
+                    return Ok(());
+                }
+
+                chat::respond!(ctx, "Deleted quote #{}.", id);
+            }
+            Some(other) => match str::parse::<i32>(other) {
+                Ok(id) => {
+                    show(ctx, &quotes, id).await?;
+                }
+                Err(..) => {
+                    chat::respond!(
+                        ctx,
+                        "Expected: <id>, get, random, search, add, edit, or delete."
+                    );
+                }
+            },
+        }
+
+        Ok(())
+    }
+}
+
+/// Show the quote with the given id.
+async fn show(ctx: &command::Context<'_>, quotes: &db::Quotes, id: i32) -> Result<()> {
+    match quotes.get(ctx.channel(), id).await? {
+        Some(quote) => {
+            chat::respond!(ctx, "{}", Display(&quote));
+        }
+        None => {
+            chat::respond!(ctx, "No quote #{}.", id);
+        }
+    }
+
+    Ok(())
+}
+
+/// Helper to display a quote in chat.
+struct Display<'a>(&'a db::Quote);
+
+impl fmt::Display for Display<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quote = self.0;
+
+        write!(
+            f,
+            "Quote #{}: \"{}\" - {}",
+            quote.id, quote.text, quote.author
+        )?;
+
+        let date = quote.added_at.format("%Y-%m-%d");
+
+        match &quote.game {
+            Some(game) => write!(f, " ({}, {})", game, date),
+            None => write!(f, " ({})", date),
+        }
+    }
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "quote"
+    }
+
+    fn per_channel(&self) -> bool {
+        true
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            stream_info,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("quote");
+
+        handlers.insert(
+            "quote",
+            Handler {
+                enabled: settings.var("enabled", true).await?,
+                quotes: injector.var().await,
+                stream_info: stream_info.clone(),
+            },
+        );
+
+        Ok(())
+    }
+}
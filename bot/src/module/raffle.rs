@@ -0,0 +1,533 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use chat::stream_info;
+use tokio::sync::Mutex;
+
+/// The number of winners shown by `!raffle winners`.
+const WINNERS_LIMIT: i64 = 5;
+
+/// A raffle which is currently running.
+struct Raffle {
+    /// Price of a single ticket.
+    price: i64,
+    /// Maximum number of tickets a single user can buy.
+    max_tickets: u32,
+    /// If tickets can still be bought.
+    open: bool,
+    /// Tickets bought, by login.
+    entries: HashMap<String, Entry>,
+}
+
+impl Raffle {
+    /// The total number of tickets sold.
+    fn total_tickets(&self) -> u32 {
+        self.entries.values().map(|e| e.tickets).sum()
+    }
+}
+
+struct Entry {
+    display_name: String,
+    tickets: u32,
+}
+
+/// Handler for the `!raffle` command.
+pub(crate) struct Handler {
+    enabled: settings::Var<bool>,
+    max_tickets: settings::Var<u32>,
+    subscriber_luck: settings::Var<u32>,
+    raffle: Arc<Mutex<Option<Raffle>>>,
+    currency: async_injector::Ref<currency::Currency>,
+    raffles: async_injector::Ref<db::Raffles>,
+    stream_info: stream_info::StreamInfo,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Raffle)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        match ctx.next().as_deref() {
+            None => {
+                let raffle = self.raffle.lock().await;
+
+                let Some(raffle) = raffle.as_ref() else {
+                    chat::respond!(ctx, "No raffle is running.");
+                    return Ok(());
+                };
+
+                let state = if raffle.open {
+                    "Buy tickets with !ticket <amount>"
+                } else {
+                    "Ticket sales are closed"
+                };
+
+                chat::respond!(
+                    ctx,
+                    "Raffle: {} tickets sold to {} users at {} each. {}.",
+                    raffle.total_tickets(),
+                    raffle.entries.len(),
+                    raffle.price,
+                    state
+                );
+            }
+            Some("open") => {
+                ctx.check_scope(auth::Scope::RaffleEdit).await?;
+
+                let price = ctx.next_parse::<i64, _>("<price> [max-tickets]")?;
+
+                if price < 0 {
+                    chat::respond_bail!("Ticket price can't be negative");
+                }
+
+                let max_tickets = match ctx.next_parse_optional::<u32>()? {
+                    Some(max_tickets) => max_tickets,
+                    None => self.max_tickets.load().await,
+                };
+
+                if max_tickets == 0 {
+                    chat::respond_bail!("Users must be able to buy at least one ticket");
+                }
+
+                let currency = match self.currency.load().await {
+                    Some(currency) => Some(currency),
+                    None if price == 0 => None,
+                    None => {
+                        chat::respond_bail!("No currency configured for stream, sorry :(");
+                    }
+                };
+
+                let mut raffle = self.raffle.lock().await;
+
+                if raffle.is_some() {
+                    chat::respond_bail!(
+                        "A raffle is already running, draw or cancel it before starting a new one"
+                    );
+                }
+
+                *raffle = Some(Raffle {
+                    price,
+                    max_tickets,
+                    open: true,
+                    entries: HashMap::new(),
+                });
+
+                match currency {
+                    Some(currency) if price > 0 => {
+                        chat::respond!(
+                            ctx,
+                            "A raffle has started! Buy up to {} tickets for {} {} each with !ticket <amount>.",
+                            max_tickets,
+                            price,
+                            currency.name
+                        );
+                    }
+                    _ => {
+                        chat::respond!(
+                            ctx,
+                            "A raffle has started! Get up to {} free tickets with !ticket <amount>.",
+                            max_tickets
+                        );
+                    }
+                }
+            }
+            Some("close") => {
+                ctx.check_scope(auth::Scope::RaffleEdit).await?;
+
+                let mut raffle = self.raffle.lock().await;
+
+                let Some(raffle) = raffle.as_mut() else {
+                    chat::respond_bail!("No raffle is running");
+                };
+
+                raffle.open = false;
+
+                chat::respond!(
+                    ctx,
+                    "Ticket sales are closed, {} tickets were sold to {} users. Draw winners with !raffle draw.",
+                    raffle.total_tickets(),
+                    raffle.entries.len()
+                );
+            }
+            Some("draw") => {
+                ctx.check_scope(auth::Scope::RaffleEdit).await?;
+
+                let count = ctx.next_parse_optional::<usize>()?.unwrap_or(1);
+
+                if count == 0 {
+                    chat::respond_bail!("Must draw at least one winner");
+                }
+
+                let Some(raffle) = self.raffle.lock().await.take() else {
+                    chat::respond_bail!("No raffle is running");
+                };
+
+                let winners = self.draw(&raffle, count).await;
+
+                if winners.is_empty() {
+                    chat::respond!(ctx, "No one entered the raffle :(");
+                    return Ok(());
+                }
+
+                if let Some(raffles) = self.raffles.load().await {
+                    let total_tickets = raffle.total_tickets();
+
+                    for (login, entry) in &winners {
+                        // NB: the raffle is over at this point, so a failure to
+                        // record the winner shouldn't stop them from being announced.
+                        if let Err(e) = raffles
+                            .insert_winner(
+                                ctx.channel(),
+                                login,
+                                entry.tickets,
+                                total_tickets,
+                                raffle.price,
+                            )
+                            .await
+                        {
+                            common::log_error!(e, "Failed to record raffle winner: {}", login);
+                        }
+                    }
+                }
+
+                let names = winners
+                    .iter()
+                    .map(|(_, entry)| entry.display_name.as_str())
+                    .collect::<Vec<_>>();
+
+                if let [name] = names.as_slice() {
+                    chat::respond!(ctx, "The winner of the raffle is {}!", name);
+                } else {
+                    chat::respond!(ctx, "The winners of the raffle are {}!", names.join(", "));
+                }
+            }
+            Some("cancel") => {
+                ctx.check_scope(auth::Scope::RaffleEdit).await?;
+
+                let mut guard = self.raffle.lock().await;
+
+                let Some(raffle) = guard.as_ref() else {
+                    chat::respond_bail!("No raffle is running");
+                };
+
+                let mut refunds = Vec::new();
+
+                if raffle.price > 0 {
+                    for (login, entry) in &raffle.entries {
+                        let Some(amount) = raffle.price.checked_mul(i64::from(entry.tickets))
+                        else {
+                            tracing::error!("Refund to {} is out of range", login);
+                            continue;
+                        };
+
+                        refunds.push((login.clone(), amount));
+                    }
+                }
+
+                let refunded = refunds.len();
+
+                if !refunds.is_empty() {
+                    let Some(currency) = self.currency.load().await else {
+                        chat::respond_bail!("No currency configured, so tickets can't be refunded");
+                    };
+
+                    if let Err(e) = currency.balances_add(ctx.channel(), refunds).await {
+                        common::log_error!(e, "Failed to refund raffle tickets");
+                        chat::respond_bail!(
+                            "Failed to refund tickets, the raffle is still running"
+                        );
+                    }
+                }
+
+                *guard = None;
+                drop(guard);
+
+                if refunded > 0 {
+                    chat::respond!(
+                        ctx,
+                        "The raffle was cancelled, tickets have been refunded to {} users.",
+                        refunded
+                    );
+                } else {
+                    chat::respond!(ctx, "The raffle was cancelled.");
+                }
+            }
+            Some("winners") => {
+                let Some(raffles) = self.raffles.load().await else {
+                    return Ok(());
+                };
+
+                let winners = raffles.winners(ctx.channel(), WINNERS_LIMIT).await?;
+
+                if winners.is_empty() {
+                    chat::respond!(ctx, "No one has won a raffle yet.");
+                    return Ok(());
+                }
+
+                let winners = winners
+                    .iter()
+                    .map(|w| format!("{} ({})", w.user, w.drawn_at.format("%Y-%m-%d")))
+                    .collect::<Vec<_>>();
+
+                chat::respond!(ctx, "Recent winners: {}.", winners.join(", "));
+            }
+            Some(..) => {
+                chat::respond!(ctx, "Expected: open, close, draw, cancel, or winners.");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Handler {
+    /// Draw up to `count` distinct winners from the raffle, weighted by the
+    /// number of tickets they hold.
+    async fn draw<'a>(&self, raffle: &'a Raffle, count: usize) -> Vec<(&'a String, &'a Entry)> {
+        use rand::Rng as _;
+
+        let luck = u64::from(self.subscriber_luck.load().await);
+
+        let mut candidates = raffle
+            .entries
+            .iter()
+            .map(|(login, entry)| {
+                let luck = if self.stream_info.is_subscriber(login) {
+                    luck
+                } else {
+                    100
+                };
+
+                (login, entry, u64::from(entry.tickets) * luck)
+            })
+            .collect::<Vec<_>>();
+
+        // NB: sorted so that draws only depend on the random number
+        // generator and not on hash map ordering.
+        candidates.sort_by(|a, b| a.0.cmp(b.0));
+
+        let mut rng = rand::thread_rng();
+        let mut winners = Vec::new();
+
+        while winners.len() < count {
+            let total = candidates.iter().map(|c| c.2).sum::<u64>();
+
+            if total == 0 {
+                break;
+            }
+
+            let weights = candidates.iter().map(|c| c.2);
+            let Some(index) = pick_weighted(weights, rng.gen_range(0..total)) else {
+                break;
+            };
+
+            let (login, entry, _) = candidates.swap_remove(index);
+            winners.push((login, entry));
+        }
+
+        winners
+    }
+}
+
+/// Pick the index of the weight that the number `n` falls into, where `n` is
+/// in the range `0..sum(weights)`.
+fn pick_weighted(weights: impl IntoIterator<Item = u64>, mut n: u64) -> Option<usize> {
+    for (index, weight) in weights.into_iter().enumerate() {
+        if n < weight {
+            return Some(index);
+        }
+
+        n -= weight;
+    }
+
+    None
+}
+
+/// Handler for the `!ticket` command.
+pub(crate) struct Ticket {
+    enabled: settings::Var<bool>,
+    raffle: Arc<Mutex<Option<Raffle>>>,
+    currency: async_injector::Ref<currency::Currency>,
+}
+
+#[async_trait]
+impl command::Handler for Ticket {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Raffle)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let amount = ctx.next_parse_optional::<u32>()?.unwrap_or(1);
+
+        if amount == 0 {
+            chat::respond_bail!("Expected a number of tickets to buy");
+        }
+
+        let Some(user) = ctx.user.real() else {
+            chat::respond_bail!("Only real users can buy tickets");
+        };
+
+        let mut raffle = self.raffle.lock().await;
+
+        let raffle = match raffle.as_mut() {
+            Some(raffle) if raffle.open => raffle,
+            Some(..) => chat::respond_bail!("Ticket sales for the raffle are closed"),
+            None => chat::respond_bail!("No raffle is running"),
+        };
+
+        let held = raffle
+            .entries
+            .get(user.login())
+            .map(|e| e.tickets)
+            .unwrap_or_default();
+
+        let available = raffle.max_tickets.saturating_sub(held);
+
+        if amount > available {
+            if available == 0 {
+                chat::respond_bail!(
+                    "You already have the maximum of {} tickets",
+                    raffle.max_tickets
+                );
+            }
+
+            chat::respond_bail!("You can only buy {} more tickets", available);
+        }
+
+        let Some(cost) = raffle.price.checked_mul(i64::from(amount)) else {
+            chat::respond_bail!("Can't buy that many tickets at once");
+        };
+
+        if cost > 0 {
+            let Some(currency) = self.currency.load().await else {
+                chat::respond_bail!("No currency configured for stream, sorry :(");
+            };
+
+            match currency
+                .balance_take(ctx.channel(), user.login(), cost)
+                .await
+            {
+                Ok(()) => {}
+                Err(currency::BalanceTransferError::NoBalance) => {
+                    let balance = currency
+                        .balance_of(ctx.channel(), user.login())
+                        .await?
+                        .unwrap_or_default()
+                        .balance;
+
+                    chat::respond_bail!(
+                        "You need {cost} {currency} to buy {amount} tickets, you currently have {balance} {currency}.",
+                        cost = cost,
+                        currency = currency.name,
+                        amount = amount,
+                        balance = balance,
+                    );
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let entry = raffle
+            .entries
+            .entry(user.login().to_string())
+            .or_insert_with(|| Entry {
+                display_name: user.display_name().to_string(),
+                tickets: 0,
+            });
+
+        entry.tickets += amount;
+
+        chat::respond!(
+            ctx,
+            "You now have {} of {} tickets in the raffle.",
+            entry.tickets,
+            raffle.max_tickets
+        );
+
+        Ok(())
+    }
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "raffle"
+    }
+
+    fn per_channel(&self) -> bool {
+        true
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector,
+            handlers,
+            settings,
+            stream_info,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("raffle");
+        let enabled = settings.var("enabled", false).await?;
+        let raffle = Arc::new(Mutex::new(None));
+
+        handlers.insert(
+            "raffle",
+            Handler {
+                enabled: enabled.clone(),
+                max_tickets: settings.var("max-tickets", 10).await?,
+                subscriber_luck: settings.var("subscriber-luck%", 100).await?,
+                raffle: raffle.clone(),
+                currency: injector.var().await,
+                raffles: injector.var().await,
+                stream_info: stream_info.clone(),
+            },
+        );
+
+        handlers.insert(
+            "ticket",
+            Ticket {
+                enabled,
+                raffle,
+                currency: injector.var().await,
+            },
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::pick_weighted;
+
+    #[test]
+    fn test_pick_weighted() {
+        let weights = [100, 0, 300];
+
+        assert_eq!(pick_weighted(weights, 0), Some(0));
+        assert_eq!(pick_weighted(weights, 99), Some(0));
+        assert_eq!(pick_weighted(weights, 100), Some(2));
+        assert_eq!(pick_weighted(weights, 399), Some(2));
+        assert_eq!(pick_weighted(weights, 400), None);
+        assert_eq!(pick_weighted([], 0), None);
+    }
+}
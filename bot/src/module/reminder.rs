@@ -0,0 +1,388 @@
+use std::collections::HashMap;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use chat::stream_info;
+use chrono::{DateTime, Utc};
+use common::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+/// A reward handed out to a user for a reminder.
+#[derive(Clone)]
+pub(crate) struct Reward {
+    pub(crate) user: String,
+    pub(crate) amount: i64,
+}
+
+/// The history of a single reminder, used to calculate rewards and to undo
+/// them.
+#[derive(Default)]
+pub(crate) struct History {
+    entries: Vec<(DateTime<Utc>, Option<Reward>)>,
+}
+
+impl History {
+    /// Get when the reminder was last issued and the reward that was handed
+    /// out for it.
+    ///
+    /// If the reminder hasn't been issued since the current stream started,
+    /// the start of the stream is used. Returns `None` if the stream is not
+    /// live and the reminder hasn't been issued yet.
+    pub(crate) fn last(
+        &mut self,
+        stream_info: &stream_info::StreamInfo,
+    ) -> Option<(DateTime<Utc>, Option<Reward>)> {
+        self.last_since(started_at(stream_info))
+    }
+
+    /// Get when the reminder was last issued and the reward that was handed
+    /// out for it, no matter which stream it was issued in.
+    ///
+    /// The start of the current stream is only used if the reminder has
+    /// never been issued, which is how `!water` works.
+    pub(crate) fn last_or_start(
+        &mut self,
+        stream_info: &stream_info::StreamInfo,
+    ) -> Option<(DateTime<Utc>, Option<Reward>)> {
+        self.last_or(started_at(stream_info))
+    }
+
+    fn last_since(
+        &mut self,
+        started_at: Option<DateTime<Utc>>,
+    ) -> Option<(DateTime<Utc>, Option<Reward>)> {
+        if let Some(started_at) = started_at {
+            if self
+                .entries
+                .last()
+                .map_or(true, |(when, _)| *when < started_at)
+            {
+                self.entries.push((started_at, None));
+            }
+        }
+
+        let (when, reward) = self.entries.last()?;
+        Some((*when, reward.clone()))
+    }
+
+    fn last_or(
+        &mut self,
+        started_at: Option<DateTime<Utc>>,
+    ) -> Option<(DateTime<Utc>, Option<Reward>)> {
+        if self.entries.is_empty() {
+            self.entries.push((started_at?, None));
+        }
+
+        let (when, reward) = self.entries.last()?;
+        Some((*when, reward.clone()))
+    }
+
+    /// Issue a reminder, calculating the reward for `user` from the time
+    /// that passed since `last`.
+    pub(crate) fn issue(
+        &mut self,
+        last: DateTime<Utc>,
+        user: &str,
+        reward_multiplier: u32,
+    ) -> Reward {
+        let now = Utc::now();
+        let amount = i64::max(0i64, (now - last).num_minutes());
+        let amount = (amount * reward_multiplier as i64) / 100i64;
+
+        let reward = Reward {
+            user: user.to_string(),
+            amount,
+        };
+
+        self.entries.push((now, Some(reward.clone())));
+        reward
+    }
+
+    /// Undo the last reminder.
+    pub(crate) fn undo(&mut self) {
+        self.entries.pop();
+    }
+}
+
+/// When the current stream started, if it's live.
+fn started_at(stream_info: &stream_info::StreamInfo) -> Option<DateTime<Utc>> {
+    stream_info
+        .data
+        .read()
+        .stream
+        .as_ref()
+        .map(|s| s.started_at)
+}
+
+/// A reminder as configured in settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Reminder {
+    name: String,
+    message: String,
+    /// How long must pass between each reminder.
+    interval: Duration,
+    /// Reward scaling, in percent. Defaults to 100%.
+    #[serde(default)]
+    reward: Option<u32>,
+    /// The scope required to undo the reminder, instead of `reminder/undo`.
+    #[serde(default)]
+    undo_scope: Option<String>,
+}
+
+pub(crate) struct Handler {
+    enabled: settings::Var<bool>,
+    reminders: settings::Var<Vec<Reminder>>,
+    currency: async_injector::Ref<currency::Currency>,
+    histories: Mutex<HashMap<String, History>>,
+    stream_info: stream_info::StreamInfo,
+    streamer: api::TwitchAndUser,
+}
+
+impl Handler {
+    /// List all reminders and when they are due.
+    async fn list(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        let reminders = self.reminders.load().await;
+
+        if reminders.is_empty() {
+            chat::respond_bail!("No reminders are configured");
+        }
+
+        let mut histories = self.histories.lock().await;
+        let now = Utc::now();
+        let mut out = Vec::new();
+
+        for reminder in &reminders {
+            let history = histories.entry(reminder.name.clone()).or_default();
+
+            let Some((last, _)) = history.last(&self.stream_info) else {
+                out.push(reminder.name.clone());
+                continue;
+            };
+
+            let due = last + reminder.interval.as_chrono();
+
+            if due <= now {
+                out.push(format!("{} (due)", reminder.name));
+            } else {
+                let remaining = Duration::seconds((due - now).num_seconds() as u64);
+                out.push(format!("{} (in {})", reminder.name, remaining));
+            }
+        }
+
+        chat::respond!(ctx, "Reminders: {}.", out.join(", "));
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Reminder)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        if !self.enabled.load().await {
+            return Ok(());
+        }
+
+        let Some(name) = ctx.next() else {
+            return self.list(ctx).await;
+        };
+
+        let reminder = self
+            .reminders
+            .load()
+            .await
+            .into_iter()
+            .find(|r| r.name.eq_ignore_ascii_case(&name));
+
+        let Some(reminder) = reminder else {
+            chat::respond_bail!("No reminder named `{}`", name);
+        };
+
+        let currency = match self.currency.load().await {
+            Some(currency) => currency,
+            None => {
+                chat::respond_bail!("No currency configured for stream, sorry :(");
+            }
+        };
+
+        let mut histories = self.histories.lock().await;
+        let history = histories.entry(reminder.name.clone()).or_default();
+
+        let Some((last, reward)) = history.last(&self.stream_info) else {
+            chat::respond_bail!(
+                "Sorry, the {} reminder is currently not available :(",
+                reminder.name
+            );
+        };
+
+        match ctx.next().as_deref() {
+            Some("undo") => {
+                let scope = match &reminder.undo_scope {
+                    Some(scope) => str::parse::<auth::Scope>(scope)?,
+                    None => auth::Scope::ReminderUndo,
+                };
+
+                ctx.check_scope(scope).await?;
+
+                let Some(reward) = reward else {
+                    chat::respond_bail!(
+                        "No one has been rewarded for the {} reminder yet cmonBruh",
+                        reminder.name
+                    );
+                };
+
+                history.undo();
+
+                ctx.privmsg(format!(
+                    "{user} issued a bad {name} reminder that is now being undone FeelsBadMan",
+                    user = reward.user,
+                    name = reminder.name,
+                ))
+                .await;
+
+                if let Err(e) = currency
+                    .balance_add(ctx.channel(), &reward.user, -reward.amount)
+                    .await
+                {
+                    common::log_error!(e, "Failed to undo reminder reward");
+                }
+            }
+            None => {
+                let Some(user) = ctx.user.real() else {
+                    chat::respond_bail!("Only real users can issue reminders");
+                };
+
+                let due = last + reminder.interval.as_chrono();
+                let now = Utc::now();
+
+                if now < due {
+                    let remaining = Duration::seconds((due - now).num_seconds() as u64);
+
+                    chat::respond_bail!(
+                        "The {} reminder isn't due for another {}, please wait a bit longer!",
+                        reminder.name,
+                        remaining
+                    );
+                }
+
+                let reward = history.issue(last, user.login(), reminder.reward.unwrap_or(100));
+
+                chat::respond!(
+                    ctx,
+                    "{streamer}, {message} {user} has been rewarded {amount} {currency} for the reminder.",
+                    streamer = self.streamer.user.login,
+                    message = reminder.message,
+                    user = user.display_name(),
+                    amount = reward.amount,
+                    currency = currency.name
+                );
+
+                if let Err(error) = currency
+                    .balance_add(ctx.channel(), user.login(), reward.amount)
+                    .await
+                {
+                    common::log_error!(error, "Failed to apply reminder reward");
+                }
+            }
+            Some(_) => {
+                chat::respond!(
+                    ctx,
+                    "Expected: !reminder {name}, or !reminder {name} undo.",
+                    name = reminder.name
+                );
+            }
+        }
+
+        Ok(())
+    }
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "reminder"
+    }
+
+    /// Set up command handlers for this module.
+    async fn hook(
+        &self,
+        module::HookContext {
+            handlers,
+            stream_info,
+            settings,
+            injector,
+            streamer,
+            ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        let settings = settings.scoped("reminder");
+
+        handlers.insert(
+            "reminder",
+            Handler {
+                enabled: settings.var("enabled", false).await?,
+                reminders: settings.var("reminders", Vec::new()).await?,
+                currency: injector.var().await,
+                histories: Mutex::new(HashMap::new()),
+                stream_info: stream_info.clone(),
+                streamer: streamer.clone(),
+            },
+        );
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::History;
+
+    #[test]
+    fn test_last_since_stream_start() {
+        let first = Utc::now() - Duration::hours(3);
+
+        let mut history = History::default();
+        assert!(history.last_since(None).is_none());
+        assert_eq!(Some(first), history.last_since(Some(first)).map(|(w, _)| w));
+
+        let reward = history.issue(first, "setbac", 100);
+        assert_eq!("setbac", reward.user);
+
+        // A stream starting after the reminder was issued resets when it's
+        // due.
+        let second = Utc::now() + Duration::minutes(1);
+        let (when, reward) = history.last_since(Some(second)).expect("last");
+        assert_eq!(second, when);
+        assert!(reward.is_none());
+    }
+
+    #[test]
+    fn test_last_across_streams() {
+        let first = Utc::now() - Duration::hours(3);
+
+        let mut history = History::default();
+        assert!(history.last_or(None).is_none());
+        assert_eq!(Some(first), history.last_or(Some(first)).map(|(w, _)| w));
+
+        history.issue(first, "setbac", 100);
+
+        // Restarting the stream keeps the last reward, so it can still be
+        // undone.
+        let second = Utc::now() + Duration::minutes(1);
+        let (when, reward) = history.last_or(Some(second)).expect("last");
+        assert!(when < second);
+        assert_eq!("setbac", reward.expect("reward").user);
+
+        history.undo();
+        assert_eq!(Some(first), history.last_or(Some(second)).map(|(w, _)| w));
+    }
+}
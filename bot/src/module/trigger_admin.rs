@@ -0,0 +1,128 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chat::command;
+use chat::module;
+use common::Duration;
+use db::triggers::{Kind, Pattern, Update};
+
+/// Handler for the `!trigger` command.
+pub(crate) struct Handler {
+    triggers: async_injector::Ref<db::Triggers>,
+}
+
+#[async_trait]
+impl command::Handler for Handler {
+    fn scope(&self) -> Option<auth::Scope> {
+        Some(auth::Scope::Trigger)
+    }
+
+    async fn handle(&self, ctx: &mut command::Context<'_>) -> Result<()> {
+        let triggers = match self.triggers.load().await {
+            Some(triggers) => triggers,
+            None => return Ok(()),
+        };
+
+        let next = command_base!(ctx, triggers, "trigger", TriggerEdit);
+
+        let mut update = Update::default();
+
+        let name = match next.as_deref() {
+            Some("edit") => {
+                ctx.check_scope(auth::Scope::TriggerEdit).await?;
+
+                let usage = "<name> <phrase|keyword|regex> <pattern> <template..>";
+                let name = ctx.next_str(usage)?;
+                let kind = ctx.next_parse::<Kind, _>(usage)?;
+                let pattern = ctx.next_str(usage)?;
+
+                let pattern = match Pattern::new(kind, &pattern) {
+                    Ok(pattern) => pattern,
+                    Err(e) => {
+                        chat::respond_bail!("Bad pattern: {}", e);
+                    }
+                };
+
+                let template = ctx.rest_parse(usage)?;
+                triggers
+                    .edit(ctx.channel(), &name, pattern, template)
+                    .await?;
+                chat::respond!(ctx, "Edited trigger.");
+                return Ok(());
+            }
+            Some("priority") => {
+                ctx.check_scope(auth::Scope::TriggerEdit).await?;
+                let name = ctx.next_str("<name> <priority>")?;
+                update.priority = Some(ctx.next_parse("<name> <priority>")?);
+                name
+            }
+            Some("cooldown") => {
+                ctx.check_scope(auth::Scope::TriggerEdit).await?;
+                let name = ctx.next_str("<name> <duration>")?;
+                update.cooldown = Some(ctx.next_parse::<Duration, _>("<name> <duration>")?);
+                name
+            }
+            Some("exempt-mods") => {
+                ctx.check_scope(auth::Scope::TriggerEdit).await?;
+                let name = ctx.next_str("<name> <true|false>")?;
+                update.exempt_moderators = Some(ctx.next_parse("<name> <true|false>")?);
+                name
+            }
+            Some("exempt-bots") => {
+                ctx.check_scope(auth::Scope::TriggerEdit).await?;
+                let name = ctx.next_str("<name> <true|false>")?;
+                update.exempt_bots = Some(ctx.next_parse("<name> <true|false>")?);
+                name
+            }
+            Some("live") => {
+                ctx.check_scope(auth::Scope::TriggerEdit).await?;
+                let name = ctx.next_str("<name> <true|false>")?;
+                update.only_live = Some(ctx.next_parse("<name> <true|false>")?);
+                name
+            }
+            None | Some(..) => {
+                chat::respond!(
+                    ctx,
+                    "Expected: show, list, edit, priority, cooldown, exempt-mods, exempt-bots, live, delete, enable, disable, or group."
+                );
+                return Ok(());
+            }
+        };
+
+        if !triggers.update(ctx.channel(), &name, update).await? {
+            chat::respond!(ctx, "No trigger named `{}`.", name);
+            return Ok(());
+        }
+
+        chat::respond!(ctx, "Updated trigger `{}`.", name);
+        Ok(())
+    }
+}
+
+pub(crate) struct Module;
+
+#[async_trait]
+impl chat::Module for Module {
+    fn ty(&self) -> &'static str {
+        "trigger"
+    }
+
+    fn per_channel(&self) -> bool {
+        true
+    }
+
+    async fn hook(
+        &self,
+        module::HookContext {
+            injector, handlers, ..
+        }: module::HookContext<'_, '_>,
+    ) -> Result<()> {
+        handlers.insert(
+            "trigger",
+            Handler {
+                triggers: injector.var().await,
+            },
+        );
+
+        Ok(())
+    }
+}
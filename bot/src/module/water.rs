@@ -7,45 +7,25 @@ use chrono::{DateTime, Utc};
 use common::{Cooldown, Duration};
 use tokio::sync::Mutex;
 
-#[derive(Clone)]
-pub(crate) struct Reward {
-    user: String,
-    amount: i64,
-}
+use crate::module::reminder::{History, Reward};
 
 pub(crate) struct Handler {
     enabled: settings::Var<bool>,
     cooldown: settings::Var<Cooldown>,
     currency: async_injector::Ref<currency::Currency>,
-    waters: Mutex<Vec<(DateTime<Utc>, Option<Reward>)>>,
+    waters: Mutex<History>,
     stream_info: stream_info::StreamInfo,
     reward_multiplier: settings::Var<u32>,
     streamer: api::TwitchAndUser,
 }
 
 impl Handler {
-    async fn check_waters(
-        &self,
-        waters: &mut Vec<(DateTime<Utc>, Option<Reward>)>,
-    ) -> Result<(DateTime<Utc>, Option<Reward>)> {
-        if let Some((when, user)) = waters.last() {
-            return Ok((*when, user.clone()));
-        }
-
-        let started_at = self
-            .stream_info
-            .data
-            .read()
-            .stream
-            .as_ref()
-            .map(|s| s.started_at);
-
-        let started_at = started_at.ok_or(chat::respond_err!(
-            "Sorry, the !water command is currently not available :("
-        ))?;
-
-        waters.push((started_at, None));
-        Ok((started_at, None))
+    async fn check_waters(&self, waters: &mut History) -> Result<(DateTime<Utc>, Option<Reward>)> {
+        Ok(waters
+            .last_or_start(&self.stream_info)
+            .ok_or(chat::respond_err!(
+                "Sorry, the !water command is currently not available :("
+            ))?)
     }
 }
 
@@ -80,7 +60,7 @@ impl command::Handler for Handler {
                 let mut waters = self.waters.lock().await;
                 let (_, reward) = self.check_waters(&mut waters).await?;
 
-                waters.pop();
+                waters.undo();
 
                 let reward = match reward {
                     Some(reward) => reward,
@@ -115,18 +95,8 @@ impl command::Handler for Handler {
                     }
                 };
 
-                let now = Utc::now();
-                let diff = now - last;
-                let amount = i64::max(0i64, diff.num_minutes());
-                let amount = (amount * self.reward_multiplier.load().await as i64) / 100i64;
-
-                waters.push((
-                    now,
-                    Some(Reward {
-                        user: user.login().to_string(),
-                        amount,
-                    }),
-                ));
+                let Reward { amount, .. } =
+                    waters.issue(last, user.login(), self.reward_multiplier.load().await);
 
                 chat::respond!(
                     ctx,
@@ -188,7 +158,7 @@ impl chat::Module for Module {
                 enabled,
                 cooldown,
                 currency: injector.var().await,
-                waters: Mutex::new(Vec::new()),
+                waters: Mutex::new(History::default()),
                 stream_info: stream_info.clone(),
                 reward_multiplier,
                 streamer: streamer.clone(),
@@ -1,7 +1,7 @@
 use anyhow::{bail, Result};
 use bytes::Bytes;
 use reqwest::{header, Client, Method, StatusCode, Url};
-use serde::de;
+use serde::{de, Deserialize};
 use thiserror::Error;
 
 use crate::token::Token;
@@ -196,8 +196,9 @@ impl<'a> RequestBuilder<'a> {
             tracing::trace!(?status, "Response: {response}");
         }
 
+        // NB: refreshing the token won't grant scopes it's missing.
         if let Some(token) = self.token {
-            if status == StatusCode::UNAUTHORIZED {
+            if status == StatusCode::UNAUTHORIZED && !is_missing_scope(&body) {
                 token.force_refresh();
             }
         }
@@ -211,6 +212,20 @@ impl<'a> RequestBuilder<'a> {
     }
 }
 
+/// Test if the body is a Helix error about a missing scope.
+fn is_missing_scope(body: &Bytes) -> bool {
+    #[derive(Deserialize)]
+    struct Error {
+        #[serde(default)]
+        message: String,
+    }
+
+    match serde_json::from_slice::<Error>(body) {
+        Ok(e) => e.message.to_lowercase().contains("missing scope"),
+        Err(..) => false,
+    }
+}
+
 pub(crate) struct Response<B> {
     method: Method,
     url: Url,
@@ -219,6 +234,11 @@ pub(crate) struct Response<B> {
 }
 
 impl Response<Bytes> {
+    /// Test if the response indicates that the token is missing a scope.
+    pub(crate) fn is_missing_scope(&self) -> bool {
+        self.status == StatusCode::UNAUTHORIZED && is_missing_scope(&self.body)
+    }
+
     /// Expect a successful response.
     pub(crate) fn ok(self) -> Result<()> {
         if self.status.is_success() {
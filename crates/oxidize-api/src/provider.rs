@@ -29,7 +29,8 @@ pub struct User {
 }
 
 impl User {
-    fn from_api(api: crate::twitch::model::User) -> Self {
+    /// Construct user information from an API response.
+    pub fn from_api(api: crate::twitch::model::User) -> Self {
         Self {
             id: api.id,
             login: api.login,
@@ -4,7 +4,10 @@ mod gql;
 
 pub mod model;
 
-pub mod pubsub;
+pub mod eventsub;
+
+pub mod moderation;
+pub use self::moderation::Moderation;
 
 use anyhow::Result;
 use common::stream::Stream;
@@ -161,8 +164,8 @@ impl Twitch {
     pub async fn patch_redemptions(
         &self,
         broadcaster_id: &str,
-        redemption: &pubsub::Redemption,
-        status: pubsub::Status,
+        redemption: &eventsub::Redemption,
+        status: eventsub::Status,
     ) -> Result<()> {
         let mut req = self.new_api(
             Method::PATCH,
@@ -184,10 +187,25 @@ impl Twitch {
 
         #[derive(Serialize)]
         struct UpdateRedemption {
-            status: pubsub::Status,
+            status: eventsub::Status,
         }
     }
 
+    /// Create an EventSub subscription.
+    pub async fn create_eventsub_subscription(
+        &self,
+        request: &eventsub::transport::CreateSubscription<'_>,
+    ) -> Result<()> {
+        let body = serde_json::to_vec(request)?;
+
+        self.new_api(Method::POST, &["eventsub", "subscriptions"])
+            .header(header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .execute()
+            .await?
+            .ok()
+    }
+
     /// Get the channel associated with the current authentication.
     pub async fn user(&self) -> Result<model::User> {
         let req = self.new_api(Method::GET, &["users"]);
@@ -196,6 +214,14 @@ impl Twitch {
         Ok(user)
     }
 
+    /// Get the user with the specified login.
+    pub async fn user_by_login(&self, login: &str) -> Result<Option<model::User>> {
+        let mut req = self.new_api(Method::GET, &["users"]);
+        req.query_param("login", login);
+        let data = req.execute().await?.json::<Data<Vec<model::User>>>()?;
+        Ok(data.data.into_iter().next())
+    }
+
     /// Get the channel associated with the specified broadcaster id.
     pub async fn channels(&self, broadcaster_id: &str) -> Result<Option<model::Channel>> {
         let mut req = self.new_api(Method::GET, &["channels"]);
@@ -0,0 +1,979 @@
+//! Twitch EventSub integration over websockets.
+//!
+//! See <https://dev.twitch.tv/docs/eventsub/handling-websocket-events/>.
+
+use std::collections::{HashSet, VecDeque};
+use std::future::Future;
+use std::mem;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_fuse::Fuse;
+use async_injector::{Injector, Key};
+use backoff::backoff::Backoff;
+use chrono::{DateTime, Utc};
+use common::sink::SinkExt;
+use common::stream::Stream;
+use common::{tags, BoxStream};
+use serde::{Deserialize, Serialize};
+use tokio::time::{self, Sleep};
+use tokio_tungstenite::tungstenite;
+use tokio_tungstenite::tungstenite::client::IntoClientRequest;
+use tokio_tungstenite::tungstenite::http::Uri;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+use tracing::Instrument;
+
+const URL: &str = "wss://eventsub.wss.twitch.tv/ws";
+
+/// Slack added on top of the keepalive timeout communicated by the server
+/// before we consider the connection dead.
+const KEEPALIVE_SLACK: Duration = Duration::from_secs(5);
+
+/// The number of message ids to remember when detecting messages which have
+/// been sent more than once.
+const SEEN_CAPACITY: usize = 256;
+
+/// Websocket EventSub integration for twitch.
+#[derive(Clone)]
+pub struct EventSub {
+    bus: bus::Bus<Event>,
+}
+
+impl EventSub {
+    /// Subscribe to all events.
+    pub fn subscribe(&self) -> bus::Reader<Event> {
+        self.bus.subscribe()
+    }
+
+    /// Subscribe for redemptions.
+    pub fn redemptions(&self) -> TwitchStream<Redemption> {
+        use tokio::sync::broadcast::error::RecvError;
+
+        let mut s = self.bus.subscribe();
+
+        TwitchStream {
+            stream: Box::pin(async_stream::stream! {
+                loop {
+                    match s.recv().await {
+                        Ok(Event::Redemption(item)) => yield item,
+                        Ok(..) => (),
+                        Err(RecvError::Closed) => break,
+                        Err(RecvError::Lagged(..)) => (),
+                    }
+                }
+            }),
+        }
+    }
+}
+
+pub struct TwitchStream<T> {
+    stream: BoxStream<'static, T>,
+}
+
+impl<T> Stream for TwitchStream<T> {
+    type Item = T;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.stream.as_mut().poll_next(cx)
+    }
+}
+
+struct Client {
+    stream: WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>,
+}
+
+impl Client {
+    /// Connect to the given url.
+    async fn connect(url: &str) -> Result<Self> {
+        let uri = str::parse::<Uri>(url)?;
+        let req = uri.into_client_request()?;
+        let (stream, _) = tokio_tungstenite::connect_async(req).await?;
+        Ok(Self { stream })
+    }
+
+    /// Close the connection.
+    async fn close(&mut self) {
+        if let Err(e) = self.stream.close(None).await {
+            common::log_error!(e, "Error when closing stream");
+        }
+    }
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<String>>> {
+        loop {
+            let message = match Pin::new(&mut self.as_mut().stream).poll_next(cx)? {
+                Poll::Ready(message) => message,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            let message = match message {
+                Some(message) => message,
+                None => return Poll::Ready(None),
+            };
+
+            let text = match message {
+                tungstenite::Message::Text(text) => text,
+                tungstenite::Message::Close(..) => return Poll::Ready(None),
+                tungstenite::Message::Ping(..) | tungstenite::Message::Pong(..) => continue,
+                message => {
+                    tracing::warn!("Unhandled websocket message: {:?}", message);
+                    continue;
+                }
+            };
+
+            return Poll::Ready(Some(Ok(text)));
+        }
+    }
+}
+
+/// Action that needs to be taken in response to a frame.
+#[derive(Debug, PartialEq, Eq)]
+enum Action {
+    /// Nothing needs to be done.
+    None,
+    /// A new session was welcomed, subscriptions need to be created.
+    Subscribe { session_id: String },
+    /// An existing session was welcomed on a new connection, subscriptions
+    /// carry over.
+    Resumed,
+    /// The server asked us to reconnect to the given url.
+    Reconnect { url: String },
+}
+
+/// The protocol state of a single EventSub session.
+struct Session {
+    bus: bus::Bus<Event>,
+    /// Identifier of the welcomed session.
+    id: Option<String>,
+    /// Keepalive timeout communicated by the server.
+    keepalive: Duration,
+    /// Recently seen message ids.
+    seen: HashSet<String>,
+    /// Recently seen message ids in the order they were received, so that
+    /// the oldest can be forgotten.
+    seen_order: VecDeque<String>,
+}
+
+impl Session {
+    fn new(bus: bus::Bus<Event>) -> Self {
+        Self {
+            bus,
+            id: None,
+            keepalive: Duration::from_secs(10),
+            seen: HashSet::new(),
+            seen_order: VecDeque::new(),
+        }
+    }
+
+    /// Remember the given message id, returns `false` if it has already been
+    /// seen.
+    fn insert_seen(&mut self, id: &str) -> bool {
+        if self.seen.contains(id) {
+            return false;
+        }
+
+        if self.seen_order.len() >= SEEN_CAPACITY {
+            if let Some(old) = self.seen_order.pop_front() {
+                self.seen.remove(&old);
+            }
+        }
+
+        self.seen.insert(id.to_owned());
+        self.seen_order.push_back(id.to_owned());
+        true
+    }
+
+    /// Handle an incoming text frame.
+    async fn handle_text(&mut self, text: &str) -> Result<Action> {
+        let frame = match serde_json::from_str::<self::transport::Frame>(text) {
+            Ok(frame) => frame,
+            Err(e) => {
+                tracing::trace!("<< raw: {}", text);
+                return Err(e.into());
+            }
+        };
+
+        tracing::trace!("<< {:?}", frame);
+        self.handle_frame(frame).await
+    }
+
+    /// Handle an incoming frame.
+    async fn handle_frame(&mut self, frame: self::transport::Frame) -> Result<Action> {
+        use self::transport::MessageType;
+
+        // NB: Twitch sends messages at least once, so a message it's unsure
+        // we received is sent again with the same id.
+        if !self.insert_seen(&frame.metadata.message_id) {
+            tracing::trace!(id = %frame.metadata.message_id, "Ignoring duplicate message");
+            return Ok(Action::None);
+        }
+
+        match frame.metadata.message_type {
+            MessageType::SessionWelcome => {
+                let payload: self::transport::SessionPayload =
+                    serde_json::from_value(frame.payload)?;
+                let session = payload.session;
+
+                if let Some(seconds) = session.keepalive_timeout_seconds {
+                    self.keepalive = Duration::from_secs(seconds);
+                }
+
+                if self.id.as_deref() == Some(session.id.as_str()) {
+                    tracing::info!("Reconnected to Twitch EventSub!");
+                    return Ok(Action::Resumed);
+                }
+
+                tracing::info!("Connected to Twitch EventSub!");
+                self.id = Some(session.id.clone());
+
+                Ok(Action::Subscribe {
+                    session_id: session.id,
+                })
+            }
+            MessageType::SessionKeepalive => Ok(Action::None),
+            MessageType::SessionReconnect => {
+                let payload: self::transport::SessionPayload =
+                    serde_json::from_value(frame.payload)?;
+
+                let url = payload
+                    .session
+                    .reconnect_url
+                    .ok_or_else(|| anyhow!("reconnect without a url"))?;
+
+                Ok(Action::Reconnect { url })
+            }
+            MessageType::Notification => {
+                let notification: self::transport::Notification =
+                    serde_json::from_value(frame.payload)?;
+
+                let ty = notification.subscription.ty;
+
+                match Event::from_notification(&ty, notification.event)? {
+                    Some(event) => {
+                        self.bus.send(event).await;
+                    }
+                    None => {
+                        tracing::warn!(?ty, "Unsupported notification");
+                    }
+                }
+
+                Ok(Action::None)
+            }
+            MessageType::Revocation => {
+                let revocation: self::transport::Revocation =
+                    serde_json::from_value(frame.payload)?;
+                let subscription = revocation.subscription;
+
+                tracing::warn!(
+                    ty = ?subscription.ty,
+                    status = ?subscription.status,
+                    "Subscription revoked"
+                );
+
+                self.bus
+                    .send(Event::Revoked(Revoked {
+                        subscription_type: subscription.ty,
+                        status: subscription.status,
+                    }))
+                    .await;
+
+                Ok(Action::None)
+            }
+            MessageType::Unknown => {
+                tracing::warn!(metadata = ?frame.metadata, "Unsupported frame");
+                Ok(Action::None)
+            }
+        }
+    }
+}
+
+/// Connect to EventSub once available.
+#[tracing::instrument(skip_all)]
+pub fn connect<S>(
+    settings: &settings::Settings<S>,
+    injector: &Injector,
+) -> impl Future<Output = Result<()>>
+where
+    S: settings::Scope,
+{
+    task(settings.clone(), injector.clone()).in_current_span()
+}
+
+struct State {
+    enabled: bool,
+    session: Session,
+    client: Fuse<Client>,
+    /// Client connected to in response to a reconnect request, which replaces
+    /// `client` once it's been welcomed.
+    next_client: Fuse<Client>,
+    streamer: Option<crate::TwitchAndUser>,
+    keepalive_deadline: Fuse<Pin<Box<Sleep>>>,
+    reconnect: Fuse<Pin<Box<Sleep>>>,
+    reconnect_backoff: backoff::ExponentialBackoff,
+}
+
+impl State {
+    /// Disconnect and clear clients (if connected).
+    async fn disconnect(&mut self) {
+        if let Some(client) = self.next_client.as_inner_mut() {
+            client.close().await;
+        }
+
+        if let Some(client) = self.client.as_inner_mut() {
+            client.close().await;
+            tracing::info!("Disconnected from Twitch EventSub!");
+        }
+
+        self.next_client.clear();
+        self.client.clear();
+    }
+
+    /// Clear state.
+    async fn clear(&mut self) {
+        self.disconnect().await;
+        self.session.id = None;
+        self.keepalive_deadline.clear();
+        self.reconnect.clear();
+    }
+
+    /// An error happened, try to automatically recover the connection.
+    async fn recover(&mut self) {
+        self.clear().await;
+
+        // NB: if still enabled, set a reconnect.
+        if self.enabled {
+            tracing::info!("Attempting to reconnect");
+            let backoff = self.reconnect_backoff.next_backoff().unwrap_or_default();
+            tracing::warn!("Reconnecting in {:?}", backoff);
+            self.reconnect.set(Box::pin(time::sleep(backoff)));
+        }
+    }
+
+    /// Any frame counts as a sign of life, so push the keepalive deadline
+    /// forward.
+    fn set_keepalive_deadline(&mut self) {
+        self.keepalive_deadline.set(Box::pin(time::sleep(
+            self.session.keepalive + KEEPALIVE_SLACK,
+        )));
+    }
+
+    // Rebuild state from the current configuration.
+    async fn build(&mut self) {
+        self.clear().await;
+
+        if !self.enabled || self.streamer.is_none() {
+            return;
+        }
+
+        tracing::trace!("Connecting to Twitch EventSub");
+
+        match Client::connect(URL).await {
+            Ok(client) => {
+                self.client.set(client);
+            }
+            Err(e) => {
+                common::log_error!(e, "Failed to build EventSub client");
+                self.recover().await;
+            }
+        }
+    }
+
+    /// Handle a frame received over the current or the next client.
+    async fn handle_text(&mut self, text: &str, next: bool) -> Result<()> {
+        let action = self.session.handle_text(text).await;
+
+        // NB: armed after handling the frame, so that the keepalive timeout
+        // communicated in the welcome message is used.
+        self.set_keepalive_deadline();
+
+        match action? {
+            Action::None => {}
+            Action::Subscribe { session_id } => {
+                self.reconnect_backoff.reset();
+                self.subscribe(&session_id).await;
+            }
+            Action::Resumed => {
+                if next {
+                    mem::swap(&mut self.client, &mut self.next_client);
+
+                    if let Some(client) = self.next_client.as_inner_mut() {
+                        client.close().await;
+                    }
+
+                    self.next_client.clear();
+                }
+            }
+            Action::Reconnect { url } => {
+                tracing::info!("Reconnect requested");
+
+                match Client::connect(&url).await {
+                    Ok(client) => {
+                        self.next_client.set(client);
+                    }
+                    Err(e) => {
+                        common::log_error!(e, "Failed to connect to reconnect url");
+                        self.recover().await;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Create all subscriptions for the given session.
+    async fn subscribe(&self, session_id: &str) {
+        let Some(streamer) = self.streamer.as_ref() else {
+            return;
+        };
+
+        for request in self::transport::subscriptions(&streamer.user.id, session_id) {
+            tracing::trace!(ty = request.ty, "Subscribing");
+
+            if let Err(e) = streamer.client.create_eventsub_subscription(&request).await {
+                common::log_warn!(e, "Failed to subscribe to `{}`", request.ty);
+            }
+        }
+    }
+}
+
+async fn task<S>(settings: settings::Settings<S>, injector: Injector) -> Result<()>
+where
+    S: settings::Scope,
+{
+    let settings = settings.scoped("eventsub");
+
+    let (mut enabled_stream, enabled) = settings.stream::<bool>("enabled").or_default().await?;
+
+    let bus = bus::Bus::new();
+
+    injector.update(EventSub { bus: bus.clone() }).await;
+
+    let streamer_key = Key::<crate::TwitchAndUser>::tagged(tags::Twitch::Streamer)?;
+    let (mut streamer_stream, streamer) = injector.stream_key(&streamer_key).await;
+
+    let mut state = State {
+        enabled,
+        session: Session::new(bus),
+        client: Fuse::empty(),
+        next_client: Fuse::empty(),
+        streamer,
+        keepalive_deadline: Fuse::empty(),
+        reconnect: Fuse::empty(),
+        reconnect_backoff: {
+            let mut backoff = backoff::ExponentialBackoff::default();
+            backoff.current_interval = Duration::from_secs(5);
+            backoff.initial_interval = Duration::from_secs(5);
+            backoff.max_elapsed_time = None;
+            backoff
+        },
+    };
+
+    state.build().await;
+
+    loop {
+        tokio::select! {
+            message = state.client.as_pin_mut().poll_stream(Client::poll_next) => {
+                let message = match message {
+                    Some(Ok(message)) => message,
+                    Some(Err(e)) => {
+                        common::log_error!(e, "Error in websocket");
+                        state.recover().await;
+                        continue;
+                    }
+                    None => {
+                        tracing::error!("End of websocket stream");
+                        state.recover().await;
+                        continue;
+                    },
+                };
+
+                if let Err(e) = state.handle_text(&message, false).await {
+                    common::log_error!(e, "Failed to handle message");
+                }
+            }
+            message = state.next_client.as_pin_mut().poll_stream(Client::poll_next) => {
+                let message = match message {
+                    Some(Ok(message)) => message,
+                    Some(Err(e)) => {
+                        common::log_error!(e, "Error in reconnecting websocket");
+                        state.recover().await;
+                        continue;
+                    }
+                    None => {
+                        tracing::error!("End of reconnecting websocket stream");
+                        state.recover().await;
+                        continue;
+                    },
+                };
+
+                if let Err(e) = state.handle_text(&message, true).await {
+                    common::log_error!(e, "Failed to handle message");
+                }
+            }
+            enabled = enabled_stream.recv() => {
+                state.enabled = enabled;
+                state.build().await;
+            }
+            streamer = streamer_stream.recv() => {
+                state.streamer = streamer;
+                state.build().await;
+            }
+            _ = &mut state.reconnect => {
+                state.build().await;
+            }
+            _ = &mut state.keepalive_deadline => {
+                tracing::warn!("Did not receive keepalive in time!");
+                state.recover().await;
+            }
+        }
+    }
+}
+
+pub mod transport {
+    use serde::{Deserialize, Serialize};
+
+    /// Subscription types and their versions that we subscribe to.
+    const SUBSCRIPTIONS: &[(&str, &str, ConditionKind)] = &[
+        (
+            "channel.channel_points_custom_reward_redemption.add",
+            "1",
+            ConditionKind::Broadcaster,
+        ),
+        ("channel.follow", "2", ConditionKind::Moderator),
+        ("channel.subscribe", "1", ConditionKind::Broadcaster),
+        ("channel.cheer", "1", ConditionKind::Broadcaster),
+        ("channel.raid", "1", ConditionKind::ToBroadcaster),
+        ("channel.hype_train.begin", "1", ConditionKind::Broadcaster),
+        (
+            "channel.hype_train.progress",
+            "1",
+            ConditionKind::Broadcaster,
+        ),
+        ("channel.hype_train.end", "1", ConditionKind::Broadcaster),
+        ("stream.online", "1", ConditionKind::Broadcaster),
+        ("stream.offline", "1", ConditionKind::Broadcaster),
+    ];
+
+    #[derive(Debug, Clone, Copy)]
+    enum ConditionKind {
+        Broadcaster,
+        Moderator,
+        ToBroadcaster,
+    }
+
+    /// Build all subscription requests for the given broadcaster.
+    pub(super) fn subscriptions<'a>(
+        broadcaster_id: &'a str,
+        session_id: &'a str,
+    ) -> impl Iterator<Item = CreateSubscription<'a>> {
+        SUBSCRIPTIONS.iter().map(move |&(ty, version, kind)| {
+            let mut condition = Condition::default();
+
+            match kind {
+                ConditionKind::Broadcaster => {
+                    condition.broadcaster_user_id = Some(broadcaster_id);
+                }
+                ConditionKind::Moderator => {
+                    condition.broadcaster_user_id = Some(broadcaster_id);
+                    condition.moderator_user_id = Some(broadcaster_id);
+                }
+                ConditionKind::ToBroadcaster => {
+                    condition.to_broadcaster_user_id = Some(broadcaster_id);
+                }
+            }
+
+            CreateSubscription {
+                ty,
+                version,
+                condition,
+                transport: Transport {
+                    method: "websocket",
+                    session_id,
+                },
+            }
+        })
+    }
+
+    /// Request to create a subscription.
+    #[derive(Debug, Serialize)]
+    pub struct CreateSubscription<'a> {
+        #[serde(rename = "type")]
+        pub ty: &'a str,
+        pub version: &'a str,
+        pub condition: Condition<'a>,
+        pub transport: Transport<'a>,
+    }
+
+    #[derive(Debug, Default, Serialize)]
+    pub struct Condition<'a> {
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub broadcaster_user_id: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub moderator_user_id: Option<&'a str>,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        pub to_broadcaster_user_id: Option<&'a str>,
+    }
+
+    #[derive(Debug, Serialize)]
+    pub struct Transport<'a> {
+        pub method: &'a str,
+        pub session_id: &'a str,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Frame {
+        pub metadata: Metadata,
+        #[serde(default)]
+        pub payload: serde_json::Value,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Metadata {
+        pub message_id: String,
+        pub message_type: MessageType,
+    }
+
+    #[derive(Debug, Clone, Copy, Deserialize)]
+    pub enum MessageType {
+        #[serde(rename = "session_welcome")]
+        SessionWelcome,
+        #[serde(rename = "session_keepalive")]
+        SessionKeepalive,
+        #[serde(rename = "session_reconnect")]
+        SessionReconnect,
+        #[serde(rename = "notification")]
+        Notification,
+        #[serde(rename = "revocation")]
+        Revocation,
+        #[serde(other)]
+        Unknown,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct SessionPayload {
+        pub session: Session,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Session {
+        pub id: String,
+        #[serde(default)]
+        pub keepalive_timeout_seconds: Option<u64>,
+        #[serde(default)]
+        pub reconnect_url: Option<String>,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Notification {
+        pub subscription: Subscription,
+        pub event: serde_json::Value,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Revocation {
+        pub subscription: Subscription,
+    }
+
+    #[derive(Debug, Deserialize)]
+    pub struct Subscription {
+        pub id: String,
+        #[serde(rename = "type")]
+        pub ty: String,
+        pub version: String,
+        pub status: String,
+    }
+}
+
+/// Typed events received over EventSub.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+#[non_exhaustive]
+pub enum Event {
+    #[serde(rename = "redemption")]
+    Redemption(Redemption),
+    #[serde(rename = "follow")]
+    Follow(Follow),
+    #[serde(rename = "subscribe")]
+    Subscribe(Subscribe),
+    #[serde(rename = "cheer")]
+    Cheer(Cheer),
+    #[serde(rename = "raid")]
+    Raid(Raid),
+    #[serde(rename = "hype-train/begin")]
+    HypeTrainBegin(HypeTrain),
+    #[serde(rename = "hype-train/progress")]
+    HypeTrainProgress(HypeTrain),
+    #[serde(rename = "hype-train/end")]
+    HypeTrainEnd(HypeTrain),
+    #[serde(rename = "stream/online")]
+    StreamOnline(StreamOnline),
+    #[serde(rename = "stream/offline")]
+    StreamOffline(StreamOffline),
+    /// A subscription was revoked by Twitch, and no further events of the
+    /// given type will be received.
+    #[serde(rename = "revoked")]
+    Revoked(Revoked),
+}
+
+impl Event {
+    /// Decode the event of a notification with the given subscription type.
+    fn from_notification(ty: &str, event: serde_json::Value) -> Result<Option<Self>> {
+        Ok(Some(match ty {
+            "channel.channel_points_custom_reward_redemption.add" => {
+                Event::Redemption(serde_json::from_value(event)?)
+            }
+            "channel.follow" => Event::Follow(serde_json::from_value(event)?),
+            "channel.subscribe" => Event::Subscribe(serde_json::from_value(event)?),
+            "channel.cheer" => Event::Cheer(serde_json::from_value(event)?),
+            "channel.raid" => Event::Raid(serde_json::from_value(event)?),
+            "channel.hype_train.begin" => Event::HypeTrainBegin(serde_json::from_value(event)?),
+            "channel.hype_train.progress" => {
+                Event::HypeTrainProgress(serde_json::from_value(event)?)
+            }
+            "channel.hype_train.end" => Event::HypeTrainEnd(serde_json::from_value(event)?),
+            "stream.online" => Event::StreamOnline(serde_json::from_value(event)?),
+            "stream.offline" => Event::StreamOffline(serde_json::from_value(event)?),
+            _ => return Ok(None),
+        }))
+    }
+}
+
+impl bus::Message for Event {}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Reward {
+    pub id: String,
+    pub title: String,
+    pub cost: i64,
+    #[serde(default)]
+    pub prompt: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Redemption {
+    pub id: String,
+    pub broadcaster_user_id: String,
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    #[serde(default, deserialize_with = "empty_string")]
+    pub user_input: Option<String>,
+    pub status: Status,
+    pub reward: Reward,
+    pub redeemed_at: DateTime<Utc>,
+}
+
+/// Status of a redemption.
+///
+/// Helix expects these in uppercase, while EventSub sends them in lowercase.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Status {
+    #[serde(rename = "FULFILLED", alias = "fulfilled")]
+    Fulfilled,
+    #[serde(rename = "UNFULFILLED", alias = "unfulfilled")]
+    Unfulfilled,
+    #[serde(rename = "CANCELED", alias = "canceled")]
+    Canceled,
+    #[serde(rename = "UNKNOWN", alias = "unknown")]
+    Unknown,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Follow {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub broadcaster_user_id: String,
+    pub followed_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subscribe {
+    pub user_id: String,
+    pub user_login: String,
+    pub user_name: String,
+    pub broadcaster_user_id: String,
+    pub tier: String,
+    pub is_gift: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Cheer {
+    pub is_anonymous: bool,
+    #[serde(default)]
+    pub user_id: Option<String>,
+    #[serde(default)]
+    pub user_login: Option<String>,
+    #[serde(default)]
+    pub user_name: Option<String>,
+    pub broadcaster_user_id: String,
+    pub message: String,
+    pub bits: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Raid {
+    pub from_broadcaster_user_id: String,
+    pub from_broadcaster_user_login: String,
+    pub from_broadcaster_user_name: String,
+    pub to_broadcaster_user_id: String,
+    pub viewers: u64,
+}
+
+/// A hype train event. Progress and goal are only present while the train is
+/// running.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HypeTrain {
+    pub id: String,
+    pub broadcaster_user_id: String,
+    pub level: u32,
+    pub total: u64,
+    #[serde(default)]
+    pub progress: Option<u64>,
+    #[serde(default)]
+    pub goal: Option<u64>,
+    pub started_at: DateTime<Utc>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub ended_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamOnline {
+    pub id: String,
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+    pub started_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamOffline {
+    pub broadcaster_user_id: String,
+    pub broadcaster_user_login: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Revoked {
+    pub subscription_type: String,
+    pub status: String,
+}
+
+/// Deserializes an empty string as `None`.
+fn empty_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+where
+    D: serde::de::Deserializer<'de>,
+{
+    Ok(match <Option<String>>::deserialize(deserializer)? {
+        Some(string) if !string.is_empty() => Some(string),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::future::poll_fn;
+    use std::pin::Pin;
+
+    use anyhow::Result;
+    use common::sink::SinkExt;
+    use tokio::net::TcpListener;
+    use tokio_tungstenite::tungstenite;
+
+    use super::{Action, Client, Event, Session, Status};
+
+    /// Frames recorded from a live EventSub session.
+    const RECORDED: &str = include_str!("eventsub/recorded.jsonl");
+
+    /// Stand-in for the EventSub server which replays the recorded frames to
+    /// the first client that connects.
+    async fn replay(listener: TcpListener) -> Result<()> {
+        let (stream, _) = listener.accept().await?;
+        let mut ws = tokio_tungstenite::accept_async(stream).await?;
+
+        for line in RECORDED.lines().filter(|l| !l.trim().is_empty()) {
+            ws.send(tungstenite::Message::Text(line.to_owned())).await?;
+        }
+
+        ws.close(None).await?;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_replay_recorded_frames() -> Result<()> {
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = format!("ws://{}", listener.local_addr()?);
+        let server = tokio::spawn(replay(listener));
+
+        let bus = bus::Bus::new();
+        let mut events = bus.subscribe();
+        let mut session = Session::new(bus);
+
+        let mut client = Client::connect(&url).await?;
+        let mut actions = Vec::new();
+
+        while let Some(text) = poll_fn(|cx| Pin::new(&mut client).poll_next(cx)).await {
+            actions.push(session.handle_text(&text?).await?);
+        }
+
+        server.await??;
+
+        assert_eq!(
+            actions,
+            [
+                Action::Subscribe {
+                    session_id: String::from("AQoQILE98gtqShGmLD7AM6yJThAB")
+                },
+                Action::None,
+                Action::None,
+                Action::None,
+                Action::None,
+                Action::None,
+                Action::Reconnect {
+                    url: String::from("wss://eventsub.wss.twitch.tv?reconnect=true")
+                },
+                Action::Resumed,
+                Action::None,
+            ]
+        );
+
+        assert_eq!(session.keepalive, std::time::Duration::from_secs(10));
+
+        let Event::Redemption(redemption) = events.try_recv()? else {
+            panic!("expected redemption");
+        };
+
+        assert_eq!(redemption.reward.title, "Song Request");
+        assert_eq!(redemption.user_login, "setbac");
+        assert_eq!(
+            redemption.user_input.as_deref(),
+            Some("never gonna give you up")
+        );
+        assert_eq!(redemption.status, Status::Unfulfilled);
+
+        // NB: the redemption is sent twice, but only published once.
+
+        let Event::Follow(follow) = events.try_recv()? else {
+            panic!("expected follow");
+        };
+
+        assert_eq!(follow.user_login, "cool_user");
+
+        let Event::StreamOnline(online) = events.try_recv()? else {
+            panic!("expected stream online");
+        };
+
+        assert_eq!(online.broadcaster_user_login, "setbac");
+
+        let Event::Revoked(revoked) = events.try_recv()? else {
+            panic!("expected revocation");
+        };
+
+        assert_eq!(revoked.subscription_type, "channel.follow");
+        assert_eq!(revoked.status, "authorization_revoked");
+        assert!(events.try_recv().is_err());
+        Ok(())
+    }
+}
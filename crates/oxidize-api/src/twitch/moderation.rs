@@ -0,0 +1,219 @@
+//! Moderation actions performed through Helix.
+//!
+//! Twitch no longer honours moderation slash commands like `/ban` or
+//! `/delete` when sent over chat, so everything has to go through the API
+//! using the token of the moderating user.
+
+use std::time::Duration;
+
+use reqwest::{header, Method};
+use serde::Serialize;
+use thiserror::Error;
+
+use crate::base::RequestBuilder;
+use crate::twitch::{Twitch, BROADCASTER_ID};
+
+const MODERATOR_ID: &str = "moderator_id";
+
+/// The longest timeout permitted by Twitch.
+const MAX_TIMEOUT: Duration = Duration::from_secs(1_209_600);
+
+/// Error raised by a moderation action.
+#[derive(Debug, Error)]
+pub enum ModerationError {
+    /// The moderating user is missing the scope required for the action.
+    #[error("missing the `{0}` scope, the bot needs to be re-authenticated to grant it")]
+    MissingScope(&'static str),
+    /// Any other error.
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+/// Chat settings which can be changed by a moderator.
+///
+/// Settings which are `None` are left unchanged.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ChatSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_mode: Option<bool>,
+    /// Seconds users have to wait between messages.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub slow_mode_wait_time: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follower_mode: Option<bool>,
+    /// Minutes users have to have followed for to chat.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub follower_mode_duration: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub emote_mode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub subscriber_mode: Option<bool>,
+}
+
+/// The color of an announcement.
+#[derive(Debug, Default, Clone, Copy, Serialize)]
+pub enum AnnouncementColor {
+    #[default]
+    #[serde(rename = "primary")]
+    Primary,
+    #[serde(rename = "blue")]
+    Blue,
+    #[serde(rename = "green")]
+    Green,
+    #[serde(rename = "orange")]
+    Orange,
+    #[serde(rename = "purple")]
+    Purple,
+}
+
+/// Moderation service for a single channel.
+#[derive(Clone, Debug)]
+pub struct Moderation {
+    client: Twitch,
+    moderator_id: String,
+    broadcaster_id: String,
+}
+
+impl Moderation {
+    /// Construct a moderation service acting in the channel of the given
+    /// broadcaster, using a client authenticated as the given moderator.
+    pub fn new(client: Twitch, moderator_id: String, broadcaster_id: String) -> Self {
+        Self {
+            client,
+            moderator_id,
+            broadcaster_id,
+        }
+    }
+
+    /// Ban the given user.
+    pub async fn ban(&self, user_id: &str, reason: Option<&str>) -> Result<(), ModerationError> {
+        self.bans(user_id, None, reason).await
+    }
+
+    /// Time out the given user for the given duration.
+    pub async fn timeout(
+        &self,
+        user_id: &str,
+        duration: Duration,
+        reason: Option<&str>,
+    ) -> Result<(), ModerationError> {
+        let duration = duration.clamp(Duration::from_secs(1), MAX_TIMEOUT);
+        self.bans(user_id, Some(duration.as_secs()), reason).await
+    }
+
+    /// Unban, or remove the timeout for, the given user.
+    pub async fn unban(&self, user_id: &str) -> Result<(), ModerationError> {
+        let mut req = self.new_api(Method::DELETE, &["moderation", "bans"]);
+        req.query_param("user_id", user_id);
+        send(req, "moderator:manage:banned_users").await
+    }
+
+    /// Delete the message with the given id.
+    pub async fn delete_message(&self, message_id: &str) -> Result<(), ModerationError> {
+        let mut req = self.new_api(Method::DELETE, &["moderation", "chat"]);
+        req.query_param("message_id", message_id);
+        send(req, "moderator:manage:chat_messages").await
+    }
+
+    /// Clear all messages in chat.
+    pub async fn clear_chat(&self) -> Result<(), ModerationError> {
+        let req = self.new_api(Method::DELETE, &["moderation", "chat"]);
+        send(req, "moderator:manage:chat_messages").await
+    }
+
+    /// Update chat settings.
+    pub async fn update_chat_settings(
+        &self,
+        settings: &ChatSettings,
+    ) -> Result<(), ModerationError> {
+        let mut req = self.new_api(Method::PATCH, &["chat", "settings"]);
+        req.header(header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(settings).map_err(anyhow::Error::from)?);
+        send(req, "moderator:manage:chat_settings").await
+    }
+
+    /// Send an announcement.
+    pub async fn announcement(
+        &self,
+        message: &str,
+        color: AnnouncementColor,
+    ) -> Result<(), ModerationError> {
+        let body =
+            serde_json::to_vec(&Announcement { message, color }).map_err(anyhow::Error::from)?;
+
+        let mut req = self.new_api(Method::POST, &["chat", "announcements"]);
+        req.header(header::CONTENT_TYPE, "application/json")
+            .body(body);
+        return send(req, "moderator:manage:announcements").await;
+
+        #[derive(Serialize)]
+        struct Announcement<'a> {
+            message: &'a str,
+            color: AnnouncementColor,
+        }
+    }
+
+    /// Shout out the given broadcaster.
+    pub async fn shoutout(&self, to_broadcaster_id: &str) -> Result<(), ModerationError> {
+        let mut req = self.client.new_api(Method::POST, &["chat", "shoutouts"]);
+        req.query_param("from_broadcaster_id", &self.broadcaster_id)
+            .query_param("to_broadcaster_id", to_broadcaster_id)
+            .query_param(MODERATOR_ID, &self.moderator_id)
+            .empty_body();
+        send(req, "moderator:manage:shoutouts").await
+    }
+
+    /// Ban or time out a user.
+    async fn bans(
+        &self,
+        user_id: &str,
+        duration: Option<u64>,
+        reason: Option<&str>,
+    ) -> Result<(), ModerationError> {
+        let body = Body {
+            data: Ban {
+                user_id,
+                duration,
+                reason: reason.unwrap_or_default(),
+            },
+        };
+
+        let mut req = self.new_api(Method::POST, &["moderation", "bans"]);
+        req.header(header::CONTENT_TYPE, "application/json")
+            .body(serde_json::to_vec(&body).map_err(anyhow::Error::from)?);
+        return send(req, "moderator:manage:banned_users").await;
+
+        #[derive(Serialize)]
+        struct Body<'a> {
+            data: Ban<'a>,
+        }
+
+        #[derive(Serialize)]
+        struct Ban<'a> {
+            user_id: &'a str,
+            #[serde(skip_serializing_if = "Option::is_none")]
+            duration: Option<u64>,
+            reason: &'a str,
+        }
+    }
+
+    /// Construct a request scoped to the current broadcaster and moderator.
+    fn new_api<'a>(&'a self, method: Method, path: &[&str]) -> RequestBuilder<'a> {
+        let mut req = self.client.new_api(method, path);
+        req.query_param(BROADCASTER_ID, &self.broadcaster_id)
+            .query_param(MODERATOR_ID, &self.moderator_id);
+        req
+    }
+}
+
+/// Send a moderation request which requires the given scope.
+async fn send(req: RequestBuilder<'_>, scope: &'static str) -> Result<(), ModerationError> {
+    let res = req.execute().await?;
+
+    if res.is_missing_scope() {
+        return Err(ModerationError::MissingScope(scope));
+    }
+
+    res.ok()?;
+    Ok(())
+}
@@ -3,7 +3,7 @@ use std::fmt;
 use std::iter;
 use std::sync::Arc;
 
-use anyhow::{Context, Error, Result};
+use anyhow::{bail, Context, Error, Result};
 use chrono::{DateTime, Utc};
 use common::{Cooldown, Duration};
 use diesel::backend::Backend;
@@ -58,6 +58,40 @@ impl std::str::FromStr for RoleOrUser {
     }
 }
 
+/// A scope or a role, like `song` or `@subscriber`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopeOrRole {
+    Scope(Scope),
+    Role(Role),
+}
+
+impl fmt::Display for ScopeOrRole {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScopeOrRole::Scope(scope) => scope.fmt(fmt),
+            ScopeOrRole::Role(role) => role.fmt(fmt),
+        }
+    }
+}
+
+impl std::str::FromStr for ScopeOrRole {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with('@') {
+            match str::parse::<Role>(s)? {
+                Role::Unknown => bail!("no such role `{}`", s),
+                role => return Ok(ScopeOrRole::Role(role)),
+            }
+        }
+
+        match str::parse::<Scope>(s)? {
+            Scope::Unknown => bail!("no such scope `{}`", s),
+            scope => Ok(ScopeOrRole::Scope(scope)),
+        }
+    }
+}
+
 /// The kind of temporary grant.
 #[derive(Debug, Clone, Copy)]
 pub enum TemporaryKind {
@@ -623,6 +657,9 @@ scopes! {
     (ThemeEdit, "theme/edit"),
     (PromoEdit, "promo/edit"),
     (AliasEdit, "alias/edit"),
+    (BadWordsEdit, "bad-words/edit"),
+    (Trigger, "trigger"),
+    (TriggerEdit, "trigger/edit"),
     (Countdown, "countdown"),
     (GtavBypassCooldown, "gtav/bypass-cooldown"),
     (GtavRaw, "gtav/raw"),
@@ -631,11 +668,24 @@ scopes! {
     (CurrencyBoost, "currency/boost"),
     (CurrencyWindfall, "currency/windfall"),
     (WaterUndo, "water/undo"),
+    (Reminder, "reminder"),
+    (ReminderUndo, "reminder/undo"),
+    (Quote, "quote"),
+    (QuoteEdit, "quote/edit"),
+    (Counter, "counter"),
+    (CounterEdit, "counter/edit"),
+    (Raffle, "raffle"),
+    (RaffleEdit, "raffle/edit"),
+    (Bet, "bet"),
+    (BetEdit, "bet/edit"),
+    (Queue, "queue"),
+    (QueueEdit, "queue/edit"),
     (AuthPermit, "auth/permit"),
     (ChatBypassUrlWhitelist, "chat/bypass-url-whitelist"),
     (Time, "time"),
     (Poll, "poll"),
     (Weather, "weather"),
+    (Nuke, "nuke"),
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -1,15 +1,18 @@
+use std::borrow::Cow;
 use std::collections::HashMap;
 use std::sync::Arc;
 
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use common::models::{Song, State, Track, TrackId};
+use common::OwnedChannel;
 use serde::Serialize;
 use tokio::sync::broadcast;
 use tokio::sync::RwLock;
 
 pub trait Message: 'static + Clone + Send + Sync + Serialize {
     /// The ID of a bussed message.
-    fn id(&self) -> Option<&'static str> {
+    fn id(&self) -> Option<Cow<'static, str>> {
         None
     }
 }
@@ -23,7 +26,7 @@ where
 {
     subs: broadcast::Sender<T>,
     /// Latest instances of all messages.
-    latest: RwLock<HashMap<&'static str, T>>,
+    latest: RwLock<HashMap<Cow<'static, str>, T>>,
 }
 
 /// Bus system.
@@ -126,12 +129,92 @@ pub enum YouTube {
 
 impl Message for YouTube {
     /// Whether a message should be cached or not and under what key.
-    fn id(&self) -> Option<&'static str> {
+    fn id(&self) -> Option<Cow<'static, str>> {
         use self::YouTube::*;
 
         match *self {
-            YouTubeCurrent { .. } => Some("youtube/current"),
-            YouTubeVolume { .. } => Some("youtube/volume"),
+            YouTubeCurrent { .. } => Some(Cow::Borrowed("youtube/current")),
+            YouTubeVolume { .. } => Some(Cow::Borrowed("youtube/volume")),
+        }
+    }
+}
+
+/// The state of a single countdown.
+#[derive(Debug, Clone, Serialize)]
+pub struct CountdownState {
+    pub name: String,
+    /// The countdown rendered through its template.
+    pub text: String,
+    /// Total duration of the countdown in seconds.
+    pub duration: u64,
+    /// Elapsed seconds.
+    pub elapsed: u64,
+    /// Remaining seconds.
+    pub remaining: u64,
+    pub paused: bool,
+}
+
+/// Events for driving countdown overlays.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+#[non_exhaustive]
+pub enum Countdown {
+    /// All active countdowns.
+    #[serde(rename = "countdown/all")]
+    Countdowns { countdowns: Vec<CountdownState> },
+}
+
+impl Message for Countdown {
+    /// Whether a message should be cached or not and under what key.
+    fn id(&self) -> Option<Cow<'static, str>> {
+        use self::Countdown::*;
+
+        match *self {
+            Countdowns { .. } => Some(Cow::Borrowed("countdown/all")),
+        }
+    }
+}
+
+/// The state of a single loaded script.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptState {
+    pub channel: String,
+    /// Path to the script.
+    pub path: String,
+    /// Commands registered by the script.
+    pub commands: Vec<String>,
+    /// When the script was last loaded, or failed to load.
+    pub loaded_at: DateTime<Utc>,
+    /// Why the script failed to load, if it did.
+    pub load_error: Option<String>,
+    /// Problems found while loading the script which didn't prevent it from
+    /// loading, like commands which were already registered.
+    pub warnings: Vec<String>,
+    /// Number of consecutive faults, reset by a successful invocation.
+    pub faults: u32,
+    /// If the script has been disabled for faulting too many times.
+    pub disabled: bool,
+    /// The last error raised by the script.
+    pub last_error: Option<String>,
+}
+
+/// Events describing the state of loaded scripts.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+#[non_exhaustive]
+pub enum Script {
+    /// All loaded scripts.
+    #[serde(rename = "script/all")]
+    Scripts { scripts: Vec<ScriptState> },
+}
+
+impl Message for Script {
+    /// Whether a message should be cached or not and under what key.
+    fn id(&self) -> Option<Cow<'static, str>> {
+        use self::Script::*;
+
+        match *self {
+            Scripts { .. } => Some(Cow::Borrowed("script/all")),
         }
     }
 }
@@ -161,16 +244,46 @@ pub enum Global {
     },
     #[serde(rename = "song/modified")]
     SongModified,
+    /// A named counter was changed.
+    #[serde(rename = "counter")]
+    Counter {
+        channel: OwnedChannel,
+        name: String,
+        /// The new value of the counter, or `None` if it was deleted.
+        count: Option<i64>,
+    },
+    /// The open bet changed.
+    #[serde(rename = "bet")]
+    Bet {
+        channel: OwnedChannel,
+        /// The open bet, or `None` if it was resolved or cancelled.
+        bet: Option<Bet>,
+    },
+    /// The viewer queue changed.
+    #[serde(rename = "queue")]
+    Queue {
+        channel: OwnedChannel,
+        /// Everyone in the queue, in order.
+        users: Vec<QueueUser>,
+    },
+    /// The tally of a poll changed.
+    #[serde(rename = "poll")]
+    Poll { channel: OwnedChannel, poll: Poll },
 }
 
 impl Message for Global {
     /// Whether a message should be cached or not and under what key.
-    fn id(&self) -> Option<&'static str> {
+    fn id(&self) -> Option<Cow<'static, str>> {
         use self::Global::*;
 
         match *self {
-            SongProgress { .. } => Some("song/progress"),
-            SongCurrent { .. } => Some("song/current"),
+            SongProgress { .. } => Some(Cow::Borrowed("song/progress")),
+            SongCurrent { .. } => Some(Cow::Borrowed("song/current")),
+            Bet { ref channel, .. } => Some(Cow::Owned(format!("bet/{channel}"))),
+            Queue { ref channel, .. } => Some(Cow::Owned(format!("queue/{channel}"))),
+            // NB: keyed by channel only, so that closed polls are replaced
+            // rather than kept around forever.
+            Poll { ref channel, .. } => Some(Cow::Owned(format!("poll/{channel}"))),
             _ => None,
         }
     }
@@ -224,6 +337,59 @@ impl Global {
     }
 }
 
+/// A bet which viewers can stake currency on.
+#[derive(Debug, Clone, Serialize)]
+pub struct Bet {
+    pub title: String,
+    pub outcomes: Vec<BetOutcome>,
+    /// Unix timestamp in seconds at which the bet locks.
+    pub locks_at: i64,
+    /// If the bet is locked and no longer accepts stakes.
+    pub locked: bool,
+}
+
+/// A single outcome of a bet.
+#[derive(Debug, Clone, Serialize)]
+pub struct BetOutcome {
+    pub name: String,
+    /// The total amount staked on the outcome.
+    pub pool: i64,
+    /// The number of users that staked on the outcome.
+    pub users: u32,
+}
+
+/// A user in the viewer queue.
+#[derive(Debug, Clone, Serialize)]
+pub struct QueueUser {
+    pub user: String,
+    pub display_name: String,
+}
+
+/// A poll and its current tally.
+#[derive(Debug, Clone, Serialize)]
+pub struct Poll {
+    /// The id of the poll, as shown in chat.
+    pub id: String,
+    pub question: String,
+    pub options: Vec<PollOption>,
+    /// If votes are weighted by the currency of the voter.
+    pub weighted: bool,
+    /// Unix timestamp in seconds at which the poll closes, if it has a
+    /// duration.
+    pub closes_at: Option<i64>,
+    /// If the poll is closed and these are the final results.
+    pub closed: bool,
+}
+
+/// The tally for a single option of a poll.
+#[derive(Debug, Clone, Serialize)]
+pub struct PollOption {
+    pub key: String,
+    pub description: Option<String>,
+    pub votes: u32,
+    pub weight: i64,
+}
+
 /// Events for running commands externally.
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "type")]
@@ -235,7 +401,7 @@ pub enum Command {
 
 impl Message for Command {
     /// Whether a message should be cached or not and under what key.
-    fn id(&self) -> Option<&'static str> {
+    fn id(&self) -> Option<Cow<'static, str>> {
         None
     }
 }
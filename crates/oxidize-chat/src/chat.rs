@@ -1,3 +1,5 @@
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
 use std::path::{Path, PathBuf};
 use std::pin::{pin, Pin};
 use std::sync::Arc;
@@ -6,11 +8,12 @@ use std::time;
 use anyhow::{anyhow, bail, Context as _, Result};
 use async_fuse::Fuse;
 use async_injector::{Injector, Key, Provider};
-use auth::{Auth, Role, Scope};
+use auth::{Auth, Role, Scope, ScopeOrRole};
 use common::backoff;
 use common::irc::Tags;
 use common::stream::{Stream, StreamExt};
-use common::{tags, Channel, Cooldown};
+use common::{tags, Channel, Cooldown, OwnedChannel};
+use db::moderation_log::{Action as ModerationAction, Entry as ModerationEntry};
 use irc::client::{self, Client};
 use irc::proto::command::Command;
 use irc::proto::message::{Message, Tag};
@@ -24,8 +27,10 @@ use tokio::sync::{mpsc, Notify};
 use crate::chat_log;
 use crate::command;
 use crate::currency_admin;
+use crate::filters;
 use crate::idle;
 use crate::messages;
+use crate::moderation_log;
 use crate::module;
 use crate::reward_loop;
 use crate::script;
@@ -37,6 +42,8 @@ use crate::utils;
 const SERVER: &str = "irc.chat.twitch.tv";
 const TWITCH_TAGS_CAP: &str = "twitch.tv/tags";
 const TWITCH_COMMANDS_CAP: &str = "twitch.tv/commands";
+/// Timeout used for bad words which don't specify a duration.
+const DEFAULT_BAD_WORD_TIMEOUT: time::Duration = time::Duration::from_secs(600);
 
 /// The type of a pending command.
 type PendingOutput<'a> = (Result<()>, command::Context<'a>);
@@ -136,10 +143,14 @@ struct Setup {
     #[dependency]
     message_log: messagelog::MessageLog,
     #[dependency]
+    moderation_log: db::ModerationLog,
+    #[dependency]
     command_bus: bus::Bus<bus::Command>,
     #[dependency]
     global_bus: bus::Bus<bus::Global>,
     #[dependency]
+    script_bus: bus::Bus<bus::Script>,
+    #[dependency]
     settings: settings::Settings<::auth::Scope>,
 }
 
@@ -164,8 +175,10 @@ impl ChatLoop<'_> {
             auth,
             bad_words,
             message_log,
+            moderation_log,
             command_bus,
             global_bus,
+            script_bus,
             settings,
         } = setup;
 
@@ -183,16 +196,32 @@ impl ChatLoop<'_> {
         tracing::trace!("Streamer: {:?}", streamer.user.display_name);
         tracing::trace!("Bot: {:?}", bot.user.display_name);
 
-        let chat_channel = format!("#{}", streamer.user.login);
+        let chat_settings = settings.scoped("chat");
+
+        let (mut channels_stream, extra_channels) = chat_settings
+            .stream::<HashSet<String>>("channels")
+            .or_default()
+            .await?;
+
+        let channel_users = resolve_channels(&streamer, &extra_channels).await;
+
+        let primary_channel = Channel::from_string(&streamer.user.login).into_owned();
+
         injector
-            .update_key(Key::tagged(tags::Globals::Channel)?, chat_channel.clone())
+            .update_key(
+                Key::tagged(tags::Globals::Channel)?,
+                primary_channel.to_string(),
+            )
             .await;
 
         let access_token = bot.client.token().read().context("missing bot token")?.0;
 
         let irc_client_config = client::data::config::Config {
             nickname: Some(bot.user.login.to_string()),
-            channels: vec![chat_channel.clone()],
+            channels: channel_users
+                .iter()
+                .map(|c| format!("#{}", c.user.login))
+                .collect(),
             password: Some(format!("oauth:{}", access_token.as_str())),
             server: Some(String::from(SERVER)),
             port: Some(6697),
@@ -203,36 +232,17 @@ impl ChatLoop<'_> {
         let mut client = Client::from_config(irc_client_config).await?;
         client.identify()?;
 
-        let chat_settings = settings.scoped("chat");
-
         let url_whitelist_enabled = chat_settings.var("url-whitelist/enabled", true).await?;
         let bad_words_enabled = chat_settings.var("bad-words/enabled", false).await?;
+        let triggers_enabled = chat_settings.var("triggers/enabled", true).await?;
+        let known_bots = chat_settings
+            .var("triggers/bots", default_known_bots())
+            .await?;
         let sender_ty = chat_settings.var("sender-type", sender::Type::Chat).await?;
         let threshold = chat_settings.var("idle-detection/threshold", 5).await?;
-        let idle = idle::Idle::new(threshold);
 
         let nightbot = injector.var::<api::NightBot>().await;
 
-        let sender =
-            sender::Sender::new(sender_ty, chat_channel.clone(), client.sender(), nightbot)?;
-
-        let (stream_info, stream_info_future) =
-            stream_info::setup(streamer.clone(), stream_state_tx.clone());
-
-        let context_inner = Arc::new(command::ContextInner::new(
-            sender.clone(),
-            auth.scope_cooldowns(),
-            restart.clone(),
-        ));
-
-        let refresh_roles_future = refresh_roles(context_inner.clone(), streamer.clone());
-
-        let mut handlers = module::Handlers::default();
-
-        let channel = Channel::from_string(&streamer.user.login);
-
-        let mut scripts = script::load_dir(channel.as_ref(), db.clone(), script_dirs).await?;
-
         let (scripts_watch_tx, mut scripts_watch_rx) = mpsc::unbounded_channel();
 
         let _watcher = if !script_dirs.is_empty() {
@@ -251,38 +261,152 @@ impl ChatLoop<'_> {
             None
         };
 
+        let script_limits = script::Limits::new(&settings).await?;
+        let script_states = script::States::new(script_bus);
+
+        let mut channels = HashMap::new();
+        let mut scripts = HashMap::new();
+        let moderation_log = moderation_log::ModerationLog::new(moderation_log);
+
         let mut hook_futures = Vec::new();
+        let mut channel_futures = Vec::<Pin<Box<dyn Future<Output = Result<()>>>>>::new();
 
-        for module in modules {
-            tracing::trace!("Initializing module: {}", module.ty());
-
-            let result = module
-                .hook(module::HookContext {
-                    handlers: &mut handlers,
-                    tasks: &mut hook_futures,
-                    stream_info: &stream_info,
-                    idle: &idle,
-                    streamer: &streamer,
-                    sender: &sender,
-                    settings: &settings,
-                    injector,
-                })
-                .await;
+        for channel_streamer in channel_users {
+            let channel = Channel::from_string(&channel_streamer.user.login).into_owned();
+            let owned = channel == primary_channel;
+
+            tracing::trace!(?channel, owned, "Setting up channel");
+
+            // NB: NightBot can only ever send to the streamer's own channel.
+            let sender_ty = if owned {
+                sender_ty.clone()
+            } else {
+                settings::Var::new(sender::Type::Chat)
+            };
+
+            let sender = sender::Sender::new(
+                sender_ty,
+                channel.to_string(),
+                client.sender(),
+                nightbot.clone(),
+            )?;
+
+            let (stream_info, stream_info_future) = stream_info::setup(
+                channel_streamer.clone(),
+                owned.then(|| stream_state_tx.clone()),
+            );
+
+            channel_futures.push(Box::pin(stream_info_future));
+
+            let moderation = api::twitch::Moderation::new(
+                bot.client.clone(),
+                bot.user.id.clone(),
+                channel_streamer.user.id.clone(),
+            );
+
+            let context_inner = Arc::new(command::ContextInner::new(
+                sender.clone(),
+                moderation,
+                moderation_log.clone(),
+                auth.scope_cooldowns(),
+                restart.clone(),
+            ));
+
+            // NB: moderators and VIPs can only be listed by the broadcaster.
+            if owned {
+                channel_futures.push(Box::pin(refresh_roles(
+                    context_inner.clone(),
+                    channel_streamer.clone(),
+                )));
+            }
+
+            // NB: settings changed in other channels only apply to that
+            // channel, falling back to the settings of the streamer.
+            let channel_settings = if owned {
+                settings.clone()
+            } else {
+                settings.channel(&channel.to_string())
+            };
+
+            let idle = idle::Idle::new(threshold.clone());
+            let mut handlers = module::Handlers::default();
 
-            result.with_context(|| anyhow!("failed to initialize module: {}", module.ty()))?;
+            for module in modules {
+                if !owned && !module.per_channel() {
+                    continue;
+                }
+
+                tracing::trace!(?channel, "Initializing module: {}", module.ty());
+
+                let result = module
+                    .hook(module::HookContext {
+                        handlers: &mut handlers,
+                        tasks: &mut hook_futures,
+                        stream_info: &stream_info,
+                        idle: &idle,
+                        streamer: &channel_streamer,
+                        sender: &sender,
+                        settings: &channel_settings,
+                        injector,
+                    })
+                    .await;
+
+                result.with_context(|| {
+                    anyhow!(
+                        "failed to initialize module `{}` for {}",
+                        module.ty(),
+                        channel
+                    )
+                })?;
+            }
+
+            let services = script::Services {
+                channel: channel.clone(),
+                sink: script::Sink::Chat(sender.clone()),
+                hooks: Some(command::Hooks::new(context_inner.clone())),
+                limits: script_limits.clone(),
+                states: script_states.clone(),
+                currency: injector.var().await,
+                player: injector.var().await,
+                settings: channel_settings.clone(),
+                stream_info: stream_info.clone(),
+            };
+
+            scripts.insert(
+                channel.clone(),
+                script::load_dir(db.clone(), services, script_dirs).await?,
+            );
+
+            channels.insert(
+                channel,
+                ChannelState {
+                    streamer: channel_streamer,
+                    sender,
+                    stream_info,
+                    idle,
+                    handlers,
+                    context_inner,
+                },
+            );
         }
 
+        let primary = channels
+            .get(&primary_channel)
+            .context("missing streamer channel")?;
+
         let currency_handler = currency_admin::setup(injector).await?;
 
         let reward_loop_future = reward_loop::setup(
             streamer.clone(),
-            sender.clone(),
-            idle.clone(),
+            primary.sender.clone(),
+            primary.idle.clone(),
             injector.clone(),
             chat_settings.clone(),
             settings.clone(),
         );
 
+        let filters = filters::Filters::new(&chat_settings.scoped("filters")).await?;
+
         let (mut whitelisted_hosts_stream, whitelisted_hosts) = chat_settings
             .stream("whitelisted-hosts")
             .or_default()
@@ -306,6 +430,8 @@ impl ChatLoop<'_> {
 
         let (mut commands_stream, commands) = injector.stream().await;
         let (mut aliases_stream, aliases) = injector.stream().await;
+        let (mut triggers_stream, triggers) = injector.stream().await;
+        let (mut counters_stream, counters) = injector.stream().await;
 
         let mut pong_timeout = Fuse::empty();
 
@@ -314,12 +440,14 @@ impl ChatLoop<'_> {
         // Join all local tasks we are performing.
         common::local_join! {
             futures =>
-            stream_info_future,
-            refresh_roles_future,
             reward_loop_future,
             messages_future
         }
 
+        for future in &mut channel_futures {
+            futures.push(future.as_mut());
+        }
+
         for future in &mut hook_futures {
             futures.push(future.as_mut());
         }
@@ -331,29 +459,32 @@ impl ChatLoop<'_> {
         let mut hooks = common::Futures::default();
 
         let mut handler = Handler {
-            streamer: &streamer,
-            sender: sender.clone(),
+            channels: &channels,
+            primary,
+            sender: primary.sender.clone(),
             whitelisted_hosts,
             commands,
             bad_words: &bad_words,
+            filters: &filters,
+            moderation_log: &moderation_log,
             global_bus: &global_bus,
             aliases,
+            triggers,
+            counters,
             api_url: Arc::new(api_url),
             moderator_cooldown,
-            handlers: &handlers,
             scripts: &mut scripts,
-            idle: &idle,
             pong_timeout: &mut pong_timeout,
             bot: &bot,
             handler_shutdown: false,
-            stream_info: &stream_info,
             auth: &auth,
             currency_handler: &currency_handler,
             url_whitelist_enabled,
             bad_words_enabled,
+            triggers_enabled,
+            known_bots,
             chat_log: chat_log_builder.build()?,
             messages: &messages,
-            context_inner: &context_inner,
         };
 
         let mut outgoing = client
@@ -386,7 +517,7 @@ impl ChatLoop<'_> {
                 }
                 Some(ev) = scripts_watch_rx.recv() => {
                     if let Ok(ev) = ev {
-                        if let Err(e) = handler.handle_script_filesystem_event(ev) {
+                        if let Err(e) = handler.handle_script_filesystem_event(ev).await {
                             common::log_error!(e, "Failed to handle script filesystem event");
                         }
                     }
@@ -421,12 +552,22 @@ impl ChatLoop<'_> {
                     // If configuration state changes, force a reconnect.
                     leave.set(Fuse::new(tokio::time::sleep(time::Duration::from_secs(1))));
                 }
+                _ = channels_stream.recv() => {
+                    // Joined channels changed, reconnect to join them.
+                    leave.set(Fuse::new(tokio::time::sleep(time::Duration::from_secs(1))));
+                }
                 commands = commands_stream.recv() => {
                     handler.commands = commands;
                 }
                 aliases = aliases_stream.recv() => {
                     handler.aliases = aliases;
                 }
+                triggers = triggers_stream.recv() => {
+                    handler.triggers = triggers;
+                }
+                counters = counters_stream.recv() => {
+                    handler.counters = counters;
+                }
                 chat_log = chat_log_builder.update() => {
                     handler.chat_log = chat_log?;
                 }
@@ -498,11 +639,29 @@ impl ChatLoop<'_> {
     }
 }
 
+/// State associated with a single joined channel.
+struct ChannelState {
+    /// The user who owns the channel.
+    streamer: api::TwitchAndUser,
+    /// Queue for sending messages to the channel.
+    sender: sender::Sender,
+    /// Stream information for the channel.
+    stream_info: stream_info::StreamInfo,
+    /// Idle detection for the channel.
+    idle: idle::Idle,
+    /// Handlers for specific commands like `!skip`.
+    handlers: module::Handlers,
+    /// Shared context paramters.
+    context_inner: Arc<command::ContextInner>,
+}
+
 /// Handler for incoming messages.
 struct Handler<'a> {
-    /// Current Streamer.
-    streamer: &'a api::TwitchAndUser,
-    /// Queue for sending messages.
+    /// All joined channels.
+    channels: &'a HashMap<OwnedChannel, ChannelState>,
+    /// The streamer's own channel.
+    primary: &'a ChannelState,
+    /// Queue for sending messages to the streamer's own channel.
     sender: sender::Sender,
     /// Whitelisted hosts for links.
     whitelisted_hosts: HashSet<String>,
@@ -510,45 +669,48 @@ struct Handler<'a> {
     commands: Option<db::Commands>,
     /// Bad words.
     bad_words: &'a db::Words,
+    /// Configurable message filters.
+    filters: &'a filters::Filters,
+    /// Log of moderation actions.
+    moderation_log: &'a moderation_log::ModerationLog,
     /// For sending notifications.
     global_bus: &'a bus::Bus<bus::Global>,
     /// Aliases.
     aliases: Option<db::Aliases>,
+    /// Keyword auto-responders.
+    triggers: Option<db::Triggers>,
+    /// Named counters, accessible from command templates.
+    counters: Option<db::Counters>,
     /// Configured API URL.
     api_url: Arc<Option<String>>,
     /// Active moderator cooldown.
     moderator_cooldown: Option<Cooldown>,
-    /// Handlers for specific commands like `!skip`.
-    handlers: &'a module::Handlers,
-    /// Dynamic handlers.
-    scripts: &'a mut script::Scripts,
-    /// Build idle detection.
-    idle: &'a idle::Idle,
+    /// Dynamic handlers, per channel.
+    scripts: &'a mut HashMap<OwnedChannel, script::Scripts>,
     /// Pong timeout currently running.
     pong_timeout: &'a mut Fuse<Pin<Box<tokio::time::Sleep>>>,
     /// OAuth 2.0 Token used to authenticate with Chat.
     bot: &'a api::TwitchAndUser,
     /// Force a shutdown.
     handler_shutdown: bool,
-    /// Stream information.
-    stream_info: &'a stream_info::StreamInfo,
     /// Information about auth.
     auth: &'a Auth,
     /// Handler for currencies.
     currency_handler: &'a currency_admin::Handler,
     bad_words_enabled: settings::Var<bool>,
     url_whitelist_enabled: settings::Var<bool>,
+    triggers_enabled: settings::Var<bool>,
+    /// Logins of known bots, which triggers can be exempt from.
+    known_bots: settings::Var<HashSet<String>>,
     /// Handler for chat logs.
     chat_log: Option<chat_log::ChatLog>,
     /// Messages.
     messages: &'a messages::Messages,
-    /// Shared context paramters.
-    pub(crate) context_inner: &'a Arc<command::ContextInner>,
 }
 
 impl Handler<'_> {
     /// Handle filesystem event.
-    fn handle_script_filesystem_event(&mut self, ev: notify::Event) -> Result<()> {
+    async fn handle_script_filesystem_event(&mut self, ev: notify::Event) -> Result<()> {
         use notify::event::{CreateKind, EventKind::*, ModifyKind, RemoveKind, RenameMode};
 
         tracing::trace!("Filesystem event: {:?}", ev);
@@ -572,8 +734,15 @@ impl Handler<'_> {
 
                     let p = p.canonicalize()?;
 
-                    if let Err(e) = self.scripts.reload(&p) {
-                        common::log_error!(e, "Failed to reload: {}", p.display());
+                    for (channel, scripts) in self.scripts.iter_mut() {
+                        if let Err(e) = scripts.reload(&p).await {
+                            common::log_error!(
+                                e,
+                                "Failed to reload in {}: {}",
+                                channel,
+                                p.display()
+                            );
+                        }
                     }
                 }
             }
@@ -582,7 +751,10 @@ impl Handler<'_> {
                     tracing::info!("Unloading script: {}", p.display());
 
                     let p = p.canonicalize()?;
-                    self.scripts.unload(&p);
+
+                    for scripts in self.scripts.values_mut() {
+                        scripts.unload(&p).await;
+                    }
                 }
             }
         }
@@ -604,7 +776,7 @@ async fn process_command<'a>(
     global_bus: &'a bus::Bus<bus::Global>,
     currency_handler: &'a currency_admin::Handler,
     handlers: &'a module::Handlers,
-    scripts: &script::Scripts,
+    scripts: Option<&script::Scripts>,
     pending: &mut common::Futures<'a, PendingOutput<'a>>,
 ) -> Result<()> {
     match command {
@@ -659,7 +831,7 @@ async fn process_command<'a>(
                 return Ok(());
             }
 
-            if let Some(handler) = scripts.get(other) {
+            if let Some(handler) = scripts.and_then(|scripts| scripts.get(other)) {
                 if let Err(e) = handler.call(ctx.clone()).await {
                     ctx.respond("Sorry, something went wrong :(").await;
                     common::log_error!(e, "Error when processing command");
@@ -675,35 +847,150 @@ async fn process_command<'a>(
 
 impl<'a> Handler<'a> {
     /// Delete the given message.
-    fn delete_message(&self, user: &User) -> Result<()> {
+    async fn delete_message(&self, user: &User, message: &str, offence: &Offence) {
         let id = match &user.inner.tags.id {
             Some(id) => id,
-            None => return Ok(()),
+            None => return,
         };
 
         tracing::info!("Attempting to delete message: {}", id);
-        user.inner.sender.delete(id);
-        Ok(())
+
+        if let Err(e) = user.inner.context.moderation.delete_message(id).await {
+            common::log_error!(e, "Failed to delete message");
+            return;
+        }
+
+        self.record(user, ModerationAction::Delete, None, message, offence)
+            .await;
     }
 
-    /// Test if the message should be deleted.
-    async fn should_be_deleted(&self, user: &User, message: &str) -> bool {
+    /// Time out the user who sent the given message.
+    async fn timeout_user(
+        &self,
+        user: &User,
+        duration: time::Duration,
+        message: &str,
+        offence: &Offence,
+    ) {
+        let Some(user_id) = &user.inner.tags.user_id else {
+            return;
+        };
+
+        let result = user
+            .inner
+            .context
+            .moderation
+            .timeout(user_id, duration, Some(offence.reason))
+            .await;
+
+        if let Err(e) = result {
+            common::log_error!(e, "Failed to time out user");
+            return;
+        }
+
+        let duration = Some(duration.as_secs());
+
+        self.record(user, ModerationAction::Timeout, duration, message, offence)
+            .await;
+    }
+
+    /// Ban the user who sent the given message.
+    async fn ban_user(&self, user: &User, message: &str, offence: &Offence) {
+        let Some(user_id) = &user.inner.tags.user_id else {
+            return;
+        };
+
+        let result = user
+            .inner
+            .context
+            .moderation
+            .ban(user_id, Some(offence.reason))
+            .await;
+
+        if let Err(e) = result {
+            common::log_error!(e, "Failed to ban user");
+            return;
+        }
+
+        self.record(user, ModerationAction::Ban, None, message, offence)
+            .await;
+    }
+
+    /// Apply the given punishment to an offending message.
+    async fn apply(&self, user: &User, message: &str, offence: &Offence, punishment: Punishment) {
+        match punishment {
+            Punishment::Delete => {
+                self.delete_message(user, message, offence).await;
+            }
+            Punishment::Timeout(duration) => {
+                self.timeout_user(user, duration, message, offence).await;
+            }
+            Punishment::Ban => {
+                self.ban_user(user, message, offence).await;
+            }
+            Punishment::Log => {
+                self.record(user, ModerationAction::Flag, None, message, offence)
+                    .await;
+            }
+        }
+    }
+
+    /// Record a moderation action taken by the bot.
+    async fn record(
+        &self,
+        user: &User,
+        action: ModerationAction,
+        duration: Option<u64>,
+        message: &str,
+        offence: &Offence,
+    ) {
+        let mut entry = ModerationEntry::new(user.sender().channel(), true, action);
+        entry.user = user.name();
+        entry.duration = duration;
+        entry.reason = Some(offence.reason);
+        entry.source = Some(&offence.source);
+        entry.message = Some(message);
+        entry.message_id = user.inner.tags.id.as_deref();
+        self.moderation_log.record(entry).await;
+    }
+
+    /// Test if the message should be punished, and how.
+    ///
+    /// Bad words which are only logged are recorded here, and don't count as
+    /// an offence.
+    async fn test_offence(&self, user: &User, message: &str) -> Option<(Offence, Punishment)> {
         // Moderators can say whatever they want.
         if user.is_moderator() {
-            return false;
+            return None;
         }
 
         if self.bad_words_enabled.load().await {
-            if let Some(word) = self.test_bad_words(message).await {
-                if let Some(why) = word.why.as_ref() {
+            let live = user.inner.stream_info.is_live();
+
+            if let Some(word) = self.test_bad_words(message, live).await {
+                let punishment = match word.action {
+                    db::words::Action::Delete => Punishment::Delete,
+                    db::words::Action::Timeout => Punishment::Timeout(
+                        word.duration
+                            .map(time::Duration::from_secs)
+                            .unwrap_or(DEFAULT_BAD_WORD_TIMEOUT),
+                    ),
+                    db::words::Action::Ban => Punishment::Ban,
+                    db::words::Action::Log => Punishment::Log,
+                };
+
+                // Don't call out messages which are only logged.
+                let why = word.why.as_ref().filter(|_| punishment != Punishment::Log);
+
+                if let Some(why) = why {
                     let why = why.render_to_string(BadWordsVars {
                         name: user.display_name(),
-                        target: &self.streamer.user.login,
+                        target: user.streamer_login(),
                     });
 
                     match why {
                         Ok(why) => {
-                            self.sender.privmsg(&why).await;
+                            user.sender().privmsg(&why).await;
                         }
                         Err(e) => {
                             common::log_error!(e, "Failed to render response");
@@ -711,44 +998,111 @@ impl<'a> Handler<'a> {
                     }
                 }
 
-                return true;
+                let offence = Offence {
+                    reason: "bad word",
+                    source: word.word().to_owned(),
+                };
+
+                // NB: messages which are only logged are otherwise processed
+                // as usual.
+                if punishment == Punishment::Log {
+                    self.apply(user, message, &offence, punishment).await;
+                } else {
+                    return Some((offence, punishment));
+                }
             }
         }
 
         if !user.has_scope(Scope::ChatBypassUrlWhitelist).await
             && self.url_whitelist_enabled.load().await
-            && self.has_bad_link(message)
         {
-            return true;
+            if let Some(host) = self.find_bad_link(message) {
+                let offence = Offence {
+                    reason: "link",
+                    source: host,
+                };
+
+                return Some((offence, Punishment::Delete));
+            }
         }
 
-        false
+        None
     }
 
-    /// Test the message for bad words.
-    async fn test_bad_words(&self, message: &str) -> Option<Arc<db::Word>> {
-        let tester = self.bad_words.tester().await;
+    /// Test the message against configured filters.
+    async fn test_filters(&self, user: &User, message: &str) -> Option<filters::Violation> {
+        let real = user.real()?;
+
+        if real.is_moderator() {
+            return None;
+        }
+
+        self.filters
+            .test(
+                user.streamer_login(),
+                real.login(),
+                &real.roles(),
+                &user.inner.tags,
+                message,
+            )
+            .await
+    }
+
+    /// Punish the user for violating a filter, escalating for repeat
+    /// offenders.
+    async fn punish(&self, user: &User, message: &str, violation: filters::Violation) {
+        let Some(real) = user.real() else {
+            return;
+        };
+
+        let offence = Offence {
+            reason: violation.reason(),
+            source: violation.id().to_owned(),
+        };
+
+        let action = self
+            .filters
+            .escalate(user.streamer_login(), real.login())
+            .await;
+
+        tracing::info!(user = real.login(), ?violation, ?action, "Filter triggered");
 
-        for word in common::words::trimmed(message) {
-            if let Some(word) = tester.test(word) {
-                return Some(word);
+        match action {
+            filters::Action::Warn => {
+                let warning = self.messages.get(messages::FILTER_WARNING).await.to_owned();
+                user.respond(format!("{} ({})", warning, violation)).await;
+
+                self.record(user, ModerationAction::Warn, None, message, &offence)
+                    .await;
+            }
+            filters::Action::Delete => {
+                self.delete_message(user, message, &offence).await;
+            }
+            filters::Action::Timeout(duration) => {
+                self.timeout_user(user, duration.as_std(), message, &offence)
+                    .await;
             }
         }
+    }
 
-        None
+    /// Test the message for bad words.
+    async fn test_bad_words(&self, message: &str, live: bool) -> Option<Arc<db::Word>> {
+        let tester = self.bad_words.tester().await;
+        tester.test_message(message, live)
     }
 
-    /// Check if the given iterator has URLs that need to be
-    fn has_bad_link(&self, message: &str) -> bool {
+    /// Find the host of the first link in the message which isn't
+    /// whitelisted.
+    fn find_bad_link(&self, message: &str) -> Option<String> {
         for url in utils::Urls::new(message) {
             if let Some(host) = url.host_str() {
                 if !self.whitelisted_hosts.contains(host) {
-                    return true;
+                    return Some(host.to_owned());
                 }
             }
         }
 
-        false
+        None
     }
 
     /// Send a ping to the remote server.
@@ -764,6 +1118,7 @@ impl<'a> Handler<'a> {
     /// Process the given command.
     pub(crate) async fn process_message(
         &mut self,
+        channel: &'a ChannelState,
         user: &User,
         mut message: Arc<String>,
         pending: &mut common::Futures<'a, PendingOutput<'a>>,
@@ -772,7 +1127,7 @@ impl<'a> Handler<'a> {
         // Run message hooks.
         hooks.push({
             let user = user.clone();
-            let context_inner = self.context_inner;
+            let context_inner = &channel.context_inner;
             let message = message.clone();
 
             Box::pin(async move {
@@ -790,7 +1145,7 @@ impl<'a> Handler<'a> {
 
         // only non-moderators and non-streamer bumps the idle counter.
         if !user.is_streamer() {
-            self.idle.seen();
+            channel.idle.seen();
         }
 
         // NB: declared here to be in scope.
@@ -824,36 +1179,59 @@ impl<'a> Handler<'a> {
 
         let mut it = common::words::split(message.clone());
         let first = it.next();
+        let is_command = first.as_deref().is_some_and(|c| c.starts_with('!'));
 
         if let Some(commands) = self.commands.as_ref() {
             if let Some((command, captures)) = commands
                 .resolve(user.sender().channel(), first.as_deref(), &it)
                 .await
             {
-                if command.has_var("count") {
-                    commands.increment(&command).await?;
-                }
+                if let Some(charge) = self.admit_command(user, &command).await? {
+                    if command.has_var("count") {
+                        if let Err(e) = commands.increment(&command).await {
+                            charge.refund(user).await;
+                            return Err(e);
+                        }
+                    }
 
-                let vars = CommandVars {
-                    name: user.display_name(),
-                    target: &self.streamer.user.login,
-                    count: command.count(),
-                    captures,
-                };
+                    let counters = match self.counters.as_ref() {
+                        Some(counters) if command.has_var_path("counters") => {
+                            counters.snapshot(user.sender().channel()).await
+                        }
+                        _ => BTreeMap::new(),
+                    };
 
-                let response = command.render(&vars)?;
-                self.sender.privmsg(response).await;
+                    let vars = CommandVars {
+                        name: user.display_name(),
+                        target: &channel.streamer.user.login,
+                        count: command.count(),
+                        counters,
+                        captures,
+                    };
+
+                    match command.render(&vars) {
+                        Ok(response) => {
+                            channel.sender.privmsg(response).await;
+                        }
+                        Err(e) => {
+                            charge.refund(user).await;
+                            return Err(e);
+                        }
+                    }
+                }
             }
         }
 
         if let Some(command) = first {
             if let Some(command) = command.strip_prefix('!') {
+                let (command, it) = self.counter_shorthand(channel, command, it).await;
+
                 let ctx = command::Context {
                     api_url: self.api_url.clone(),
                     user: user.clone(),
                     it,
                     messages: self.messages,
-                    inner: self.context_inner,
+                    inner: &channel.context_inner,
                 };
 
                 let result = process_command(
@@ -861,8 +1239,8 @@ impl<'a> Handler<'a> {
                     ctx,
                     self.global_bus,
                     self.currency_handler,
-                    self.handlers,
-                    self.scripts,
+                    &channel.handlers,
+                    self.scripts.get(channel.sender.channel()),
                     pending,
                 )
                 .await;
@@ -873,13 +1251,202 @@ impl<'a> Handler<'a> {
             }
         }
 
-        if self.should_be_deleted(user, &message).await {
-            self.delete_message(user)?;
+        if let Some((offence, punishment)) = self.test_offence(user, &message).await {
+            self.apply(user, &message, &offence, punishment).await;
+            return Ok(());
+        }
+
+        if let Some(violation) = self.test_filters(user, &message).await {
+            self.punish(user, &message, violation).await;
+            return Ok(());
+        }
+
+        if !is_command {
+            self.fire_trigger(channel, user, &message).await?;
         }
 
         Ok(())
     }
 
+    /// Route `!deaths +1` to `!counter deaths +1`, as long as `deaths` is an
+    /// existing counter and not the name of another command.
+    async fn counter_shorthand<'m>(
+        &self,
+        channel: &ChannelState,
+        command: &'m str,
+        it: common::words::Split,
+    ) -> (&'m str, common::words::Split) {
+        let Some(counters) = self.counters.as_ref() else {
+            return (command, it);
+        };
+
+        let is_delta = it
+            .clone()
+            .next()
+            .filter(|arg| arg.starts_with(['+', '-']))
+            .is_some_and(|arg| arg.parse::<i64>().is_ok());
+
+        if !is_delta || channel.handlers.get(command).is_some() {
+            return (command, it);
+        }
+
+        let name = channel.sender.channel();
+        let scripts = self.scripts.get(name);
+
+        if scripts.is_some_and(|scripts| scripts.get(command).is_some()) {
+            return (command, it);
+        }
+
+        if counters.get(name, command).await.is_none() {
+            return (command, it);
+        }
+
+        let args = format!("{} {}", command, it.rest());
+        ("counter", common::words::split(Arc::new(args)))
+    }
+
+    /// Test if the user is allowed to use the given custom command right now,
+    /// charging its cost if it has one.
+    ///
+    /// Returns the charge made if the command should be run.
+    async fn admit_command(
+        &self,
+        user: &User,
+        command: &db::commands::Command,
+    ) -> Result<Option<Charge>> {
+        // Injected commands are not restricted.
+        let Some(real) = user.real() else {
+            return Ok(Some(Charge::default()));
+        };
+
+        let restrictions = &command.restrictions;
+
+        if let Some(requires) = &restrictions.requires {
+            let allowed = match str::parse::<ScopeOrRole>(requires) {
+                Ok(ScopeOrRole::Scope(scope)) => real.has_scope(scope).await,
+                Ok(ScopeOrRole::Role(role)) => {
+                    let roles = real.roles();
+                    role == Role::Everyone
+                        || roles.contains(&role)
+                        || roles.contains(&Role::Streamer)
+                }
+                Err(e) => {
+                    common::log_warn!(e, "Bad requirement for command `{}`", command.key);
+                    false
+                }
+            };
+
+            if !allowed {
+                tracing::trace!(
+                    user = real.login(),
+                    command = %command.key,
+                    "Not allowed to use command"
+                );
+                return Ok(None);
+            }
+        }
+
+        if !real.has_scope(Scope::BypassCooldowns).await {
+            if let Some(remaining) = command.cooldown_remaining(real.login()) {
+                respond!(
+                    user,
+                    "Cooldown in effect for !{}, please wait at least {}!",
+                    command.key.name,
+                    common::display::compact_duration(remaining),
+                );
+
+                return Ok(None);
+            }
+        }
+
+        let mut charge = Charge::default();
+
+        if let Some(cost) = restrictions.cost.filter(|cost| *cost > 0) {
+            let Some(currency) = self.currency_handler.currency.load().await else {
+                respond!(
+                    user,
+                    "No currency configured, so !{} can't be used",
+                    command.key.name
+                );
+                return Ok(None);
+            };
+
+            match currency
+                .balance_take(user.sender().channel(), real.login(), cost)
+                .await
+            {
+                Ok(()) => {}
+                Err(currency::BalanceTransferError::NoBalance) => {
+                    let balance = currency
+                        .balance_of(user.sender().channel(), real.login())
+                        .await?
+                        .unwrap_or_default()
+                        .balance;
+
+                    respond!(
+                        user,
+                        "You need at least {cost} {currency} to use !{command}, you currently have {balance} {currency}.",
+                        cost = cost,
+                        currency = currency.name,
+                        command = command.key.name,
+                        balance = balance,
+                    );
+
+                    return Ok(None);
+                }
+                Err(e) => return Err(e.into()),
+            }
+
+            charge = Charge {
+                currency: Some(currency),
+                amount: cost,
+            };
+        }
+
+        command.start_cooldown(real.login());
+        Ok(Some(charge))
+    }
+
+    /// Respond to the message with the highest priority trigger matching it,
+    /// if any.
+    async fn fire_trigger(&self, channel: &ChannelState, user: &User, message: &str) -> Result<()> {
+        let Some(triggers) = self.triggers.as_ref() else {
+            return Ok(());
+        };
+
+        if !self.triggers_enabled.load().await {
+            return Ok(());
+        }
+
+        // Only messages from actual users fire triggers.
+        let Some(real) = user.real() else {
+            return Ok(());
+        };
+
+        let bot = real.login() == self.bot.user.login
+            || self.known_bots.read().await.contains(real.login());
+
+        let message = db::triggers::Message {
+            text: message,
+            live: user.inner.stream_info.is_live(),
+            moderator: real.is_moderator(),
+            bot,
+        };
+
+        let Some(trigger) = triggers.fire(channel.sender.channel(), message).await else {
+            return Ok(());
+        };
+
+        let vars = TriggerVars {
+            name: user.display_name(),
+            target: &channel.streamer.user.login,
+        };
+
+        let response = trigger.render(&vars)?;
+        channel.sender.privmsg(response).await;
+        Ok(())
+    }
+
     /// Run the given raw command.
     pub(crate) async fn raw(
         &mut self,
@@ -888,20 +1455,21 @@ impl<'a> Handler<'a> {
         hook: &mut common::Futures<'a, HookOutput<'a>>,
     ) -> Result<()> {
         let tags = Tags::default();
+        let channel = self.primary;
 
         let user = User {
             inner: Arc::new(UserInner {
                 tags,
-                sender: self.sender.clone(),
+                sender: channel.sender.clone(),
                 principal: Principal::Injected,
-                streamer_login: self.streamer.user.login.clone(),
-                stream_info: self.stream_info.clone(),
+                streamer_login: channel.streamer.user.login.clone(),
+                stream_info: channel.stream_info.clone(),
                 auth: self.auth.clone(),
-                context: self.context_inner.clone(),
+                context: channel.context_inner.clone(),
             }),
         };
 
-        self.process_message(&user, Arc::new(message), pending, hook)
+        self.process_message(channel, &user, Arc::new(message), pending, hook)
             .await?;
         Ok(())
     }
@@ -915,7 +1483,14 @@ impl<'a> Handler<'a> {
         hooks: &mut common::Futures<'a, HookOutput<'a>>,
     ) -> Result<()> {
         match m.command {
-            Command::PRIVMSG(_, message) => {
+            Command::PRIVMSG(target, message) => {
+                let channels = self.channels;
+
+                let Some(channel) = channels.get(&*Channel::from_string(&target)) else {
+                    tracing::trace!(?target, "Message for channel which is not joined");
+                    return Ok(());
+                };
+
                 let message = Arc::new(message);
 
                 let tags = Tags::from_tags(m.tags.iter().flat_map(|tag| {
@@ -932,13 +1507,16 @@ impl<'a> Handler<'a> {
                 let login = Box::<str>::from(login);
 
                 if let Some(chat_log) = self.chat_log.as_ref().cloned() {
+                    let target = channel.sender.channel().to_owned();
                     let tags = tags.clone();
-                    let user = self.streamer.user.clone();
+                    let user = channel.streamer.user.clone();
                     let login = login.clone();
                     let message = message.clone();
 
                     task::spawn(Box::pin(async move {
-                        chat_log.observe(&tags, &user, &login, &message).await;
+                        chat_log
+                            .observe(&target, &tags, &user, &login, &message)
+                            .await;
                         Ok(())
                     }));
                 }
@@ -946,16 +1524,17 @@ impl<'a> Handler<'a> {
                 let user = User {
                     inner: Arc::new(UserInner {
                         tags,
-                        sender: self.sender.clone(),
+                        sender: channel.sender.clone(),
                         principal: Principal::User { login },
-                        streamer_login: self.streamer.user.login.clone(),
-                        stream_info: self.stream_info.clone(),
+                        streamer_login: channel.streamer.user.login.clone(),
+                        stream_info: channel.stream_info.clone(),
                         auth: self.auth.clone(),
-                        context: self.context_inner.clone(),
+                        context: channel.context_inner.clone(),
                     }),
                 };
 
-                self.process_message(&user, message, pending, hooks).await?;
+                self.process_message(channel, &user, message, pending, hooks)
+                    .await?;
             }
             Command::JOIN(channel, _, _) => {
                 let user = match &m.prefix {
@@ -997,15 +1576,34 @@ impl<'a> Handler<'a> {
             }
             Command::Raw(raw, tail) => match raw.as_str() {
                 "CLEARMSG" => {
+                    let Some(tags) = ClearMsgTags::from_tags(m.tags) else {
+                        return Ok(());
+                    };
+
                     if let Some(chat_log) = self.chat_log.as_ref() {
-                        if let Some(tags) = ClearMsgTags::from_tags(m.tags) {
-                            chat_log.message_log.delete_by_id(&tags.target_msg_id).await;
-                        }
+                        chat_log.message_log.delete_by_id(&tags.target_msg_id).await;
+                    }
+
+                    // NB: arguments are the channel followed by the message.
+                    if let Some(channel) = tail.first() {
+                        let channel = Channel::from_string(channel);
+                        let mut entry =
+                            ModerationEntry::new(&channel, false, ModerationAction::Delete);
+                        entry.user = tags.login.as_deref();
+                        entry.message = tail.get(1).map(String::as_str);
+                        entry.message_id = Some(&tags.target_msg_id);
+                        self.moderation_log.observe(entry).await;
                     }
                 }
                 "CLEARCHAT" => {
+                    let tags = ClearChatTags::from_tags(m.tags);
+
+                    // NB: arguments are the channel followed by the user, if
+                    // only a single user is being cleared.
+                    let user = tail.get(1);
+
                     if let Some(chat_log) = self.chat_log.as_ref() {
-                        match tail.first() {
+                        match user {
                             Some(user) => {
                                 chat_log.message_log.delete_by_user(user).await;
                             }
@@ -1014,6 +1612,21 @@ impl<'a> Handler<'a> {
                             }
                         }
                     }
+
+                    if let Some(channel) = tail.first() {
+                        let channel = Channel::from_string(channel);
+
+                        let action = match (user, tags.ban_duration) {
+                            (None, _) => ModerationAction::Clear,
+                            (Some(..), Some(..)) => ModerationAction::Timeout,
+                            (Some(..), None) => ModerationAction::Ban,
+                        };
+
+                        let mut entry = ModerationEntry::new(&channel, false, action);
+                        entry.user = user.map(String::as_str);
+                        entry.duration = tags.ban_duration;
+                        self.moderation_log.observe(entry).await;
+                    }
                 }
                 _ => {
                     tracing::trace!(?raw, ?tail, "Unhandled raw command");
@@ -1181,6 +1794,11 @@ impl User {
         &self.inner.sender
     }
 
+    /// Get the login of the streamer owning the channel the user is in.
+    pub fn streamer_login(&self) -> &str {
+        &self.inner.streamer_login
+    }
+
     /// Test if the current user is the given user.
     pub fn is(&self, name: &str) -> bool {
         self.real().map(|u| u.is(name)).unwrap_or(false)
@@ -1347,12 +1965,14 @@ where
 
 /// Tags associated with a CLEARMSG.
 struct ClearMsgTags {
+    login: Option<String>,
     target_msg_id: String,
 }
 
 impl ClearMsgTags {
     /// Extract tags from message.
     fn from_tags(tags: Option<Vec<Tag>>) -> Option<ClearMsgTags> {
+        let mut login = None;
         let mut target_msg_id = None;
 
         if let Some(tags) = tags {
@@ -1361,33 +1981,165 @@ impl ClearMsgTags {
                     continue;
                 };
 
-                if name.as_str() == "target-msg-id" {
-                    target_msg_id = Some(value);
+                match name.as_str() {
+                    "login" => login = Some(value),
+                    "target-msg-id" => target_msg_id = Some(value),
+                    _ => {}
                 }
             }
         }
 
         Some(ClearMsgTags {
+            login,
             target_msg_id: target_msg_id?,
         })
     }
 }
 
+/// Tags associated with a CLEARCHAT.
+struct ClearChatTags {
+    ban_duration: Option<u64>,
+}
+
+impl ClearChatTags {
+    /// Extract tags from message.
+    fn from_tags(tags: Option<Vec<Tag>>) -> ClearChatTags {
+        let mut ban_duration = None;
+
+        if let Some(tags) = tags {
+            for t in tags {
+                let Tag(name, Some(value)) = t else {
+                    continue;
+                };
+
+                if name.as_str() == "ban-duration" {
+                    ban_duration = str::parse::<u64>(&value).ok();
+                }
+            }
+        }
+
+        ClearChatTags { ban_duration }
+    }
+}
+
+/// The reason the bot is moderating a message.
+struct Offence {
+    /// Human readable reason.
+    reason: &'static str,
+    /// The filter or word which triggered the action.
+    source: String,
+}
+
+/// How to punish an offending message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Punishment {
+    /// Delete the message.
+    Delete,
+    /// Time out the user.
+    Timeout(time::Duration),
+    /// Ban the user.
+    Ban,
+    /// Only record the message in the moderation log.
+    Log,
+}
+
 #[derive(Serialize)]
 pub(crate) struct BadWordsVars<'a> {
     name: Option<&'a str>,
     target: &'a str,
 }
 
+#[derive(Serialize)]
+pub(crate) struct TriggerVars<'a> {
+    name: Option<&'a str>,
+    target: &'a str,
+}
+
 #[derive(Serialize)]
 pub(crate) struct CommandVars<'a> {
     name: Option<&'a str>,
     target: &'a str,
     count: i32,
+    counters: BTreeMap<String, i64>,
     #[serde(flatten)]
     captures: db::Captures<'a>,
 }
 
+/// Currency charged for using a custom command.
+#[derive(Default)]
+struct Charge {
+    currency: Option<currency::Currency>,
+    amount: i64,
+}
+
+impl Charge {
+    /// Give the charged currency back to the user.
+    async fn refund(self, user: &User) {
+        let (Some(currency), Some(real)) = (self.currency, user.real()) else {
+            return;
+        };
+
+        if let Err(e) = currency
+            .balance_add(user.sender().channel(), real.login(), self.amount)
+            .await
+        {
+            common::log_error!(e, "Failed to refund {} to {}", self.amount, real.login());
+        }
+    }
+}
+
+/// Logins of commonly used chat bots.
+fn default_known_bots() -> HashSet<String> {
+    [
+        "nightbot",
+        "streamelements",
+        "streamlabs",
+        "moobot",
+        "fossabot",
+        "wizebot",
+    ]
+    .into_iter()
+    .map(String::from)
+    .collect()
+}
+
+/// Resolve the users owning all channels to join, starting with the
+/// streamer's own channel.
+///
+/// Additional channels share the streamer's client, since the bot doesn't
+/// hold any tokens for them.
+async fn resolve_channels(
+    streamer: &api::TwitchAndUser,
+    extra: &HashSet<String>,
+) -> Vec<api::TwitchAndUser> {
+    let mut out = vec![streamer.clone()];
+
+    for login in extra {
+        let login = login.trim().trim_start_matches('#').to_lowercase();
+
+        if login.is_empty() || out.iter().any(|c| c.user.login == login) {
+            continue;
+        }
+
+        match streamer.client.user_by_login(&login).await {
+            Ok(Some(user)) => {
+                out.push(api::TwitchAndUser {
+                    user: Arc::new(api::User::from_api(user)),
+                    client: streamer.client.clone(),
+                });
+            }
+            Ok(None) => {
+                tracing::warn!(?login, "No such channel to join");
+            }
+            Err(e) => {
+                common::log_warn!(e, "Failed to look up channel: {}", login);
+            }
+        }
+    }
+
+    out
+}
+
 // Future to populate moderators and VIPs.
 #[tracing::instrument(skip_all)]
 async fn refresh_roles(
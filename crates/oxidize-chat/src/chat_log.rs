@@ -1,5 +1,6 @@
 use anyhow::Result;
 use common::irc::Tags;
+use common::Channel;
 use storage::Cache;
 
 pub(crate) struct Builder {
@@ -94,7 +95,14 @@ pub(crate) struct ChatLog {
 }
 
 impl ChatLog {
-    pub(crate) async fn observe(&self, tags: &Tags, user: &api::User, login: &str, message: &str) {
+    pub(crate) async fn observe(
+        &self,
+        channel: &Channel,
+        tags: &Tags,
+        user: &api::User,
+        login: &str,
+        message: &str,
+    ) {
         let rendered = match self.emotes.as_ref() {
             Some(emotes) => match emotes.render(tags, user, login, message).await {
                 Ok(rendered) => Some(rendered),
@@ -107,7 +115,7 @@ impl ChatLog {
         };
 
         self.message_log
-            .push_back(tags, login, message, rendered)
+            .push_back(channel, tags, login, message, rendered)
             .await;
     }
 }
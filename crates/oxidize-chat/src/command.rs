@@ -18,6 +18,7 @@ use tokio::sync::Notify;
 
 use crate::chat::User;
 use crate::messages;
+use crate::moderation_log;
 use crate::sender;
 
 /// An opaque identifier for a hook that has been inserted.
@@ -83,6 +84,10 @@ impl ContextNotify {
 pub(crate) struct ContextInner {
     /// Sender associated with the command.
     sender: sender::Sender,
+    /// Moderation service for the channel.
+    pub(crate) moderation: api::twitch::Moderation,
+    /// Log of moderation actions.
+    moderation_log: moderation_log::ModerationLog,
     /// Active scope cooldowns.
     scope_cooldowns: sync::Mutex<HashMap<Scope, Cooldown>>,
     /// A hook that can be installed to peek at all incoming messages.
@@ -98,11 +103,15 @@ pub(crate) struct ContextInner {
 impl ContextInner {
     pub(crate) fn new(
         sender: sender::Sender,
+        moderation: api::twitch::Moderation,
+        moderation_log: moderation_log::ModerationLog,
         scope_cooldowns: HashMap<Scope, Cooldown>,
         restart: Arc<Notify>,
     ) -> Self {
         Self {
             sender,
+            moderation,
+            moderation_log,
             scope_cooldowns: sync::Mutex::new(scope_cooldowns),
             message_hooks: Default::default(),
             moderators: Default::default(),
@@ -117,6 +126,37 @@ impl ContextInner {
     }
 }
 
+/// A handle to the message hooks of a channel.
+#[derive(Clone)]
+pub struct Hooks {
+    inner: Arc<ContextInner>,
+}
+
+impl Hooks {
+    pub(crate) fn new(inner: Arc<ContextInner>) -> Self {
+        Self { inner }
+    }
+
+    /// Setup the specified hook.
+    pub async fn insert<H>(&self, hook: H) -> HookId
+    where
+        H: MessageHook,
+    {
+        let mut hooks = self.inner.message_hooks.write().await;
+        let len = hooks.insert(Box::new(hook));
+        HookId(len)
+    }
+
+    /// Remove the specified hook.
+    pub async fn remove(&self, id: HookId) {
+        let mut hooks = self.inner.message_hooks.write().await;
+
+        if hooks.contains(id.0) {
+            let _ = hooks.remove(id.0);
+        }
+    }
+}
+
 /// Context for a single command invocation.
 #[derive(Clone)]
 pub struct Context<'a> {
@@ -138,6 +178,16 @@ impl<'a> Context<'a> {
         &self.inner.notify
     }
 
+    /// Access the moderation service for the channel.
+    pub fn moderation(&self) -> &api::twitch::Moderation {
+        &self.inner.moderation
+    }
+
+    /// Record a moderation action taken by the bot in the moderation log.
+    pub async fn log_moderation(&self, entry: db::moderation_log::Entry<'_>) {
+        self.inner.moderation_log.record(entry).await;
+    }
+
     /// Access the last known API url.
     pub fn api_url(&self) -> Option<&str> {
         self.api_url.as_deref()
@@ -153,17 +203,19 @@ impl<'a> Context<'a> {
     where
         H: MessageHook,
     {
-        let mut hooks = self.inner.message_hooks.write().await;
-        let len = hooks.insert(Box::new(hook));
-        HookId(len)
+        self.hooks().insert(hook).await
     }
 
     /// Setup the specified hook.
     pub async fn remove_hook(&self, id: HookId) {
-        let mut hooks = self.inner.message_hooks.write().await;
+        self.hooks().remove(id).await;
+    }
 
-        if hooks.contains(id.0) {
-            let _ = hooks.remove(id.0);
+    /// Get a handle to the message hooks of the channel, which can be used
+    /// to remove hooks after the command has been handled.
+    pub fn hooks(&self) -> Hooks {
+        Hooks {
+            inner: self.inner.clone(),
         }
     }
 
@@ -0,0 +1,403 @@
+//! Configurable filters for chat messages, with escalating punishments for
+//! repeat offenders.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::time::Instant;
+
+use anyhow::Result;
+use auth::Role;
+use common::irc::Tags;
+use common::Duration;
+
+/// Prune tracked users once this many are tracked.
+const PRUNE_THRESHOLD: usize = 512;
+
+/// The reason a message was caught by a filter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Violation {
+    Caps,
+    Symbols,
+    Emotes,
+    Repeat,
+    Length,
+    Zalgo,
+}
+
+impl Violation {
+    /// Get the identifier of the filter, as used in settings.
+    pub(crate) fn id(&self) -> &'static str {
+        match self {
+            Violation::Caps => "caps",
+            Violation::Symbols => "symbols",
+            Violation::Emotes => "emotes",
+            Violation::Repeat => "repeat",
+            Violation::Length => "length",
+            Violation::Zalgo => "zalgo",
+        }
+    }
+
+    /// Get a human readable reason for the violation.
+    pub(crate) fn reason(&self) -> &'static str {
+        match self {
+            Violation::Caps => "excessive caps",
+            Violation::Symbols => "symbol spam",
+            Violation::Emotes => "emote flood",
+            Violation::Repeat => "repeated messages",
+            Violation::Length => "message too long",
+            Violation::Zalgo => "zalgo text",
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.reason().fmt(f)
+    }
+}
+
+/// The punishment to hand out for an offence.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Action {
+    /// Warn the user.
+    Warn,
+    /// Delete the offending message.
+    Delete,
+    /// Time out the user, which also clears their messages from chat.
+    Timeout(Duration),
+}
+
+/// Settings shared by all filters.
+struct Common {
+    enabled: settings::Var<bool>,
+    exempt: settings::Var<HashSet<Role>>,
+}
+
+impl Common {
+    async fn new(settings: &settings::Settings<auth::Scope>) -> Result<Self> {
+        Ok(Self {
+            enabled: settings.var("enabled", false).await?,
+            exempt: settings.var("exempt", HashSet::new()).await?,
+        })
+    }
+
+    /// Test if the filter applies to a user with the given roles.
+    async fn applies(&self, roles: &[Role]) -> bool {
+        if !self.enabled.load().await {
+            return false;
+        }
+
+        let exempt = self.exempt.read().await;
+        !roles.iter().any(|r| exempt.contains(r))
+    }
+}
+
+/// A filter which triggers when the share of some class of characters is too
+/// high.
+struct Ratio {
+    common: Common,
+    min_length: settings::Var<u32>,
+    max: settings::Var<u32>,
+}
+
+impl Ratio {
+    async fn new(settings: &settings::Settings<auth::Scope>, max: u32) -> Result<Self> {
+        Ok(Self {
+            common: Common::new(settings).await?,
+            min_length: settings.var("min-length", 10).await?,
+            max: settings.var("max", max).await?,
+        })
+    }
+
+    /// Test if `matching` out of `total` characters exceeds the ratio.
+    async fn exceeded(&self, matching: usize, total: usize) -> bool {
+        if total == 0 || total < self.min_length.load().await as usize {
+            return false;
+        }
+
+        matching * 100 > total * self.max.load().await as usize
+    }
+}
+
+/// A filter which triggers when some count is above a limit.
+struct Limit {
+    common: Common,
+    max: settings::Var<u32>,
+}
+
+impl Limit {
+    async fn new(settings: &settings::Settings<auth::Scope>, max: u32) -> Result<Self> {
+        Ok(Self {
+            common: Common::new(settings).await?,
+            max: settings.var("max", max).await?,
+        })
+    }
+
+    async fn exceeded(&self, count: usize) -> bool {
+        count > self.max.load().await as usize
+    }
+}
+
+/// The last message seen by a user.
+struct LastMessage {
+    message: String,
+    count: u32,
+    at: Instant,
+}
+
+/// Offences committed by a user.
+struct Offences {
+    count: u32,
+    at: Instant,
+}
+
+/// Filters to apply to messages, and the state used to escalate punishments.
+pub(crate) struct Filters {
+    caps: Ratio,
+    symbols: Ratio,
+    emotes: Limit,
+    repeat: Limit,
+    repeat_window: settings::Var<Duration>,
+    length: Limit,
+    zalgo: Limit,
+    window: settings::Var<Duration>,
+    timeout: settings::Var<Duration>,
+    last_messages: parking_lot::Mutex<HashMap<(String, String), LastMessage>>,
+    offences: parking_lot::Mutex<HashMap<(String, String), Offences>>,
+}
+
+impl Filters {
+    /// Construct filters from the `chat/filters` settings scope.
+    pub(crate) async fn new(settings: &settings::Settings<auth::Scope>) -> Result<Self> {
+        Ok(Self {
+            caps: Ratio::new(&settings.scoped("caps"), 70).await?,
+            symbols: Ratio::new(&settings.scoped("symbols"), 50).await?,
+            emotes: Limit::new(&settings.scoped("emotes"), 10).await?,
+            repeat: Limit::new(&settings.scoped("repeat"), 2).await?,
+            repeat_window: settings.var("repeat/window", Duration::seconds(30)).await?,
+            length: Limit::new(&settings.scoped("length"), 300).await?,
+            zalgo: Limit::new(&settings.scoped("zalgo"), 2).await?,
+            window: settings
+                .var("escalation/window", Duration::seconds(600))
+                .await?,
+            timeout: settings
+                .var("escalation/timeout", Duration::seconds(60))
+                .await?,
+            last_messages: Default::default(),
+            offences: Default::default(),
+        })
+    }
+
+    /// Test the given message against all filters, returning the first one
+    /// which it violates.
+    pub(crate) async fn test(
+        &self,
+        channel: &str,
+        login: &str,
+        roles: &[Role],
+        tags: &Tags,
+        message: &str,
+    ) -> Option<Violation> {
+        // NB: always track the last message, so that the repeat filter has
+        // an accurate count once it's enabled.
+        let repeats = self.track_repeat(channel, login, message).await;
+
+        if self.repeat.common.applies(roles).await && self.repeat.exceeded(repeats).await {
+            return Some(Violation::Repeat);
+        }
+
+        if self.length.common.applies(roles).await
+            && self.length.exceeded(message.chars().count()).await
+        {
+            return Some(Violation::Length);
+        }
+
+        if self.zalgo.common.applies(roles).await
+            && self.zalgo.exceeded(max_combining_marks(message)).await
+        {
+            return Some(Violation::Zalgo);
+        }
+
+        let emotes = tags.emote_ranges().count();
+
+        if self.emotes.common.applies(roles).await && self.emotes.exceeded(emotes).await {
+            return Some(Violation::Emotes);
+        }
+
+        // Emotes like `LUL` shouldn't count towards caps or symbols.
+        let text = strip_emotes(tags, message);
+
+        if self.caps.common.applies(roles).await {
+            let letters = text.chars().filter(|c| c.is_alphabetic()).count();
+            let upper = text.chars().filter(|c| c.is_uppercase()).count();
+
+            if self.caps.exceeded(upper, letters).await {
+                return Some(Violation::Caps);
+            }
+        }
+
+        if self.symbols.common.applies(roles).await {
+            let total = text.chars().filter(|c| !c.is_whitespace()).count();
+            let symbols = text
+                .chars()
+                .filter(|c| !c.is_whitespace() && !c.is_alphanumeric())
+                .count();
+
+            if self.symbols.exceeded(symbols, total).await {
+                return Some(Violation::Symbols);
+            }
+        }
+
+        None
+    }
+
+    /// Register an offence by the given user and get the action to take.
+    ///
+    /// The first offence in a window is a warning, the second deletes the
+    /// message, and each one after that is a timeout which doubles in length.
+    pub(crate) async fn escalate(&self, channel: &str, login: &str) -> Action {
+        let window = self.window.load().await.as_std();
+        let timeout = self.timeout.load().await;
+        let now = Instant::now();
+
+        let count = {
+            let mut offences = self.offences.lock();
+
+            if offences.len() > PRUNE_THRESHOLD {
+                offences.retain(|_, o| now.duration_since(o.at) < window);
+            }
+
+            let o = offences
+                .entry((channel.to_owned(), login.to_owned()))
+                .or_insert(Offences { count: 0, at: now });
+
+            if now.duration_since(o.at) >= window {
+                o.count = 0;
+            }
+
+            o.count += 1;
+            o.at = now;
+            o.count
+        };
+
+        match count {
+            1 => Action::Warn,
+            2 => Action::Delete,
+            n => {
+                let factor = 1u64.checked_shl(n - 3).unwrap_or(u64::MAX);
+                let seconds = timeout.num_seconds().saturating_mul(factor);
+                Action::Timeout(Duration::seconds(seconds))
+            }
+        }
+    }
+
+    /// Track the last message of a user and return how many times in a row
+    /// it has been repeated.
+    async fn track_repeat(&self, channel: &str, login: &str, message: &str) -> usize {
+        let window = self.repeat_window.load().await.as_std();
+        let message = message.trim().to_lowercase();
+        let now = Instant::now();
+
+        let mut last_messages = self.last_messages.lock();
+
+        if last_messages.len() > PRUNE_THRESHOLD {
+            last_messages.retain(|_, m| now.duration_since(m.at) < window);
+        }
+
+        let key = (channel.to_owned(), login.to_owned());
+
+        match last_messages.get_mut(&key) {
+            Some(last) if last.message == message && now.duration_since(last.at) < window => {
+                last.count += 1;
+                last.at = now;
+                last.count as usize
+            }
+            _ => {
+                last_messages.insert(
+                    key,
+                    LastMessage {
+                        message,
+                        count: 1,
+                        at: now,
+                    },
+                );
+
+                1
+            }
+        }
+    }
+}
+
+/// Remove all emotes from the given message.
+fn strip_emotes(tags: &Tags, message: &str) -> String {
+    let ranges = tags
+        .emote_ranges()
+        .map(|(_, s, e)| (s, e))
+        .collect::<Vec<_>>();
+
+    if ranges.is_empty() {
+        return message.to_owned();
+    }
+
+    message
+        .chars()
+        .enumerate()
+        .filter(|(i, _)| !ranges.iter().any(|&(s, e)| s <= *i && *i <= e))
+        .map(|(_, c)| c)
+        .collect()
+}
+
+/// Find the longest run of stacked combining marks in the message.
+fn max_combining_marks(message: &str) -> usize {
+    let mut max = 0;
+    let mut current = 0;
+
+    for c in message.chars() {
+        if is_combining_mark(c) {
+            current += 1;
+            max = usize::max(max, current);
+        } else {
+            current = 0;
+        }
+    }
+
+    max
+}
+
+/// Test if the character is a combining mark, which are used to build zalgo
+/// text.
+fn is_combining_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{0483}'..='\u{0489}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{max_combining_marks, strip_emotes};
+    use common::irc::Tags;
+
+    #[test]
+    fn test_strip_emotes() {
+        let tags = Tags {
+            emotes: Some(String::from("25:0-4,18-22/1902:6-10")),
+            ..Tags::default()
+        };
+
+        assert_eq!("  HELLO ", strip_emotes(&tags, "Kappa Keepo HELLO Kappa"));
+        assert_eq!("hello", strip_emotes(&Tags::default(), "hello"));
+    }
+
+    #[test]
+    fn test_combining_marks() {
+        assert_eq!(0, max_combining_marks("hello"));
+        assert_eq!(1, max_combining_marks("Tiê\u{301}ng Việt"));
+        assert_eq!(4, max_combining_marks("h\u{30d}\u{34e}\u{31f}\u{320}ello"));
+    }
+}
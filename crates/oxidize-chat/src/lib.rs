@@ -14,6 +14,7 @@ pub use self::module::Module;
 pub mod stream_info;
 
 mod script;
+pub use self::script::{test_dir, TestOutcome, TestUser};
 
 mod utils;
 
@@ -23,6 +24,8 @@ pub mod messages;
 
 mod chat_log;
 mod currency_admin;
+mod filters;
+mod moderation_log;
 mod reward_loop;
 mod sender;
 pub use self::sender::Sender;
@@ -30,8 +30,12 @@ pub const AUTH_FAILED_RUDE: Required = Required {
     id: "auth-failed-rude",
     default: "Do you think this is a democracy? LUL",
 };
+pub const FILTER_WARNING: Required = Required {
+    id: "filter-warning",
+    default: "Please keep it down, next time your message will be removed",
+};
 
-const REQUIRED: [Required; 2] = [AUTH_FAILED, AUTH_FAILED_RUDE];
+const REQUIRED: [Required; 3] = [AUTH_FAILED, AUTH_FAILED_RUDE, FILTER_WARNING];
 
 const OPTIONAL: [Optional; 2] = [JOIN_CHAT, LEAVE_CHAT];
 
@@ -0,0 +1,69 @@
+//! Recording of moderation actions to the moderation log.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use db::moderation_log::{Action, Entry};
+
+/// How many actions taken by the bot to remember, so that their echoes in
+/// chat aren't recorded twice.
+const RECENT_CAPACITY: usize = 64;
+
+#[derive(Clone)]
+pub(crate) struct ModerationLog {
+    log: db::ModerationLog,
+    recent: Arc<parking_lot::Mutex<VecDeque<String>>>,
+}
+
+impl ModerationLog {
+    pub(crate) fn new(log: db::ModerationLog) -> Self {
+        Self {
+            log,
+            recent: Default::default(),
+        }
+    }
+
+    /// Record an action taken by the bot.
+    pub(crate) async fn record(&self, entry: Entry<'_>) {
+        if let Some(key) = echo_key(&entry) {
+            let mut recent = self.recent.lock();
+
+            if recent.len() >= RECENT_CAPACITY {
+                recent.pop_front();
+            }
+
+            recent.push_back(key);
+        }
+
+        if let Err(e) = self.log.push(entry).await {
+            common::log_error!(e, "Failed to record moderation action");
+        }
+    }
+
+    /// Record an action observed in chat, unless it's the echo of an action
+    /// taken by the bot.
+    pub(crate) async fn observe(&self, entry: Entry<'_>) {
+        if let Some(key) = echo_key(&entry) {
+            let mut recent = self.recent.lock();
+
+            if let Some(index) = recent.iter().position(|k| *k == key) {
+                recent.remove(index);
+                return;
+            }
+        }
+
+        if let Err(e) = self.log.push(entry).await {
+            common::log_error!(e, "Failed to record moderation action");
+        }
+    }
+}
+
+/// Key used to match an action taken by the bot with its echo in chat.
+fn echo_key(entry: &Entry<'_>) -> Option<String> {
+    match entry.action {
+        Action::Delete => Some(format!("delete:{}", entry.message_id?)),
+        Action::Timeout | Action::Ban => Some(format!("user:{}:{}", entry.channel, entry.user?)),
+        Action::Clear => Some(format!("clear:{}", entry.channel)),
+        Action::Warn | Action::Unban | Action::Flag => None,
+    }
+}
@@ -37,6 +37,8 @@ pub struct HookContext<'a, 'task> {
     pub idle: &'a idle::Idle,
     pub streamer: &'a api::TwitchAndUser,
     pub sender: &'a sender::Sender,
+    /// Settings, which are scoped to the channel for channels other than the
+    /// streamer's own.
     pub settings: &'a settings::Settings<::auth::Scope>,
     pub handlers: &'a mut Handlers,
     pub tasks: &'a mut Vec<BoxFuture<'task, Result<()>>>,
@@ -51,6 +53,15 @@ where
     /// Type of the module as a string to help with diagnostics.
     fn ty(&self) -> &'static str;
 
+    /// Test if the module should be hooked up for every joined channel, or
+    /// only for the streamer's own channel.
+    ///
+    /// Modules which drive state that is shared between channels, like the
+    /// player, should keep the default.
+    fn per_channel(&self) -> bool {
+        false
+    }
+
     /// Set up command handlers for this module.
     async fn hook(&self, _: HookContext<'_, '_>) -> Result<()>;
 }
@@ -4,8 +4,264 @@ mod mock;
 #[cfg(feature = "scripting")]
 mod real;
 
+#[cfg(feature = "scripting")]
+pub use self::real::test_dir;
 #[cfg(feature = "scripting")]
 pub(crate) use self::real::*;
 
+#[cfg(not(feature = "scripting"))]
+pub use self::mock::test_dir;
 #[cfg(not(feature = "scripting"))]
 pub(crate) use self::mock::*;
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use anyhow::Result;
+use chrono::Utc;
+use common::{Channel, Duration, OwnedChannel};
+use parking_lot::Mutex;
+
+use crate::command;
+use crate::sender;
+use crate::stream_info;
+
+/// The fake user which invokes scripts under test.
+#[derive(Debug, Clone, Default)]
+pub struct TestUser {
+    /// The name of the user.
+    pub name: String,
+    /// Roles of the user, like `@moderator`.
+    pub roles: Vec<String>,
+    /// Scopes the user has, like `song/request`.
+    pub scopes: Vec<String>,
+}
+
+/// The outcome of a single script test.
+#[derive(Debug)]
+pub struct TestOutcome {
+    /// The script the test belongs to.
+    pub path: PathBuf,
+    /// The name of the test, or `None` if the script failed to load.
+    pub name: Option<String>,
+    /// The error raised by the test, if it failed.
+    pub error: Option<String>,
+    /// Messages recorded through `respond` and `privmsg`.
+    pub output: Vec<String>,
+}
+
+/// Where messages sent by scripts end up.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+pub(crate) enum Sink {
+    /// Send messages to chat.
+    Chat(sender::Sender),
+    /// Record messages, used when testing scripts.
+    Record(Arc<Mutex<Vec<String>>>),
+}
+
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+impl Sink {
+    /// Send a message.
+    pub(crate) async fn privmsg(&self, message: &str) {
+        match self {
+            Sink::Chat(sender) => sender.privmsg(message).await,
+            Sink::Record(output) => output.lock().push(message.to_owned()),
+        }
+    }
+
+    /// Get all messages recorded so far.
+    pub(crate) fn recorded(&self) -> Vec<String> {
+        match self {
+            Sink::Chat(..) => Vec::new(),
+            Sink::Record(output) => output.lock().clone(),
+        }
+    }
+}
+
+/// Limits applied to every invocation of a script.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+pub(crate) struct Limits {
+    /// The number of instructions a single invocation may execute.
+    pub(crate) budget: settings::Var<u32>,
+    /// How long a single invocation may run for.
+    pub(crate) timeout: settings::Var<Duration>,
+    /// The number of consecutive faults after which a script is disabled.
+    pub(crate) max_faults: settings::Var<u32>,
+}
+
+impl Limits {
+    /// Set up limits from the `script` settings.
+    pub(crate) async fn new(settings: &settings::Settings<::auth::Scope>) -> Result<Self> {
+        let settings = settings.scoped("script");
+
+        Ok(Self {
+            budget: settings.var("budget", 1_000_000).await?,
+            timeout: settings.var("timeout", Duration::seconds(5)).await?,
+            max_faults: settings.var("max-faults", 3).await?,
+        })
+    }
+}
+
+/// The state of scripts loaded in all channels, which is published on the
+/// script bus.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+pub(crate) struct States {
+    bus: bus::Bus<bus::Script>,
+    states: Arc<Mutex<BTreeMap<(String, PathBuf), bus::ScriptState>>>,
+}
+
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+impl States {
+    pub(crate) fn new(bus: bus::Bus<bus::Script>) -> Self {
+        Self {
+            bus,
+            states: Default::default(),
+        }
+    }
+
+    /// Test if the given script has been disabled.
+    pub(crate) fn is_disabled(&self, channel: &Channel, path: &Path) -> bool {
+        let states = self.states.lock();
+
+        states
+            .get(&(channel.to_string(), path.to_owned()))
+            .is_some_and(|s| s.disabled)
+    }
+
+    /// Mark the given script as freshly loaded.
+    pub(crate) async fn loaded(
+        &self,
+        channel: &Channel,
+        path: &Path,
+        commands: Vec<String>,
+        warnings: Vec<String>,
+    ) {
+        self.insert(channel, path, commands, None, warnings).await;
+    }
+
+    /// Record that the given script failed to load.
+    pub(crate) async fn load_failed(&self, channel: &Channel, path: &Path, error: String) {
+        self.insert(channel, path, Vec::new(), Some(error), Vec::new())
+            .await;
+    }
+
+    async fn insert(
+        &self,
+        channel: &Channel,
+        path: &Path,
+        commands: Vec<String>,
+        load_error: Option<String>,
+        warnings: Vec<String>,
+    ) {
+        let state = bus::ScriptState {
+            channel: channel.to_string(),
+            path: path.display().to_string(),
+            commands,
+            loaded_at: Utc::now(),
+            load_error,
+            warnings,
+            faults: 0,
+            disabled: false,
+            last_error: None,
+        };
+
+        self.states
+            .lock()
+            .insert((channel.to_string(), path.to_owned()), state);
+        self.publish().await;
+    }
+
+    /// Remove the state of an unloaded script.
+    pub(crate) async fn unloaded(&self, channel: &Channel, path: &Path) {
+        let removed = self
+            .states
+            .lock()
+            .remove(&(channel.to_string(), path.to_owned()))
+            .is_some();
+
+        if removed {
+            self.publish().await;
+        }
+    }
+
+    /// Record that an invocation of the given script succeeded.
+    pub(crate) async fn succeeded(&self, channel: &Channel, path: &Path) {
+        let changed = match self
+            .states
+            .lock()
+            .get_mut(&(channel.to_string(), path.to_owned()))
+        {
+            Some(state) if state.faults > 0 => {
+                state.faults = 0;
+                true
+            }
+            _ => false,
+        };
+
+        if changed {
+            self.publish().await;
+        }
+    }
+
+    /// Record that an invocation of the given script faulted.
+    ///
+    /// Returns `true` if this caused the script to be disabled.
+    pub(crate) async fn faulted(
+        &self,
+        channel: &Channel,
+        path: &Path,
+        error: String,
+        max_faults: u32,
+    ) -> bool {
+        let disabled = {
+            let mut states = self.states.lock();
+
+            let Some(state) = states.get_mut(&(channel.to_string(), path.to_owned())) else {
+                return false;
+            };
+
+            state.faults += 1;
+            state.last_error = Some(error);
+
+            if !state.disabled && state.faults >= max_faults {
+                state.disabled = true;
+                true
+            } else {
+                false
+            }
+        };
+
+        self.publish().await;
+        disabled
+    }
+
+    /// Publish the state of all scripts.
+    async fn publish(&self) {
+        let scripts = self.states.lock().values().cloned().collect();
+        self.bus.send(bus::Script::Scripts { scripts }).await;
+    }
+}
+
+/// Services used by scripts. Most of them can only be accessed through the
+/// capabilities a script declares.
+#[derive(Clone)]
+#[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+pub(crate) struct Services {
+    /// The channel the scripts are loaded in.
+    pub(crate) channel: OwnedChannel,
+    /// Where messages sent by scripts end up.
+    pub(crate) sink: Sink,
+    /// Message hooks for the channel the scripts are loaded in, which are
+    /// absent when testing scripts.
+    pub(crate) hooks: Option<command::Hooks>,
+    pub(crate) limits: Limits,
+    pub(crate) states: States,
+    pub(crate) currency: async_injector::Ref<currency::Currency>,
+    pub(crate) player: async_injector::Ref<player::Player>,
+    pub(crate) settings: settings::Settings<::auth::Scope>,
+    pub(crate) stream_info: stream_info::StreamInfo,
+}
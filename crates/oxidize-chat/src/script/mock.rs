@@ -5,12 +5,16 @@
 
 use std::path::Path;
 
-use anyhow::Result;
-use common::Channel;
+use anyhow::{bail, Result};
 
 use crate::command;
+use crate::script::{Services, TestOutcome, TestUser};
 
-pub(crate) async fn load_dir<I>(_channel: &Channel, _db: db::Database, _paths: I) -> Result<Scripts>
+pub(crate) async fn load_dir<I>(
+    _db: db::Database,
+    _services: Services,
+    _paths: I,
+) -> Result<Scripts>
 where
     I: IntoIterator,
     I::Item: AsRef<Path>,
@@ -18,6 +22,18 @@ where
     Ok(Scripts(()))
 }
 
+pub async fn test_dir<I>(
+    _settings: settings::Settings<::auth::Scope>,
+    _user: TestUser,
+    _paths: I,
+) -> Result<Vec<TestOutcome>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+{
+    bail!("scripting support is not enabled")
+}
+
 pub(crate) struct Handler(());
 
 impl Handler {
@@ -34,13 +50,13 @@ impl Scripts {
     }
 
     #[tracing::instrument(skip(self))]
-    pub(crate) fn reload(&mut self, path: &Path) -> Result<()> {
+    pub(crate) async fn reload(&mut self, path: &Path) -> Result<()> {
         tracing::trace!("Reload");
         Ok(())
     }
 
     #[tracing::instrument(skip(self))]
-    pub(crate) fn unload(&mut self, path: &Path) {
+    pub(crate) async fn unload(&mut self, path: &Path) {
         tracing::trace!("Unload");
     }
 }
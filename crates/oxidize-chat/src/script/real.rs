@@ -1,12 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::ffi::OsStr;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
-use anyhow::{anyhow, Result};
-use common::Channel;
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use common::{Channel, Duration, OwnedChannel};
 use ignore::Walk;
-use rune::runtime::{ConstValue, Protocol, RuntimeContext, SyncFunction};
+use rune::runtime::budget;
+use rune::runtime::{
+    Args, ConstValue, Protocol, RuntimeContext, SyncFunction, VmError, VmErrorKind, VmHaltInfo,
+};
 use rune::termcolor;
 use rune::{
     Any, Context, ContextError, Diagnostics, FromValue, Module, Options, Source, Sources, Vm,
@@ -15,14 +19,111 @@ use rune::{
 use crate::chat;
 use crate::command;
 use crate::script::io;
+use crate::script::{Limits, Services, Sink, States, TestOutcome, TestUser};
+use crate::stream_info;
+use crate::task;
 
 /// Load all scripts from the given directory.
-pub(crate) async fn load_dir<I>(channel: &Channel, db: db::Database, paths: I) -> Result<Scripts>
+pub(crate) async fn load_dir<I>(db: db::Database, services: Services, paths: I) -> Result<Scripts>
+where
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+{
+    let mut scripts = Scripts::new(db, services).await?;
+
+    for path in script_paths(paths)? {
+        if let Err(e) = scripts.load(&path).await {
+            common::log_error!(e, "Failed to load script: {}", path.display())
+        }
+    }
+
+    Ok(scripts)
+}
+
+/// Run the tests registered by all scripts in the given directories.
+///
+/// Each test is called with a context for a fake user which records all
+/// messages sent through it, and storage which only lives in memory for the
+/// duration of the test.
+pub async fn test_dir<I>(
+    settings: settings::Settings<::auth::Scope>,
+    user: TestUser,
+    paths: I,
+) -> Result<Vec<TestOutcome>>
+where
+    I: IntoIterator,
+    I::Item: AsRef<Path>,
+{
+    let injector = async_injector::Injector::new();
+
+    let services = Services {
+        channel: Channel::from_string("test").into_owned(),
+        sink: Sink::Record(Default::default()),
+        hooks: None,
+        limits: Limits::new(&settings).await?,
+        states: States::new(bus::Bus::new()),
+        currency: injector.var().await,
+        player: injector.var().await,
+        settings,
+        stream_info: stream_info::StreamInfo::new(),
+    };
+
+    let mut scripts = Scripts::new(db::Database::memory()?, services.clone()).await?;
+    let user = Arc::new(user);
+    let mut outcomes = Vec::new();
+
+    for path in script_paths(paths)? {
+        if let Err(e) = scripts.load(&path).await {
+            outcomes.push(TestOutcome {
+                path,
+                name: None,
+                error: Some(format!("{:#}", e)),
+                output: Vec::new(),
+            });
+
+            continue;
+        }
+
+        let tests = match scripts.loaded.get(&path) {
+            Some(loaded) => loaded.tests.clone(),
+            None => continue,
+        };
+
+        for test in tests {
+            let sink = Sink::Record(Default::default());
+
+            let services = Services {
+                sink: sink.clone(),
+                ..services.clone()
+            };
+
+            let db = Db::new(&services.channel, db::Database::memory()?);
+            let ctx = test.ctx(&db, &services, Some(Invoker::Fake(user.clone())));
+
+            let error = match test.call_limited(&services.limits, (ctx,)).await {
+                Ok(()) => None,
+                Err(e) => Some(format!("{:#}", e)),
+            };
+
+            outcomes.push(TestOutcome {
+                path: path.clone(),
+                name: Some(test.name.clone()),
+                error,
+                output: sink.recorded(),
+            });
+        }
+    }
+
+    Ok(outcomes)
+}
+
+/// Find all scripts in the given directories.
+fn script_paths<I>(paths: I) -> Result<Vec<PathBuf>>
 where
     I: IntoIterator,
     I::Item: AsRef<Path>,
 {
-    let mut scripts = Scripts::new(channel, db).await?;
+    let mut out = Vec::new();
 
     for path in paths {
         let path = path.as_ref();
@@ -43,15 +144,60 @@ where
                 continue;
             }
 
-            let path = path.canonicalize()?;
+            out.push(path.canonicalize()?);
+        }
+    }
 
-            if let Err(e) = scripts.load(&path) {
-                common::log_error!(e, "Failed to load script: {}", path.display())
-            }
+    Ok(out)
+}
+
+/// The user invoking a script.
+#[derive(Clone)]
+enum Invoker {
+    /// A user in chat.
+    Chat(chat::User),
+    /// A fake user, used when testing scripts.
+    Fake(Arc<TestUser>),
+}
+
+/// A capability which a script must declare before it can use it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Capability {
+    /// Read and modify balances in the stream currency.
+    Currency,
+    /// Access the current song and the song queue.
+    Player,
+    /// Read settings which are not secret.
+    Settings,
+    /// Access information about the stream, like whether it is live.
+    Stream,
+    /// Access the roles and scopes of the invoking user.
+    Roles,
+}
+
+impl Capability {
+    /// Parse a capability from the name used in scripts.
+    fn parse(s: &str) -> Option<Self> {
+        match s {
+            "currency" => Some(Capability::Currency),
+            "player" => Some(Capability::Player),
+            "settings" => Some(Capability::Settings),
+            "stream" => Some(Capability::Stream),
+            "roles" => Some(Capability::Roles),
+            _ => None,
         }
     }
 
-    Ok(scripts)
+    /// The name of the capability used in scripts.
+    fn as_str(self) -> &'static str {
+        match self {
+            Capability::Currency => "currency",
+            Capability::Player => "player",
+            Capability::Settings => "settings",
+            Capability::Stream => "stream",
+            Capability::Roles => "roles",
+        }
+    }
 }
 
 struct InternalHandler {
@@ -59,6 +205,211 @@ struct InternalHandler {
     function: SyncFunction,
     path: PathBuf,
     sources: Arc<Sources>,
+    /// Capabilities declared by the script the handler belongs to.
+    capabilities: Arc<HashSet<Capability>>,
+    /// The storage namespace of the script the handler belongs to.
+    namespace: Arc<str>,
+}
+
+impl InternalHandler {
+    /// Construct a context to pass to the handler.
+    fn ctx(&self, db: &Db, services: &Services, user: Option<Invoker>) -> Ctx {
+        Ctx {
+            channel: services.channel.clone(),
+            user,
+            db: db.scoped(&self.namespace),
+            services: services.clone(),
+            capabilities: self.capabilities.clone(),
+        }
+    }
+
+    /// Call the handler with the given arguments.
+    ///
+    /// Faults are recorded, and once a script has faulted too many times in a
+    /// row it is disabled and its handlers are no longer called.
+    async fn call<A>(&self, services: &Services, args: A) -> Result<()>
+    where
+        A: Send + Args,
+    {
+        let channel = &services.channel;
+
+        if services.states.is_disabled(channel, &self.path) {
+            return Ok(());
+        }
+
+        let result = self.call_limited(&services.limits, args).await;
+
+        match &result {
+            Ok(()) => {
+                services.states.succeeded(channel, &self.path).await;
+            }
+            Err(e) => {
+                let max_faults = services.limits.max_faults.load().await;
+
+                if services
+                    .states
+                    .faulted(channel, &self.path, e.to_string(), max_faults)
+                    .await
+                {
+                    tracing::warn!(
+                        "Disabled script after {} consecutive faults: {}",
+                        max_faults,
+                        self.path.display()
+                    );
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Call the handler with the given arguments, aborting it if it exceeds
+    /// its instruction budget or runs for too long.
+    ///
+    /// The timeout only applies while the script is waiting, a script which
+    /// is busy without yielding is stopped by the instruction budget.
+    async fn call_limited<A>(&self, limits: &Limits, args: A) -> Result<()>
+    where
+        A: Send + Args,
+    {
+        let budget = limits.budget.load().await;
+        let timeout = limits.timeout.load().await;
+
+        let future = budget::with(budget as usize, self.function.async_send_call(args));
+
+        let Ok(result) = tokio::time::timeout(timeout.as_std(), future).await else {
+            bail!(
+                "handler for: {} in {} timed out after {}",
+                self.name,
+                self.path.display(),
+                timeout
+            );
+        };
+
+        let result: Result<(), ConstValue> = match result {
+            Ok(result) => result,
+            Err(error) => {
+                if let VmErrorKind::Halted {
+                    halt: VmHaltInfo::Limited,
+                } = error.kind()
+                {
+                    bail!(
+                        "handler for: {} in {} exceeded its budget of {} instructions",
+                        self.name,
+                        self.path.display(),
+                        budget
+                    );
+                }
+
+                let mut buffer = termcolor::Buffer::no_color();
+                error.emit(&mut buffer, &self.sources)?;
+
+                return Err(anyhow!(
+                    "failed to call handler for: {}:\n{}",
+                    self.name,
+                    String::from_utf8(buffer.into_inner())?
+                ));
+            }
+        };
+
+        match result {
+            Ok(()) => Ok(()),
+            Err(e) => Err(anyhow!(
+                "error when calling handler: {}: {:?}",
+                self.name,
+                e
+            )),
+        }
+    }
+}
+
+/// A message hook registered by a script.
+struct ScriptHook {
+    handler: Arc<InternalHandler>,
+    db: Db,
+    services: Services,
+}
+
+#[async_trait]
+impl command::MessageHook for ScriptHook {
+    async fn peek(&self, user: &chat::User, m: &str) -> Result<()> {
+        let ctx = self
+            .handler
+            .ctx(&self.db, &self.services, Some(Invoker::Chat(user.clone())));
+        self.handler.call(&self.services, (ctx, m.to_owned())).await
+    }
+}
+
+/// Everything registered by a single script, so that it can be unregistered
+/// when the script is unloaded.
+#[derive(Default)]
+struct Loaded {
+    commands: Vec<String>,
+    hooks: Vec<command::HookId>,
+    tasks: Vec<task::Handle<()>>,
+    tests: Vec<Arc<InternalHandler>>,
+}
+
+impl Drop for Loaded {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+/// Call the given handler at a fixed interval.
+async fn run_interval(
+    handler: Arc<InternalHandler>,
+    interval: Duration,
+    db: Db,
+    services: Services,
+) -> Result<()> {
+    let mut interval = tokio::time::interval(interval.as_std());
+    // NB: the first tick completes immediately.
+    interval.tick().await;
+
+    loop {
+        interval.tick().await;
+
+        let ctx = handler.ctx(&db, &services, None);
+
+        if let Err(e) = handler.call(&services, (ctx,)).await {
+            common::log_error!(e, "Failed to run interval in {}", handler.path.display());
+        }
+    }
+}
+
+/// Call the given handlers when the stream goes live or offline.
+async fn run_stream_callbacks(
+    start: Vec<Arc<InternalHandler>>,
+    stop: Vec<Arc<InternalHandler>>,
+    db: Db,
+    services: Services,
+) -> Result<()> {
+    let mut live = services.stream_info.subscribe_live();
+
+    loop {
+        live.changed().await?;
+
+        let handlers = if *live.borrow_and_update() {
+            &start
+        } else {
+            &stop
+        };
+
+        for handler in handlers {
+            let ctx = handler.ctx(&db, &services, None);
+
+            if let Err(e) = handler.call(&services, (ctx,)).await {
+                common::log_error!(
+                    e,
+                    "Failed to run stream callback in {}",
+                    handler.path.display()
+                );
+            }
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -73,10 +424,10 @@ impl Db {
         }
     }
 
-    /// Scope the db.
-    fn scoped(&self) -> ScopedDb {
+    /// Scope the db into the given namespace.
+    fn scoped(&self, namespace: &str) -> ScopedDb {
         ScopedDb {
-            db: self.db.clone(),
+            db: self.db.namespaced(namespace),
         }
     }
 }
@@ -99,44 +450,210 @@ impl ScopedDb {
         let value = self.db.get::<ConstValue, ConstValue>(key).await?;
         Ok(value)
     }
+
+    /// Set the given value in the database, which expires after the given
+    /// duration, like `10m`.
+    async fn set_expiring(
+        &self,
+        key: ConstValue,
+        value: ConstValue,
+        ttl: String,
+    ) -> Result<(), rune::Error> {
+        let ttl = match str::parse::<Duration>(&ttl) {
+            Ok(ttl) if !ttl.is_empty() => ttl,
+            _ => return Err(anyhow!("bad expiry `{}`", ttl).into()),
+        };
+
+        self.db.set_expiring(key, value, ttl.as_std()).await?;
+        Ok(())
+    }
+
+    /// Delete the given key, returning `true` if it existed.
+    async fn delete(&self, key: ConstValue) -> Result<bool, rune::Error> {
+        Ok(self.db.delete(key).await?)
+    }
+
+    /// List all stored keys.
+    async fn keys(&self) -> Result<Vec<ConstValue>, rune::Error> {
+        Ok(self.db.keys::<ConstValue>().await?)
+    }
+
+    /// Increment the integer stored in the given key, returning the new
+    /// value.
+    async fn increment(&self, key: ConstValue, amount: i64) -> Result<i64, rune::Error> {
+        Ok(self.db.increment(key, amount).await?)
+    }
+}
+
+/// Access to the stream currency, scoped to a channel.
+#[derive(Clone, Any)]
+struct Currency {
+    channel: OwnedChannel,
+    currency: async_injector::Ref<currency::Currency>,
+}
+
+impl Currency {
+    /// Access the underlying currency.
+    async fn currency(&self) -> Result<currency::Currency, rune::Error> {
+        match self.currency.load().await {
+            Some(currency) => Ok(currency),
+            None => Err(anyhow!("no currency configured").into()),
+        }
+    }
+
+    /// Get the name of the currency.
+    async fn name(&self) -> Result<String, rune::Error> {
+        Ok(self.currency().await?.name.to_string())
+    }
+
+    /// Get the balance of the given user.
+    async fn balance(&self, user: String) -> Result<i64, rune::Error> {
+        let balance = self
+            .currency()
+            .await?
+            .balance_of(&self.channel, &user.to_lowercase())
+            .await?
+            .unwrap_or_default();
+
+        Ok(balance.balance)
+    }
+
+    /// Add the given amount to the balance of a user. Negative amounts are
+    /// removed from the balance.
+    async fn add(&self, user: String, amount: i64) -> Result<(), rune::Error> {
+        self.currency()
+            .await?
+            .balance_add(&self.channel, &user.to_lowercase(), amount)
+            .await?;
+
+        Ok(())
+    }
+}
+
+/// A song in the player.
+#[derive(Any)]
+struct Song {
+    /// Human readable description of the song.
+    #[rune(get)]
+    what: String,
+    /// The user who requested the song.
+    #[rune(get)]
+    user: Option<String>,
+    /// The duration of the song in seconds.
+    #[rune(get)]
+    duration: i64,
+}
+
+impl Song {
+    fn new(item: &common::models::Item) -> Self {
+        Self {
+            what: item.what(),
+            user: item.user().cloned(),
+            duration: item.duration().as_secs() as i64,
+        }
+    }
+}
+
+/// Read-only access to the player.
+#[derive(Clone, Any)]
+struct Player {
+    player: async_injector::Ref<player::Player>,
+}
+
+impl Player {
+    /// Get the song which is currently playing.
+    async fn current(&self) -> Option<Song> {
+        let player = self.player.load().await?;
+        let song = player.current().await?;
+        Some(Song::new(song.item()))
+    }
+
+    /// Get the current song followed by the songs in the queue.
+    async fn queue(&self) -> Vec<Song> {
+        let Some(player) = self.player.load().await else {
+            return Vec::new();
+        };
+
+        player
+            .list()
+            .await
+            .iter()
+            .map(|item| Song::new(item))
+            .collect()
+    }
+}
+
+/// Read-only access to settings which are not secret.
+#[derive(Clone, Any)]
+struct Settings {
+    settings: settings::Settings<::auth::Scope>,
+}
+
+impl Settings {
+    /// Get the value of the given setting.
+    async fn get(&self, key: String) -> Result<Option<ConstValue>, rune::Error> {
+        match self.settings.lookup(&key) {
+            Some(schema) if !schema.is_secret() => (),
+            Some(..) => return Err(anyhow!("setting `{}` is secret", key).into()),
+            None => return Err(anyhow!("no such setting `{}`", key).into()),
+        }
+
+        let value = self.settings.get::<serde_json::Value>(&key).await?;
+        Ok(value.map(json_to_const))
+    }
+}
+
+/// Convert a JSON value into a constant value which can be passed to
+/// scripts.
+fn json_to_const(value: serde_json::Value) -> ConstValue {
+    use serde_json::Value;
+
+    match value {
+        Value::Null => ConstValue::Unit,
+        Value::Bool(b) => ConstValue::Bool(b),
+        Value::Number(n) => match n.as_i64() {
+            Some(n) => ConstValue::Integer(n),
+            None => ConstValue::Float(n.as_f64().unwrap_or_default()),
+        },
+        Value::String(s) => ConstValue::String(s),
+        Value::Array(values) => ConstValue::Vec(values.into_iter().map(json_to_const).collect()),
+        Value::Object(object) => ConstValue::Object(
+            object
+                .into_iter()
+                .map(|(key, value)| (key, json_to_const(value)))
+                .collect(),
+        ),
+    }
+}
+
+/// A snapshot of information about the stream.
+#[derive(Any)]
+struct Stream {
+    /// If the stream is live.
+    #[rune(get)]
+    live: bool,
+    #[rune(get)]
+    title: Option<String>,
+    #[rune(get)]
+    game: Option<String>,
+    /// The number of viewers, if the stream is live.
+    #[rune(get)]
+    viewers: Option<i64>,
 }
 
 pub(crate) struct Handler {
     db: Db,
+    services: Services,
     handler: Arc<InternalHandler>,
 }
 
 impl Handler {
     /// Call the given handler with the current context.
     pub(crate) async fn call(self, ctx: command::Context<'_>) -> Result<()> {
-        let ctx = Ctx {
-            user: ctx.user,
-            db: self.db.scoped(),
-        };
-
-        let result: Result<(), ConstValue> =
-            match self.handler.function.async_send_call((ctx,)).await {
-                Ok(result) => result,
-                Err(error) => {
-                    let mut buffer = termcolor::Buffer::no_color();
-                    error.emit(&mut buffer, &self.handler.sources)?;
-
-                    return Err(anyhow!(
-                        "failed to call handler for: {}:\n{}",
-                        self.handler.name,
-                        String::from_utf8(buffer.into_inner())?
-                    ));
-                }
-            };
-
-        match result {
-            Ok(()) => Ok(()),
-            Err(e) => Err(anyhow!(
-                "error when calling handler: {}: {:?}",
-                self.handler.name,
-                e
-            )),
-        }
+        let ctx = self
+            .handler
+            .ctx(&self.db, &self.services, Some(Invoker::Chat(ctx.user)));
+        self.handler.call(&self.services, (ctx,)).await
     }
 }
 
@@ -145,14 +662,16 @@ pub(crate) struct Scripts {
     runtime: Arc<RuntimeContext>,
     options: Options,
     db: Db,
+    services: Services,
     handlers: HashMap<String, Arc<InternalHandler>>,
-    // Keeps track of commands by path so that they may be unregistered.
-    handlers_by_path: HashMap<PathBuf, Vec<String>>,
+    // Keeps track of what was registered by path so that it may be
+    // unregistered.
+    loaded: HashMap<PathBuf, Loaded>,
 }
 
 impl Scripts {
     /// Construct a new script handler.
-    async fn new(channel: &Channel, db: db::Database) -> Result<Self> {
+    async fn new(db: db::Database, services: Services) -> Result<Self> {
         let context = Self::context()?;
         let runtime = Arc::new(context.runtime());
 
@@ -160,9 +679,10 @@ impl Scripts {
             context,
             runtime,
             options: Default::default(),
-            db: Db::new(channel, db),
+            db: Db::new(&services.channel, db),
+            services,
             handlers: HashMap::new(),
-            handlers_by_path: HashMap::new(),
+            loaded: HashMap::new(),
         })
     }
 
@@ -170,31 +690,69 @@ impl Scripts {
     pub(crate) fn get(&self, name: &str) -> Option<Handler> {
         let handler = self.handlers.get(name)?.clone();
 
+        if self
+            .services
+            .states
+            .is_disabled(&self.services.channel, &handler.path)
+        {
+            return None;
+        }
+
         Some(Handler {
             db: self.db.clone(),
+            services: self.services.clone(),
             handler,
         })
     }
 
     /// Same as `load`, except that it removes the old handles before loading
     /// them again.
-    pub(crate) fn reload(&mut self, path: &Path) -> Result<()> {
-        self.unload(path);
-        self.load(path)?;
+    pub(crate) async fn reload(&mut self, path: &Path) -> Result<()> {
+        self.unload(path).await;
+        self.load(path).await?;
         Ok(())
     }
 
-    /// Unload all handlers associated with the given script path.
-    pub(crate) fn unload(&mut self, path: &Path) {
-        if let Some(old) = self.handlers_by_path.remove(path) {
-            for command in old {
-                self.handlers.remove(&command);
+    /// Unload all handlers, hooks and tasks associated with the given script
+    /// path.
+    pub(crate) async fn unload(&mut self, path: &Path) {
+        if let Some(old) = self.loaded.remove(path) {
+            for command in &old.commands {
+                self.handlers.remove(command);
+            }
+
+            if let Some(hooks) = &self.services.hooks {
+                for id in &old.hooks {
+                    hooks.remove(*id).await;
+                }
             }
         }
+
+        // NB: scripts which failed to load have a state, but nothing loaded.
+        self.services
+            .states
+            .unloaded(&self.services.channel, path)
+            .await;
     }
 
     /// Load the given path as a script.
-    pub(crate) fn load(&mut self, path: &Path) -> Result<()> {
+    ///
+    /// Failures are recorded in the state of the script, so that they can be
+    /// shown to the streamer.
+    pub(crate) async fn load(&mut self, path: &Path) -> Result<()> {
+        if let Err(e) = self.load_script(path).await {
+            self.services
+                .states
+                .load_failed(&self.services.channel, path, format!("{:#}", e))
+                .await;
+
+            return Err(e);
+        }
+
+        Ok(())
+    }
+
+    async fn load_script(&mut self, path: &Path) -> Result<()> {
         let mut sources = Sources::new();
         sources.insert(Source::from_path(path)?);
 
@@ -226,30 +784,142 @@ impl Scripts {
         let mut vm = Vm::new(self.runtime.clone(), unit);
         <()>::from_value(vm.call(["main"], (&mut reg,))?)?;
 
+        let mut capabilities = HashSet::new();
+
+        for name in &reg.capabilities {
+            let Some(capability) = Capability::parse(name) else {
+                bail!(
+                    "unknown capability `{}` declared in: {}",
+                    name,
+                    path.display()
+                );
+            };
+
+            capabilities.insert(capability);
+        }
+
+        let capabilities = Arc::new(capabilities);
+
+        // NB: every script gets a namespace of its own unless it asks for
+        // another one, so that scripts can't clobber each other's keys.
+        let namespace: Arc<str> = match &reg.namespace {
+            Some(namespace) => namespace.as_str().into(),
+            None => match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(stem) => stem.into(),
+                None => bail!("cannot pick a storage namespace for: {}", path.display()),
+            },
+        };
+
+        if namespace.is_empty() {
+            bail!("empty storage namespace declared in: {}", path.display());
+        }
+
+        let mut intervals = Vec::with_capacity(reg.intervals.len());
+
+        for (interval, function) in reg.intervals {
+            let duration = match str::parse::<Duration>(&interval) {
+                Ok(duration) if !duration.is_empty() => duration,
+                _ => bail!(
+                    "bad interval `{}` registered in: {}",
+                    interval,
+                    path.display()
+                ),
+            };
+
+            intervals.push((duration, function));
+        }
+
+        let handler = |name: &str, function: SyncFunction| {
+            Arc::new(InternalHandler {
+                name: name.to_owned(),
+                function,
+                path: path.to_owned(),
+                sources: sources.clone(),
+                capabilities: capabilities.clone(),
+                namespace: namespace.clone(),
+            })
+        };
+
+        let mut loaded = Loaded::default();
+
+        let mut warnings = reg
+            .duplicates
+            .iter()
+            .map(|command| format!("command `{}` is registered more than once", command))
+            .collect::<Vec<_>>();
+
+        if let Some(hooks) = &self.services.hooks {
+            for function in reg.hooks {
+                let hook = ScriptHook {
+                    handler: handler("hook", function),
+                    db: self.db.clone(),
+                    services: self.services.clone(),
+                };
+
+                loaded.hooks.push(hooks.insert(hook).await);
+            }
+        }
+
+        for (name, function) in reg.tests {
+            loaded.tests.push(handler(&name, function));
+        }
+
+        for (duration, function) in intervals {
+            loaded.tasks.push(task::spawn(run_interval(
+                handler("interval", function),
+                duration,
+                self.db.clone(),
+                self.services.clone(),
+            )));
+        }
+
+        if !reg.stream_start.is_empty() || !reg.stream_stop.is_empty() {
+            let start = reg
+                .stream_start
+                .into_iter()
+                .map(|function| handler("stream start", function))
+                .collect();
+
+            let stop = reg
+                .stream_stop
+                .into_iter()
+                .map(|function| handler("stream stop", function))
+                .collect();
+
+            loaded.tasks.push(task::spawn(run_stream_callbacks(
+                start,
+                stop,
+                self.db.clone(),
+                self.services.clone(),
+            )));
+        }
+
         for (command, function) in reg.handlers {
             if let Some(handler) = self.handlers.get(&command) {
-                tracing::warn!(
-                    "Ignoring duplicate handler for command `{}`, already registered in {}",
+                let warning = format!(
+                    "ignoring duplicate handler for command `{}`, already registered in {}",
                     command,
                     handler.path.display()
                 );
+
+                tracing::warn!("{}: {}", path.display(), warning);
+                warnings.push(warning);
                 continue;
             }
 
-            let handler = Arc::new(InternalHandler {
-                name: command.clone(),
-                function,
-                path: path.to_owned(),
-                sources: sources.clone(),
-            });
-
-            self.handlers.insert(command.clone(), handler);
-            self.handlers_by_path
-                .entry(path.to_owned())
-                .or_default()
-                .push(command);
+            self.handlers
+                .insert(command.clone(), handler(&command, function));
+            loaded.commands.push(command);
         }
 
+        let commands = loaded.commands.clone();
+        self.loaded.insert(path.to_owned(), loaded);
+
+        self.services
+            .states
+            .loaded(&self.services.channel, path, commands, warnings)
+            .await;
+
         Ok(())
     }
 
@@ -268,16 +938,50 @@ impl Scripts {
         m.async_inst_fn("respond", Ctx::respond)?;
         m.async_inst_fn("privmsg", Ctx::privmsg)?;
         m.inst_fn("user", Ctx::user)?;
+        m.inst_fn("channel", Ctx::channel)?;
+        m.inst_fn("output", Ctx::output)?;
         m.field_fn(Protocol::GET, "db", Ctx::db)?;
+        m.inst_fn("currency", Ctx::currency)?;
+        m.inst_fn("player", Ctx::player)?;
+        m.inst_fn("settings", Ctx::settings)?;
+        m.inst_fn("stream", Ctx::stream)?;
+        m.inst_fn("roles", Ctx::roles)?;
+        m.async_inst_fn("has_scope", Ctx::has_scope)?;
 
         m.ty::<Registry>()?;
         m.inst_fn("register", Registry::register)?;
+        m.inst_fn("capability", Registry::capability)?;
+        m.inst_fn("namespace", Registry::namespace)?;
+        m.inst_fn("hook", Registry::hook)?;
+        m.inst_fn("interval", Registry::interval)?;
+        m.inst_fn("on_stream_start", Registry::on_stream_start)?;
+        m.inst_fn("on_stream_stop", Registry::on_stream_stop)?;
+        m.inst_fn("test", Registry::test)?;
+
+        m.ty::<Currency>()?;
+        m.async_inst_fn("name", Currency::name)?;
+        m.async_inst_fn("balance", Currency::balance)?;
+        m.async_inst_fn("add", Currency::add)?;
+
+        m.ty::<Player>()?;
+        m.async_inst_fn("current", Player::current)?;
+        m.async_inst_fn("queue", Player::queue)?;
+        m.ty::<Song>()?;
+
+        m.ty::<Settings>()?;
+        m.async_inst_fn("get", Settings::get)?;
+
+        m.ty::<Stream>()?;
 
         m.ty::<ScopedDb>()?;
         m.async_inst_fn(Protocol::INDEX_SET, ScopedDb::set)?;
         m.async_inst_fn("set", ScopedDb::set)?;
         m.async_inst_fn(Protocol::INDEX_GET, ScopedDb::get)?;
         m.async_inst_fn("get", ScopedDb::get)?;
+        m.async_inst_fn("set_expiring", ScopedDb::set_expiring)?;
+        m.async_inst_fn("delete", ScopedDb::delete)?;
+        m.async_inst_fn("keys", ScopedDb::keys)?;
+        m.async_inst_fn("increment", ScopedDb::increment)?;
 
         Ok(m)
     }
@@ -286,6 +990,15 @@ impl Scripts {
 #[derive(Any)]
 struct Registry {
     handlers: HashMap<String, SyncFunction>,
+    /// Commands which were registered more than once.
+    duplicates: Vec<String>,
+    capabilities: Vec<String>,
+    namespace: Option<String>,
+    hooks: Vec<SyncFunction>,
+    intervals: Vec<(String, SyncFunction)>,
+    stream_start: Vec<SyncFunction>,
+    stream_stop: Vec<SyncFunction>,
+    tests: Vec<(String, SyncFunction)>,
 }
 
 impl Registry {
@@ -293,22 +1006,160 @@ impl Registry {
     fn new() -> Self {
         Self {
             handlers: Default::default(),
+            duplicates: Vec::new(),
+            capabilities: Vec::new(),
+            namespace: None,
+            hooks: Vec::new(),
+            intervals: Vec::new(),
+            stream_start: Vec::new(),
+            stream_stop: Vec::new(),
+            tests: Vec::new(),
         }
     }
 
     /// Register the given handler.
     fn register(&mut self, name: &str, handler: SyncFunction) {
-        self.handlers.insert(name.to_owned(), handler);
+        if self.handlers.insert(name.to_owned(), handler).is_some() {
+            self.duplicates.push(name.to_owned());
+        }
+    }
+
+    /// Declare that the script uses the given capability.
+    fn capability(&mut self, name: &str) {
+        self.capabilities.push(name.to_owned());
+    }
+
+    /// Store data in the given namespace instead of the one named after the
+    /// script file.
+    ///
+    /// Data stored before scripts had namespaces lives in `shared`, so scripts
+    /// which want to keep using it should declare that namespace.
+    fn namespace(&mut self, name: &str) {
+        self.namespace = Some(name.to_owned());
+    }
+
+    /// Register a hook which is called with every chat message.
+    fn hook(&mut self, handler: SyncFunction) {
+        self.hooks.push(handler);
+    }
+
+    /// Register a handler which is called at the given interval, like `5m`.
+    fn interval(&mut self, interval: &str, handler: SyncFunction) {
+        self.intervals.push((interval.to_owned(), handler));
+    }
+
+    /// Register a handler which is called when the stream goes live.
+    fn on_stream_start(&mut self, handler: SyncFunction) {
+        self.stream_start.push(handler);
+    }
+
+    /// Register a handler which is called when the stream goes offline.
+    fn on_stream_stop(&mut self, handler: SyncFunction) {
+        self.stream_stop.push(handler);
+    }
+
+    /// Register a test, which is only called by the script test runner.
+    fn test(&mut self, name: &str, handler: SyncFunction) {
+        self.tests.push((name.to_owned(), handler));
     }
 }
 
 #[derive(Clone, Any)]
 struct Ctx {
-    user: chat::User,
+    channel: OwnedChannel,
+    /// The invoking user, which is absent for intervals and stream callbacks.
+    user: Option<Invoker>,
     db: ScopedDb,
+    services: Services,
+    capabilities: Arc<HashSet<Capability>>,
 }
 
 impl Ctx {
+    /// Test that the script declared the given capability.
+    fn require(&self, capability: Capability) -> Result<(), VmError> {
+        if !self.capabilities.contains(&capability) {
+            return Err(VmError::panic(format!(
+                "missing capability `{}`, declare it with `reg.capability(\"{}\")`",
+                capability.as_str(),
+                capability.as_str()
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Get the channel the command was invoked in.
+    fn channel(&self) -> String {
+        self.channel.to_string()
+    }
+
+    /// Access the stream currency.
+    fn currency(&self) -> Result<Currency, VmError> {
+        self.require(Capability::Currency)?;
+
+        Ok(Currency {
+            channel: self.channel.clone(),
+            currency: self.services.currency.clone(),
+        })
+    }
+
+    /// Access the player.
+    fn player(&self) -> Result<Player, VmError> {
+        self.require(Capability::Player)?;
+
+        Ok(Player {
+            player: self.services.player.clone(),
+        })
+    }
+
+    /// Access settings.
+    fn settings(&self) -> Result<Settings, VmError> {
+        self.require(Capability::Settings)?;
+
+        Ok(Settings {
+            settings: self.services.settings.clone(),
+        })
+    }
+
+    /// Get information about the stream.
+    fn stream(&self) -> Result<Stream, VmError> {
+        self.require(Capability::Stream)?;
+
+        let data = self.services.stream_info.data.read();
+
+        Ok(Stream {
+            live: data.stream.is_some(),
+            title: data.title.clone(),
+            game: data.game.clone(),
+            viewers: data.stream.as_ref().map(|s| s.viewer_count as i64),
+        })
+    }
+
+    /// Get the roles of the invoking user.
+    fn roles(&self) -> Result<Vec<String>, VmError> {
+        self.require(Capability::Roles)?;
+
+        match &self.user {
+            Some(Invoker::Chat(user)) => Ok(user.roles().iter().map(|r| r.to_string()).collect()),
+            Some(Invoker::Fake(user)) => Ok(user.roles.clone()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Test if the invoking user has the given scope.
+    async fn has_scope(&self, scope: String) -> Result<bool, VmError> {
+        self.require(Capability::Roles)?;
+
+        match &self.user {
+            Some(Invoker::Chat(user)) => {
+                let scope = str::parse::<auth::Scope>(&scope).unwrap_or(auth::Scope::Unknown);
+                Ok(user.has_scope(scope).await)
+            }
+            Some(Invoker::Fake(user)) => Ok(user.scopes.contains(&scope)),
+            None => Ok(false),
+        }
+    }
+
     /// Access the db associated with the context.
     fn db(&self) -> ScopedDb {
         self.db.clone()
@@ -316,16 +1167,195 @@ impl Ctx {
 
     /// Get the user name, if present.
     fn user(&self) -> Option<String> {
-        self.user.name().map(|s| s.to_owned())
+        match self.user.as_ref()? {
+            Invoker::Chat(user) => user.name().map(|s| s.to_owned()),
+            Invoker::Fake(user) => Some(user.name.clone()),
+        }
     }
 
     /// Respond with the given message.
+    ///
+    /// Without an invoking user this is the same as `privmsg`.
     async fn respond(&self, message: &str) {
-        self.user.respond(message).await;
+        match &self.user {
+            Some(Invoker::Chat(user)) => user.respond(message).await,
+            Some(Invoker::Fake(user)) => {
+                let message = crate::respond(&user.name, message).to_string();
+                self.services.sink.privmsg(&message).await;
+            }
+            None => self.services.sink.privmsg(message).await,
+        }
     }
 
     /// Send a privmsg, without prefixing it with the user we are responding to.
     async fn privmsg(&self, message: &str) {
-        self.user.sender().privmsg(message).await;
+        self.services.sink.privmsg(message).await;
+    }
+
+    /// Get all messages sent so far. Only records messages when testing
+    /// scripts.
+    fn output(&self) -> Vec<String> {
+        self.services.sink.recorded()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use anyhow::{Context as _, Result};
+    use common::{Channel, Duration};
+
+    use super::{
+        stream_info, test_dir, Limits, Scripts, Services, Sink, States, TestOutcome, TestUser,
+    };
+
+    const SCHEMA: &[u8] = br#"
+types:
+  script/budget:
+    doc: The instruction budget.
+    type: {id: number}
+  script/timeout:
+    doc: The timeout.
+    type: {id: duration}
+  script/max-faults:
+    doc: The number of faults before a script is disabled.
+    type: {id: number}
+"#;
+
+    /// Directory with scripts used for testing.
+    fn scripts() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("src/script/tests")
+    }
+
+    /// Set up settings with the given limits.
+    async fn settings(
+        budget: u32,
+        timeout: Duration,
+        max_faults: u32,
+    ) -> Result<settings::Settings<::auth::Scope>> {
+        let schema = settings::Schema::load_bytes(SCHEMA)?;
+        let settings = settings::Settings::new(db::Database::memory()?, schema);
+        settings.set("script/budget", budget).await?;
+        settings.set("script/timeout", timeout).await?;
+        settings.set("script/max-faults", max_faults).await?;
+        Ok(settings)
+    }
+
+    /// Find the error raised by the test with the given name.
+    fn error<'a>(outcomes: &'a [TestOutcome], name: &str) -> Result<&'a str> {
+        let outcome = outcomes
+            .iter()
+            .find(|o| o.name.as_deref() == Some(name))
+            .with_context(|| format!("missing test `{}`", name))?;
+
+        outcome
+            .error
+            .as_deref()
+            .with_context(|| format!("test `{}` didn't fail", name))
+    }
+
+    #[test]
+    fn test_limits() -> Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+
+        runtime.block_on(async {
+            let settings = settings(1_000, Duration::seconds(1), 3).await?;
+            let outcomes = test_dir(settings, Default::default(), [scripts()]).await?;
+
+            let e = error(&outcomes, "runs out of budget")?;
+            assert!(
+                e.contains("exceeded its budget of 1000 instructions"),
+                "{}",
+                e
+            );
+
+            let e = error(&outcomes, "sleeps past the timeout")?;
+            assert!(e.contains("timed out after 1s"), "{}", e);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_scripts() -> Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+
+        runtime.block_on(async {
+            let settings = settings(1_000_000, Duration::seconds(5), 3).await?;
+
+            let user = TestUser {
+                name: String::from("tester"),
+                ..TestUser::default()
+            };
+
+            let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../../scripts");
+            let outcomes = test_dir(settings, user, [dir]).await?;
+
+            assert!(outcomes.iter().any(|o| o.name.is_some()), "no tests found");
+
+            for outcome in outcomes {
+                assert!(outcome.error.is_none(), "{:?}", outcome);
+            }
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_disable_after_faults() -> Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_time()
+            .build()?;
+
+        runtime.block_on(async {
+            let settings = settings(1_000, Duration::seconds(1), 2).await?;
+            let injector = async_injector::Injector::new();
+
+            let services = Services {
+                channel: Channel::from_string("test").into_owned(),
+                sink: Sink::Record(Default::default()),
+                hooks: None,
+                limits: Limits::new(&settings).await?,
+                states: States::new(bus::Bus::new()),
+                currency: injector.var().await,
+                player: injector.var().await,
+                settings,
+                stream_info: stream_info::StreamInfo::new(),
+            };
+
+            let path = scripts().join("busy.rn");
+            let mut scripts = Scripts::new(db::Database::memory()?, services.clone()).await?;
+            scripts.load(&path).await?;
+
+            let handler = scripts
+                .handlers
+                .get("busy")
+                .context("missing handler")?
+                .clone();
+
+            let ctx = handler.ctx(&scripts.db, &services, None);
+            assert!(handler.call(&services, (ctx,)).await.is_err());
+            assert!(!services.states.is_disabled(&services.channel, &path));
+            assert!(scripts.get("busy").is_some());
+
+            let ctx = handler.ctx(&scripts.db, &services, None);
+            assert!(handler.call(&services, (ctx,)).await.is_err());
+            assert!(services.states.is_disabled(&services.channel, &path));
+            assert!(scripts.get("busy").is_none());
+
+            // Disabled scripts are no longer called at all.
+            let ctx = handler.ctx(&scripts.db, &services, None);
+            assert!(handler.call(&services, (ctx,)).await.is_ok());
+
+            // Reloading the script enables it again.
+            scripts.reload(&path).await?;
+            assert!(!services.states.is_disabled(&services.channel, &path));
+            assert!(scripts.get("busy").is_some());
+            Ok(())
+        })
     }
 }
@@ -69,12 +69,6 @@ impl Sender {
         Channel::new(self.inner.target.as_str())
     }
 
-    /// Delete the given message by id.
-    #[tracing::instrument(skip_all)]
-    pub fn delete(&self, id: &str) {
-        self.privmsg_immediate(format!("/delete {}", id));
-    }
-
     /// Only send to chat, with rate limiting.
     #[tracing::instrument(skip_all)]
     pub async fn send(&self, m: impl Into<Message>) {
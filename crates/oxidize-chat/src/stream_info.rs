@@ -5,9 +5,10 @@ use std::sync::Arc;
 use std::time;
 
 use anyhow::{anyhow, Result};
+use async_fuse::Fuse;
 use common::stream::StreamExt;
 use parking_lot::RwLock;
-use tokio::sync::mpsc;
+use tokio::sync::{mpsc, watch};
 use tracing::Instrument;
 
 #[derive(Debug, Default)]
@@ -28,11 +29,32 @@ pub enum StreamState {
 #[derive(Debug, Clone)]
 pub struct StreamInfo {
     pub data: Arc<RwLock<Data>>,
+    /// Notified when the stream goes live or offline.
+    live: Arc<watch::Sender<bool>>,
 }
 
 impl StreamInfo {
+    /// Construct stream information for a stream which is offline.
+    pub(crate) fn new() -> Self {
+        Self {
+            data: Default::default(),
+            live: Arc::new(watch::Sender::new(false)),
+        }
+    }
+
+    /// Check if the stream is live.
+    pub(crate) fn is_live(&self) -> bool {
+        self.data.read().stream.is_some()
+    }
+
+    /// Subscribe to changes in whether the stream is live.
+    #[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+    pub(crate) fn subscribe_live(&self) -> watch::Receiver<bool> {
+        self.live.subscribe()
+    }
+
     /// Check if a name is a subscriber.
-    pub(crate) fn is_subscriber(&self, name: &str) -> bool {
+    pub fn is_subscriber(&self, name: &str) -> bool {
         self.data.read().subs_set.contains(name)
     }
 
@@ -87,7 +109,7 @@ impl StreamInfo {
     pub(crate) async fn refresh_stream<'a>(
         &'a self,
         streamer: &'a api::TwitchAndUser,
-        stream_state_tx: &'a mpsc::Sender<StreamState>,
+        stream_state_tx: Option<&'a mpsc::Sender<StreamState>>,
     ) -> Result<()> {
         let mut streams = pin!(streamer.client.streams(&streamer.user.id).await);
 
@@ -99,7 +121,9 @@ impl StreamInfo {
             _ => None,
         };
 
-        if let Some(update) = update {
+        let changed = update.is_some();
+
+        if let (Some(update), Some(stream_state_tx)) = (update, stream_state_tx) {
             stream_state_tx
                 .send(update)
                 .await
@@ -110,22 +134,34 @@ impl StreamInfo {
             return Ok(());
         }
 
+        let live = stream.is_some();
         self.data.write().stream = stream;
+
+        if changed {
+            self.live.send_replace(live);
+        }
+
         Ok(())
     }
 }
 
 /// Set up a stream information loop.
+///
+/// If `stream_state_tx` is `None`, the channel is not owned by the streamer.
+/// Stream state changes are then not reported, and subscribers are not
+/// refreshed since that requires the broadcaster's own token.
 pub(crate) fn setup(
     streamer: api::TwitchAndUser,
-    stream_state_tx: mpsc::Sender<StreamState>,
+    stream_state_tx: Option<mpsc::Sender<StreamState>>,
 ) -> (StreamInfo, impl Future<Output = Result<()>>) {
-    let stream_info = StreamInfo {
-        data: Default::default(),
-    };
+    let stream_info = StreamInfo::new();
 
     let mut stream_interval = tokio::time::interval(time::Duration::from_secs(30));
-    let mut subs_interval = tokio::time::interval(time::Duration::from_secs(60 * 10));
+    let mut subs_interval = if stream_state_tx.is_some() {
+        Fuse::new(tokio::time::interval(time::Duration::from_secs(60 * 10)))
+    } else {
+        Fuse::empty()
+    };
 
     let stream_info2 = stream_info.clone();
 
@@ -134,14 +170,14 @@ pub(crate) fn setup(
             streamer.client.token().wait_until_ready().await;
 
             tokio::select! {
-                _ = subs_interval.tick() => {
+                _ = subs_interval.as_pin_mut().poll_inner(|mut i, cx| i.poll_tick(cx)) => {
                     if let Err(error) = stream_info.refresh_subs(&streamer).await {
                         common::log_error!(error, "Failed to refresh subscriptions");
                     }
                 }
                 _ = stream_interval.tick() => {
                     let stream = stream_info
-                        .refresh_stream(&streamer, &stream_state_tx);
+                        .refresh_stream(&streamer, stream_state_tx.as_ref());
                     let channel = stream_info
                         .refresh_channel(&streamer);
 
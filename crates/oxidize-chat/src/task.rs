@@ -8,6 +8,14 @@ pub struct Handle<O> {
     handle: tokio::task::JoinHandle<Result<O>>,
 }
 
+impl<O> Handle<O> {
+    /// Abort the task.
+    #[cfg_attr(not(feature = "scripting"), allow(dead_code))]
+    pub(crate) fn abort(&self) {
+        self.handle.abort();
+    }
+}
+
 impl<O> Future for Handle<O> {
     type Output = Result<O>;
 
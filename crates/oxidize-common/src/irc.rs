@@ -13,6 +13,8 @@ pub struct Tags {
     pub color: Option<String>,
     /// Emotes part of the message.
     pub emotes: Option<String>,
+    /// Badges of the user in the channel the message was sent in.
+    pub badges: Option<String>,
 }
 
 impl Tags {
@@ -29,6 +31,7 @@ impl Tags {
         let mut user_id = None;
         let mut color = None;
         let mut emotes = None;
+        let mut badges = None;
 
         for (key, value) in tags {
             match key.as_ref() {
@@ -38,6 +41,7 @@ impl Tags {
                 "user-id" => user_id = Some(value.as_ref().to_owned()),
                 "color" => color = Some(value.as_ref().to_owned()),
                 "emotes" => emotes = Some(value.as_ref().to_owned()),
+                "badges" => badges = Some(value.as_ref().to_owned()),
                 key => {
                     tracing::trace!(key, value = value.as_ref(), "unsupported tag");
                 }
@@ -51,6 +55,44 @@ impl Tags {
             user_id,
             color,
             emotes,
+            badges,
         }
     }
+
+    /// Test if the user has the given badge, like `moderator`.
+    ///
+    /// The badges tag has the form `broadcaster/1,subscriber/12`.
+    pub fn has_badge(&self, name: &str) -> bool {
+        let badges = self.badges.as_deref().unwrap_or_default();
+
+        badges
+            .split(',')
+            .any(|badge| badge.split('/').next() == Some(name))
+    }
+
+    /// Test if the user is a moderator or the broadcaster of the channel the
+    /// message was sent in.
+    pub fn is_moderator(&self) -> bool {
+        self.has_badge("broadcaster") || self.has_badge("moderator")
+    }
+
+    /// Iterate over all emote ranges in the message, as the id of the emote
+    /// and the inclusive range of characters it covers.
+    ///
+    /// The emotes tag has the form `300354391:8-16/28087:0-6,10-16`.
+    pub fn emote_ranges(&self) -> impl Iterator<Item = (&str, usize, usize)> + '_ {
+        let emotes = self.emotes.as_deref().unwrap_or_default();
+
+        emotes
+            .split('/')
+            .filter_map(|emote| emote.split_once(':'))
+            .flat_map(|(id, ranges)| {
+                ranges.split(',').filter_map(move |range| {
+                    let (s, e) = range.split_once('-')?;
+                    let s = str::parse::<usize>(s).ok()?;
+                    let e = str::parse::<usize>(e).ok()?;
+                    Some((id, s, e))
+                })
+            })
+    }
 }
@@ -1,12 +1,12 @@
 use std::fmt;
 
 /// Format the given part and whole as a percentage.
-pub fn percentage(part: u32, total: u32) -> impl fmt::Display {
+pub fn percentage(part: u64, total: u64) -> impl fmt::Display {
     Percentage(part, total)
 }
 
 #[derive(Clone, Copy)]
-struct Percentage(u32, u32);
+struct Percentage(u64, u64);
 
 impl fmt::Display for Percentage {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -17,7 +17,8 @@ impl fmt::Display for Percentage {
             total => total,
         };
 
-        let p = (part * 10_000) / total;
+        // NB: widened so that large parts can't overflow.
+        let p = (u128::from(part) * 10_000) / u128::from(total);
         write!(fmt, "{}", p / 100)?;
 
         match p % 100 {
@@ -28,3 +29,16 @@ impl fmt::Display for Percentage {
         fmt.write_str("%")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::percentage;
+
+    #[test]
+    fn test_percentage() {
+        assert_eq!("0%", percentage(1, 0).to_string());
+        assert_eq!("50%", percentage(1, 2).to_string());
+        assert_eq!("33.33%", percentage(1, 3).to_string());
+        assert_eq!("100%", percentage(u64::MAX, u64::MAX).to_string());
+    }
+}
@@ -58,6 +58,40 @@ impl Backend {
             .await
     }
 
+    /// Subtract from the balance of a single user, failing without touching
+    /// it if they don't have enough.
+    pub(crate) async fn balance_take(
+        &self,
+        channel: &Channel,
+        user: &str,
+        amount: i64,
+    ) -> Result<(), BalanceTransferError> {
+        use self::schema::balances::dsl;
+
+        let channel = channel.to_owned();
+        let user = user_id(user);
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(move |c| {
+                    let balance = dsl::balances
+                        .filter(dsl::channel.eq(&channel).and(dsl::user.eq(&user)))
+                        .select(dsl::amount)
+                        .first::<i64>(c)
+                        .optional()?
+                        .unwrap_or_default();
+
+                    if balance < amount {
+                        return Err(BalanceTransferError::NoBalance);
+                    }
+
+                    modify_balance(c, &channel, &user, -amount)?;
+                    Ok(())
+                })
+            })
+            .await
+    }
+
     /// Get balances for all users.
     pub(crate) async fn export_balances(&self) -> Result<Vec<models::Balance>> {
         use self::schema::balances::dsl;
@@ -151,6 +185,28 @@ impl Backend {
             .await
     }
 
+    /// Add (or subtract) from the balances of several users in a single
+    /// transaction.
+    pub(crate) async fn balances_add<I>(&self, channel: &Channel, amounts: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, i64)> + Send + 'static,
+        I::IntoIter: Send,
+    {
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(move |c| {
+                    for (user, amount) in amounts {
+                        modify_balance(c, &channel, &user_id(&user), amount)?;
+                    }
+
+                    Ok(())
+                })
+            })
+            .await
+    }
+
     /// Add balance to users.
     pub(crate) async fn balances_increment<I>(
         &self,
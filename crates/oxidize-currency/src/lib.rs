@@ -184,6 +184,21 @@ impl Backend {
         }
     }
 
+    /// Subtract from the balance of a single user if they have enough.
+    async fn balance_take(
+        &self,
+        channel: &Channel,
+        user: &str,
+        amount: i64,
+    ) -> Result<(), BalanceTransferError> {
+        use self::Backend::*;
+
+        match self {
+            BuiltIn(backend) => backend.balance_take(channel, user, amount).await,
+            MySql(backend) => backend.balance_take(channel, user, amount).await,
+        }
+    }
+
     /// Get balances for all users.
     async fn export_balances(&self) -> Result<Vec<Balance>> {
         use self::Backend::*;
@@ -224,6 +239,20 @@ impl Backend {
         }
     }
 
+    /// Add (or subtract) from the balances of several users at once.
+    async fn balances_add<I>(&self, channel: &Channel, amounts: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, i64)> + Send + 'static,
+        I::IntoIter: Send,
+    {
+        use self::Backend::*;
+
+        match self {
+            BuiltIn(backend) => backend.balances_add(channel, amounts).await,
+            MySql(backend) => backend.balances_add(channel, amounts).await,
+        }
+    }
+
     /// Add balance to users.
     #[tracing::instrument(skip(self, users))]
     pub async fn balances_increment<I>(
@@ -311,6 +340,17 @@ impl Currency {
             .await
     }
 
+    /// Subtract from the balance of a single user if they have enough,
+    /// checking and updating the balance in a single transaction.
+    pub async fn balance_take(
+        &self,
+        channel: &Channel,
+        user: &str,
+        amount: i64,
+    ) -> Result<(), BalanceTransferError> {
+        self.inner.backend.balance_take(channel, user, amount).await
+    }
+
     /// Get balances for all users.
     pub async fn export_balances(&self) -> Result<Vec<Balance>> {
         self.inner.backend.export_balances().await
@@ -331,6 +371,16 @@ impl Currency {
         self.inner.backend.balance_add(channel, user, amount).await
     }
 
+    /// Add (or subtract) from the balances of several users, either updating
+    /// all of them or none of them.
+    pub async fn balances_add<I>(&self, channel: &Channel, amounts: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, i64)> + Send + 'static,
+        I::IntoIter: Send + 'static,
+    {
+        self.inner.backend.balances_add(channel, amounts).await
+    }
+
     /// Add balance to users.
     pub async fn balances_increment<I>(
         &self,
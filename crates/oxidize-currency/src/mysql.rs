@@ -234,6 +234,33 @@ impl Backend {
         Ok(())
     }
 
+    /// Subtract from the balance of a single user, failing without touching
+    /// it if they don't have enough.
+    pub(crate) async fn balance_take(
+        &self,
+        _channel: &Channel,
+        user: &str,
+        amount: i64,
+    ) -> Result<(), BalanceTransferError> {
+        let amount: i32 = amount
+            .try_into()
+            .with_context(|| anyhow!("Unsupported amount `{amount}`"))?;
+        let user = user_id(user);
+
+        let opts = mysql::TxOpts::new();
+        let mut tx = self.pool.start_transaction(opts).await?;
+
+        let balance = self.queries.select_balance(&mut tx, &user).await?;
+
+        if balance.unwrap_or_default() < amount {
+            return Err(BalanceTransferError::NoBalance);
+        }
+
+        self.queries.modify_balance(&mut tx, &user, -amount).await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
     /// Get balances for all users.
     pub(crate) async fn export_balances(&self) -> Result<Vec<Balance>> {
         let channel = self.channel.to_owned();
@@ -316,6 +343,26 @@ impl Backend {
         Ok(())
     }
 
+    /// Add (or subtract) from the balances of several users in a single
+    /// transaction.
+    pub(crate) async fn balances_add<I>(&self, _channel: &Channel, amounts: I) -> Result<()>
+    where
+        I: IntoIterator<Item = (String, i64)> + Send,
+        I::IntoIter: Send,
+    {
+        let opts = mysql::TxOpts::new();
+        let mut tx = self.pool.start_transaction(opts).await?;
+
+        for (user, amount) in amounts {
+            let user = user_id(&user);
+            let amount = amount.try_into()?;
+            self.queries.modify_balance(&mut tx, &user, amount).await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
     /// Add balance to users.
     pub(crate) async fn balances_increment<I>(
         &self,
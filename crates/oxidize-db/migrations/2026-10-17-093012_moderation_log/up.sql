@@ -0,0 +1,15 @@
+CREATE TABLE moderation_log (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    automated BOOLEAN NOT NULL DEFAULT FALSE,
+    user TEXT,
+    action TEXT NOT NULL,
+    duration INTEGER,
+    reason TEXT,
+    source TEXT,
+    message TEXT,
+    message_id TEXT
+);
+
+CREATE INDEX moderation_log_channel_added_at ON moderation_log(channel, added_at);
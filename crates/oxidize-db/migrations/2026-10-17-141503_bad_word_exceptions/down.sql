@@ -0,0 +1 @@
+DROP TABLE bad_word_exceptions;
\ No newline at end of file
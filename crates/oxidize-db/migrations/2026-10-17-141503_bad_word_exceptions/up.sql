@@ -0,0 +1,5 @@
+CREATE TABLE bad_word_exceptions (
+    word VARCHAR NOT NULL,
+    exception VARCHAR NOT NULL,
+    PRIMARY KEY (word, exception)
+);
@@ -0,0 +1,4 @@
+ALTER TABLE bad_words ADD COLUMN severity INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE bad_words ADD COLUMN action VARCHAR NOT NULL DEFAULT 'delete';
+ALTER TABLE bad_words ADD COLUMN duration INTEGER;
+ALTER TABLE bad_words ADD COLUMN only_live BOOLEAN NOT NULL DEFAULT FALSE;
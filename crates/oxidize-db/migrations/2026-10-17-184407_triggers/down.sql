@@ -0,0 +1 @@
+DROP TABLE triggers;
\ No newline at end of file
@@ -0,0 +1,17 @@
+CREATE TABLE triggers (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    kind VARCHAR NOT NULL,
+    pattern VARCHAR NOT NULL,
+    text TEXT NOT NULL,
+    priority INTEGER NOT NULL DEFAULT 0,
+    cooldown INTEGER NOT NULL DEFAULT 0,
+    exempt_moderators BOOLEAN NOT NULL DEFAULT TRUE,
+    exempt_bots BOOLEAN NOT NULL DEFAULT TRUE,
+    only_live BOOLEAN NOT NULL DEFAULT FALSE,
+    "group" TEXT,
+    disabled BOOLEAN NOT NULL DEFAULT FALSE,
+    PRIMARY KEY (channel, name)
+);
+
+CREATE INDEX idx_triggers_group ON triggers("group");
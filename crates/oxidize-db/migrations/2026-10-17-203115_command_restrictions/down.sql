@@ -0,0 +1,17 @@
+CREATE TABLE commands2 (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    text TEXT NOT NULL,
+    count INTEGER DEFAULT 0,
+    disabled BOOLEAN DEFAULT false,
+    "group" TEXT,
+    pattern VARCHAR DEFAULT NULL,
+    PRIMARY KEY (channel, name)
+);
+
+INSERT INTO commands2 (channel, name, text, count, disabled, "group", pattern)
+SELECT channel, name, text, count, disabled, "group", pattern FROM commands;
+
+DROP TABLE commands;
+ALTER TABLE commands2 RENAME TO commands;
+CREATE INDEX idx_commands_group ON commands("group");
@@ -0,0 +1,4 @@
+ALTER TABLE commands ADD COLUMN cooldown INTEGER;
+ALTER TABLE commands ADD COLUMN user_cooldown INTEGER;
+ALTER TABLE commands ADD COLUMN cost INTEGER;
+ALTER TABLE commands ADD COLUMN requires VARCHAR;
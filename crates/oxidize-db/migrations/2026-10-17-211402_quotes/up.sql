@@ -0,0 +1,11 @@
+CREATE TABLE quotes (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    added_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    added_by TEXT NOT NULL,
+    author TEXT NOT NULL,
+    game TEXT,
+    text TEXT NOT NULL
+);
+
+CREATE INDEX idx_quotes_channel ON quotes(channel);
@@ -0,0 +1,6 @@
+CREATE TABLE counters (
+    channel VARCHAR NOT NULL,
+    name VARCHAR NOT NULL,
+    count BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (channel, name)
+);
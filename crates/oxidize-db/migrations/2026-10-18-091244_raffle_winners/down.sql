@@ -0,0 +1 @@
+DROP TABLE raffle_winners;
@@ -0,0 +1,11 @@
+CREATE TABLE raffle_winners (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    drawn_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    user VARCHAR NOT NULL,
+    tickets INTEGER NOT NULL,
+    total_tickets INTEGER NOT NULL,
+    ticket_price BIGINT NOT NULL
+);
+
+CREATE INDEX idx_raffle_winners_channel ON raffle_winners(channel);
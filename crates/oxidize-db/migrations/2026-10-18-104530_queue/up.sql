@@ -0,0 +1,10 @@
+CREATE TABLE queue_entries (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    user VARCHAR NOT NULL,
+    display_name VARCHAR NOT NULL,
+    joined_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    priority INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE UNIQUE INDEX idx_queue_entries_channel_user ON queue_entries(channel, user);
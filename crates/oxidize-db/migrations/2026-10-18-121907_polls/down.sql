@@ -0,0 +1,2 @@
+DROP TABLE poll_options;
+DROP TABLE polls;
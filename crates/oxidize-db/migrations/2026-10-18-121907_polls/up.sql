@@ -0,0 +1,19 @@
+CREATE TABLE polls (
+    id INTEGER NOT NULL PRIMARY KEY AUTOINCREMENT,
+    channel VARCHAR NOT NULL,
+    question TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL,
+    closed_at TIMESTAMP NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    weighted BOOLEAN NOT NULL DEFAULT FALSE
+);
+
+CREATE INDEX idx_polls_channel ON polls(channel);
+
+CREATE TABLE poll_options (
+    poll_id INTEGER NOT NULL,
+    option VARCHAR NOT NULL,
+    description TEXT,
+    votes INTEGER NOT NULL DEFAULT 0,
+    weight BIGINT NOT NULL DEFAULT 0,
+    PRIMARY KEY (poll_id, option)
+);
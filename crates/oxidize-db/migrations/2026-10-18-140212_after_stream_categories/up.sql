@@ -0,0 +1,4 @@
+ALTER TABLE after_streams ADD COLUMN category TEXT;
+ALTER TABLE after_streams ADD COLUMN done_at TIMESTAMP;
+ALTER TABLE after_streams ADD COLUMN stream_id TEXT;
+ALTER TABLE after_streams ADD COLUMN stream_started_at TIMESTAMP;
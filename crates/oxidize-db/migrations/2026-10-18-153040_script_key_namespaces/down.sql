@@ -0,0 +1,10 @@
+CREATE TABLE script_keys2 (
+    channel VARCHAR NOT NULL,
+    key BLOB NOT NULL,
+    value BLOB NOT NULL,
+    PRIMARY KEY (channel, key)
+);
+
+INSERT OR REPLACE INTO script_keys2 (channel, key, value) SELECT channel, key, value FROM script_keys;
+DROP TABLE script_keys;
+ALTER TABLE script_keys2 RENAME TO script_keys;
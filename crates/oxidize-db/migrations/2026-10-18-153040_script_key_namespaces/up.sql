@@ -0,0 +1,12 @@
+CREATE TABLE script_keys2 (
+    channel VARCHAR NOT NULL,
+    namespace VARCHAR NOT NULL,
+    key BLOB NOT NULL,
+    value BLOB NOT NULL,
+    expires_at TIMESTAMP,
+    PRIMARY KEY (channel, namespace, key)
+);
+
+INSERT INTO script_keys2 (channel, namespace, key, value) SELECT channel, 'shared', key, value FROM script_keys;
+DROP TABLE script_keys;
+ALTER TABLE script_keys2 RENAME TO script_keys;
@@ -1,11 +1,36 @@
 use anyhow::Result;
+use chrono::{DateTime, Utc};
 use common::Channel;
 use diesel::prelude::*;
+use serde::Deserialize;
 
 use crate::models;
 use crate::schema;
 
-pub(crate) use self::models::AfterStream;
+pub use self::models::AfterStream;
+
+/// The stream session an afterstream was added during.
+#[derive(Debug, Clone)]
+pub struct Session {
+    /// The id of the stream.
+    pub id: String,
+    /// When the stream started.
+    pub started_at: DateTime<Utc>,
+}
+
+/// Filter to apply when listing afterstreams.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Filter {
+    /// Only list afterstreams with the given category.
+    #[serde(default)]
+    pub category: Option<String>,
+    /// Only list afterstreams which are or aren't done.
+    #[serde(default)]
+    pub done: Option<bool>,
+    /// Only list afterstreams added during the given stream.
+    #[serde(default)]
+    pub stream_id: Option<String>,
+}
 
 #[derive(Clone)]
 pub struct AfterStreams {
@@ -19,21 +44,27 @@ impl AfterStreams {
     }
 
     /// Push the given afterstream message.
-    pub async fn push(&self, channel: &Channel, user: &str, text: &str) -> Result<()> {
+    pub async fn push(
+        &self,
+        channel: &Channel,
+        user: &str,
+        category: Option<&str>,
+        text: &str,
+        session: Option<&Session>,
+    ) -> Result<()> {
         use self::schema::after_streams::dsl;
 
-        let channel = channel.to_owned();
-        let user = user.to_string();
-        let text = text.to_string();
+        let after_stream = models::InsertAfterStream {
+            channel: Some(channel.to_string()),
+            user: user.to_string(),
+            text: text.to_string(),
+            category: category.map(|c| c.to_lowercase()),
+            stream_id: session.map(|s| s.id.clone()),
+            stream_started_at: session.map(|s| s.started_at.naive_utc()),
+        };
 
         self.db
             .asyncify(move |c| {
-                let after_stream = models::InsertAfterStream {
-                    channel: Some(channel.to_string()),
-                    user,
-                    text,
-                };
-
                 diesel::insert_into(dsl::after_streams)
                     .values(&after_stream)
                     .execute(c)?;
@@ -43,7 +74,27 @@ impl AfterStreams {
             .await
     }
 
+    /// Mark the after stream with the given id as done or not done.
+    pub async fn set_done(&self, id: i32, done: bool) -> Result<bool> {
+        use self::schema::after_streams::dsl;
+
+        let done_at = done.then(|| Utc::now().naive_utc());
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::update(dsl::after_streams.filter(dsl::id.eq(id)))
+                    .set(dsl::done_at.eq(done_at))
+                    .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
     /// Delete the after stream with the given id.
+    ///
+    /// Unlike [`AfterStreams::set_done`], this removes it from the history of
+    /// the stream it was added during, which is what you want for spam.
     pub async fn delete(&self, id: i32) -> Result<bool> {
         use self::schema::after_streams::dsl;
 
@@ -57,11 +108,36 @@ impl AfterStreams {
 
     /// List all available after streams.
     pub async fn list(&self) -> Result<Vec<AfterStream>> {
+        self.list_filtered(Filter::default()).await
+    }
+
+    /// List after streams matching the given filter.
+    pub async fn list_filtered(&self, filter: Filter) -> Result<Vec<AfterStream>> {
         use self::schema::after_streams::dsl;
 
         self.db
             .asyncify(move |c| {
-                Ok(dsl::after_streams
+                let mut query = dsl::after_streams.into_boxed();
+
+                if let Some(category) = filter.category {
+                    query = query.filter(dsl::category.eq(category.to_lowercase()));
+                }
+
+                match filter.done {
+                    Some(true) => {
+                        query = query.filter(dsl::done_at.is_not_null());
+                    }
+                    Some(false) => {
+                        query = query.filter(dsl::done_at.is_null());
+                    }
+                    None => (),
+                }
+
+                if let Some(stream_id) = filter.stream_id {
+                    query = query.filter(dsl::stream_id.eq(stream_id));
+                }
+
+                Ok(query
                     .order(dsl::added_at.asc())
                     .load::<models::AfterStream>(c)?)
             })
@@ -1,13 +1,15 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::fmt;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
+use std::time::Instant;
 
 use anyhow::{anyhow, Context, Error, Result};
 use common::words;
-use common::Channel;
+use common::{Channel, Duration};
 use diesel::prelude::*;
-use serde::{ser, Serialize};
+use parking_lot::Mutex;
+use serde::{ser, Deserialize, Serialize};
 use tokio::sync::RwLock;
 
 /// Local database wrapper.
@@ -39,6 +41,10 @@ impl Database {
                             text: text.to_string(),
                             group: None,
                             disabled: false,
+                            cooldown: None,
+                            user_cooldown: None,
+                            cost: None,
+                            requires: None,
                         };
 
                         diesel::insert_into(dsl::commands)
@@ -80,6 +86,38 @@ impl Database {
             .await
     }
 
+    /// Edit the restrictions of a command.
+    async fn edit_restrictions(
+        &self,
+        key: &crate::Key,
+        restrictions: &Restrictions,
+    ) -> Result<bool> {
+        use crate::schema::commands::dsl;
+
+        let key = key.clone();
+        let restrictions = restrictions.clone();
+
+        self.0
+            .asyncify(move |c| {
+                let set = crate::models::UpdateCommandRestrictions {
+                    cooldown: restrictions.cooldown.map(to_seconds),
+                    user_cooldown: restrictions.user_cooldown.map(to_seconds),
+                    cost: restrictions.cost,
+                    requires: restrictions.requires.as_deref(),
+                };
+
+                let count = diesel::update(
+                    dsl::commands
+                        .filter(dsl::channel.eq(&key.channel).and(dsl::name.eq(&key.name))),
+                )
+                .set(&set)
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
     /// Increment the given key.
     async fn increment(&self, key: &crate::Key) -> Result<bool, Error> {
         use crate::schema::commands::dsl;
@@ -149,6 +187,8 @@ impl Commands {
                 count: Arc::new(AtomicUsize::new(command.count as usize)),
                 template,
                 vars,
+                restrictions: Restrictions::from_db(&command),
+                cooldowns: Default::default(),
                 group: command.group,
                 disabled: command.disabled,
             });
@@ -174,6 +214,28 @@ impl Commands {
         }))
     }
 
+    /// Edit the restrictions for the given command.
+    ///
+    /// Returns `false` if there is no such command.
+    pub async fn edit_restrictions(
+        &self,
+        channel: &Channel,
+        name: &str,
+        restrictions: Restrictions,
+    ) -> Result<bool> {
+        let key = crate::Key::new(channel, name);
+
+        if !self.db.edit_restrictions(&key, &restrictions).await? {
+            return Ok(false);
+        }
+
+        self.inner.write().await.modify(key, |command| {
+            command.restrictions = restrictions;
+        });
+
+        Ok(true)
+    }
+
     /// Increment the specified command.
     pub async fn increment(&self, command: &Command) -> Result<(), Error> {
         self.db.increment(&command.key).await?;
@@ -207,10 +269,58 @@ pub struct Command {
     count: Arc<AtomicUsize>,
     pub template: template::Template,
     vars: HashSet<String>,
+    #[serde(flatten)]
+    pub restrictions: Restrictions,
+    /// Cooldowns currently in effect.
+    #[serde(skip)]
+    cooldowns: Arc<Mutex<Cooldowns>>,
     pub group: Option<String>,
     pub disabled: bool,
 }
 
+/// Restrictions on who can use a command, and how often.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct Restrictions {
+    /// Cooldown between each use of the command.
+    #[serde(default)]
+    pub cooldown: Option<Duration>,
+    /// Cooldown between each use of the command by the same user.
+    #[serde(default)]
+    pub user_cooldown: Option<Duration>,
+    /// The currency cost of using the command.
+    #[serde(default)]
+    pub cost: Option<i64>,
+    /// The scope, or `@role`, required to use the command.
+    #[serde(default)]
+    pub requires: Option<String>,
+}
+
+impl Restrictions {
+    fn from_db(command: &crate::models::Command) -> Self {
+        Self {
+            cooldown: command.cooldown.map(from_seconds),
+            user_cooldown: command.user_cooldown.map(from_seconds),
+            cost: command.cost,
+            requires: command.requires.clone(),
+        }
+    }
+}
+
+/// When a command was last used, globally and by individual users.
+#[derive(Debug, Default)]
+struct Cooldowns {
+    global: Option<Instant>,
+    users: HashMap<String, Instant>,
+}
+
+fn from_seconds(seconds: i32) -> Duration {
+    Duration::seconds(u64::try_from(seconds).unwrap_or_default())
+}
+
+fn to_seconds(duration: Duration) -> i32 {
+    i32::try_from(duration.num_seconds()).unwrap_or(i32::MAX)
+}
+
 /// Serialize the atomic count.
 fn serialize_count<S>(value: &Arc<AtomicUsize>, serializer: S) -> Result<S::Ok, S::Error>
 where
@@ -243,6 +353,8 @@ impl Command {
             count,
             template,
             vars,
+            restrictions: Restrictions::from_db(command),
+            cooldowns: Default::default(),
             group: command.group.clone(),
             disabled: command.disabled,
         })
@@ -265,6 +377,55 @@ impl Command {
     pub fn has_var(&self, var: &str) -> bool {
         self.vars.contains(var)
     }
+
+    /// Test if the rendered command accesses the given var or any of its
+    /// fields, like `counters.deaths`.
+    pub fn has_var_path(&self, var: &str) -> bool {
+        self.vars.iter().any(|v| {
+            v == var
+                || v.strip_prefix(var)
+                    .is_some_and(|rest| rest.starts_with('.'))
+        })
+    }
+
+    /// Get the remaining cooldown before the given user can use the command,
+    /// if any.
+    pub fn cooldown_remaining(&self, user: &str) -> Option<std::time::Duration> {
+        let now = Instant::now();
+        let cooldowns = self.cooldowns.lock();
+
+        let global = self
+            .restrictions
+            .cooldown
+            .zip(cooldowns.global)
+            .and_then(|(cooldown, at)| cooldown.as_std().checked_sub(now - at));
+
+        let user = self
+            .restrictions
+            .user_cooldown
+            .zip(cooldowns.users.get(user))
+            .and_then(|(cooldown, at)| cooldown.as_std().checked_sub(now - *at));
+
+        global.max(user).filter(|d| !d.is_zero())
+    }
+
+    /// Mark the command as used by the given user, starting its cooldowns.
+    pub fn start_cooldown(&self, user: &str) {
+        let now = Instant::now();
+        let mut cooldowns = self.cooldowns.lock();
+
+        if self.restrictions.cooldown.is_some() {
+            cooldowns.global = Some(now);
+        }
+
+        if let Some(cooldown) = self.restrictions.user_cooldown {
+            // Forget about users whose cooldown has expired.
+            cooldowns
+                .users
+                .retain(|_, at| now - *at < cooldown.as_std());
+            cooldowns.users.insert(user.to_owned(), now);
+        }
+    }
 }
 
 impl crate::Matchable for Command {
@@ -281,11 +442,35 @@ impl fmt::Display for Command {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(
             fmt,
-            "template = \"{template}\", pattern = {pattern}, group = {group}, disabled = {disabled}",
+            "template = \"{template}\", pattern = {pattern}, {restrictions}group = {group}, disabled = {disabled}",
             template = self.template,
             pattern = self.pattern,
+            restrictions = self.restrictions,
             group = self.group.as_deref().unwrap_or("*none*"),
             disabled = self.disabled,
         )
     }
 }
+
+/// Formats the restrictions which are set, each followed by a comma.
+impl fmt::Display for Restrictions {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(cooldown) = &self.cooldown {
+            write!(fmt, "cooldown = {}, ", cooldown)?;
+        }
+
+        if let Some(user_cooldown) = &self.user_cooldown {
+            write!(fmt, "user cooldown = {}, ", user_cooldown)?;
+        }
+
+        if let Some(cost) = self.cost {
+            write!(fmt, "cost = {}, ", cost)?;
+        }
+
+        if let Some(requires) = &self.requires {
+            write!(fmt, "requires = {}, ", requires)?;
+        }
+
+        Ok(())
+    }
+}
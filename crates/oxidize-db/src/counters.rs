@@ -0,0 +1,152 @@
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use anyhow::Result;
+use common::Channel;
+use diesel::prelude::*;
+use tokio::sync::RwLock;
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::Counter;
+
+/// Named counters, like the number of deaths in the current game.
+#[derive(Clone)]
+pub struct Counters {
+    db: crate::Database,
+    inner: Arc<RwLock<HashMap<crate::Key, i64>>>,
+}
+
+impl Counters {
+    /// Load all counters from the database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        use self::schema::counters::dsl;
+
+        let counters = db
+            .asyncify(move |c| Ok(dsl::counters.load::<Counter>(c)?))
+            .await?;
+
+        let mut inner = HashMap::new();
+
+        for counter in counters {
+            inner.insert(
+                crate::Key::new(&counter.channel, &counter.name),
+                counter.count,
+            );
+        }
+
+        Ok(Self {
+            db,
+            inner: Arc::new(RwLock::new(inner)),
+        })
+    }
+
+    /// Get the current value of the given counter.
+    pub async fn get(&self, channel: &Channel, name: &str) -> Option<i64> {
+        let key = crate::Key::new(channel, name);
+        self.inner.read().await.get(&key).copied()
+    }
+
+    /// Add to the given counter, creating it if it doesn't exist.
+    ///
+    /// Returns the new value of the counter.
+    pub async fn add(&self, channel: &Channel, name: &str, delta: i64) -> Result<i64> {
+        let key = crate::Key::new(channel, name);
+        let mut inner = self.inner.write().await;
+        let count = inner.get(&key).copied().unwrap_or_default();
+        let count = count.saturating_add(delta);
+        self.store(&key, count).await?;
+        inner.insert(key, count);
+        Ok(count)
+    }
+
+    /// Set the given counter, creating it if it doesn't exist.
+    pub async fn set(&self, channel: &Channel, name: &str, count: i64) -> Result<()> {
+        let key = crate::Key::new(channel, name);
+        let mut inner = self.inner.write().await;
+        self.store(&key, count).await?;
+        inner.insert(key, count);
+        Ok(())
+    }
+
+    /// Delete the given counter.
+    pub async fn delete(&self, channel: &Channel, name: &str) -> Result<bool> {
+        use self::schema::counters::dsl;
+
+        let key = crate::Key::new(channel, name);
+        let mut inner = self.inner.write().await;
+
+        if inner.remove(&key).is_none() {
+            return Ok(false);
+        }
+
+        self.db
+            .asyncify(move |c| {
+                diesel::delete(
+                    dsl::counters
+                        .filter(dsl::channel.eq(&key.channel).and(dsl::name.eq(&key.name))),
+                )
+                .execute(c)?;
+
+                Ok(())
+            })
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Get the values of all counters in the given channel, keyed by name.
+    ///
+    /// This is what templates see as `counters`.
+    pub async fn snapshot(&self, channel: &Channel) -> BTreeMap<String, i64> {
+        let inner = self.inner.read().await;
+
+        inner
+            .iter()
+            .filter(|(key, _)| key.channel == *channel)
+            .map(|(key, count)| (key.name.clone(), *count))
+            .collect()
+    }
+
+    /// List all counters in the given channel.
+    pub async fn list(&self, channel: &Channel) -> Vec<Counter> {
+        self.snapshot(channel)
+            .await
+            .into_iter()
+            .map(|(name, count)| Counter {
+                channel: channel.to_owned(),
+                name,
+                count,
+            })
+            .collect()
+    }
+
+    /// Persist the value of a single counter.
+    async fn store(&self, key: &crate::Key, count: i64) -> Result<()> {
+        use self::schema::counters::dsl;
+
+        let counter = Counter {
+            channel: key.channel.clone(),
+            name: key.name.clone(),
+            count,
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::replace_into(dsl::counters)
+                    .values(&counter)
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+}
+
+/// Test if the given string can be used as the name of a counter.
+///
+/// Names are restricted so that they can be accessed from templates, like
+/// `{{counters.deaths}}`.
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
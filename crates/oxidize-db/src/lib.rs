@@ -4,7 +4,7 @@
 mod macros;
 pub mod schema;
 
-mod after_streams;
+pub mod after_streams;
 pub use self::after_streams::AfterStreams;
 
 mod aliases;
@@ -13,25 +13,46 @@ pub use self::aliases::Aliases;
 pub mod commands;
 pub use self::commands::Commands;
 
+pub mod counters;
+pub use self::counters::{Counter, Counters};
+
 mod matcher;
 pub use self::matcher::{Captures, Key, Matchable, Matcher, Pattern};
 
 pub mod models;
 
+pub mod moderation_log;
+pub use self::moderation_log::ModerationLog;
+
 mod promotions;
 pub use self::promotions::{Promotion, Promotions};
 
+pub mod polls;
+pub use self::polls::Polls;
+
+pub mod queue;
+pub use self::queue::{Queue, QueueEntry};
+
+pub mod quotes;
+pub use self::quotes::{Quote, Quotes};
+
+pub mod raffles;
+pub use self::raffles::{RaffleWinner, Raffles};
+
 #[cfg(feature = "scripting")]
 mod script_storage;
 #[cfg(feature = "scripting")]
-pub use self::script_storage::ScriptStorage;
+pub use self::script_storage::{ScriptKeyEntry, ScriptKeys, ScriptStorage, SHARED_NAMESPACE};
 
 mod task;
 
 mod themes;
 pub use self::themes::Themes;
 
-mod words;
+pub mod triggers;
+pub use self::triggers::{Trigger, Triggers};
+
+pub mod words;
 pub use self::words::{Word, Words};
 
 use std::path::Path;
@@ -85,6 +106,11 @@ impl Database {
         })
     }
 
+    /// Open a database which only lives in memory, like when testing scripts.
+    pub fn memory() -> Result<Database> {
+        Self::open(Path::new(":memory:"))
+    }
+
     /// Run a blocking task with exlusive access to the database pool.
     pub async fn asyncify<F, T, E>(&self, task: F) -> Result<T, E>
     where
@@ -5,7 +5,9 @@ use diesel::{Insertable, Queryable};
 use serde::{Deserialize, Serialize};
 
 use crate::schema::{
-    after_streams, aliases, bad_words, balances, commands, promotions, script_keys, songs, themes,
+    after_streams, aliases, bad_word_exceptions, bad_words, balances, commands, counters,
+    moderation_log, poll_options, polls, promotions, queue_entries, quotes, raffle_winners,
+    script_keys, songs, themes, triggers,
 };
 
 #[derive(Serialize, Deserialize, Queryable, Insertable)]
@@ -46,6 +48,14 @@ pub struct Command {
     pub group: Option<String>,
     /// If the command is disabled.
     pub disabled: bool,
+    /// Cooldown in seconds between each use of the command.
+    pub cooldown: Option<i32>,
+    /// Cooldown in seconds between each use of the command by the same user.
+    pub user_cooldown: Option<i32>,
+    /// The currency cost of using the command.
+    pub cost: Option<i64>,
+    /// The scope or `@role` required to use the command.
+    pub requires: Option<String>,
 }
 
 #[derive(Debug, Clone, Default, diesel::AsChangeset)]
@@ -57,6 +67,16 @@ pub struct UpdateCommand<'a> {
     pub disabled: Option<bool>,
 }
 
+/// Changes the restrictions of a command, where `None` clears a restriction.
+#[derive(Debug, Clone, Default, diesel::AsChangeset)]
+#[diesel(table_name = commands, treat_none_as_null = true)]
+pub struct UpdateCommandRestrictions<'a> {
+    pub cooldown: Option<i32>,
+    pub user_cooldown: Option<i32>,
+    pub cost: Option<i64>,
+    pub requires: Option<&'a str>,
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Queryable, Insertable)]
 #[diesel(table_name = aliases)]
 pub struct Alias {
@@ -102,6 +122,14 @@ pub struct AfterStream {
     pub user: String,
     /// The text of the afterstream.
     pub text: String,
+    /// The category of the afterstream, like `bug`.
+    pub category: Option<String>,
+    /// When the afterstream was marked as done.
+    pub done_at: Option<NaiveDateTime>,
+    /// The id of the stream the afterstream was added during.
+    pub stream_id: Option<String>,
+    /// When the stream the afterstream was added during started.
+    pub stream_started_at: Option<NaiveDateTime>,
 }
 
 /// Insert model for afterstreams.
@@ -111,12 +139,202 @@ pub struct InsertAfterStream {
     pub channel: Option<String>,
     pub user: String,
     pub text: String,
+    pub category: Option<String>,
+    pub stream_id: Option<String>,
+    pub stream_started_at: Option<NaiveDateTime>,
 }
 
-#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Queryable, Insertable)]
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct ModerationLogEntry {
+    /// The unique identifier of the entry.
+    pub id: i32,
+    /// The channel the action was taken in.
+    pub channel: OwnedChannel,
+    /// When the action was taken.
+    pub added_at: NaiveDateTime,
+    /// If the action was taken by the bot, or observed in chat.
+    pub automated: bool,
+    /// The user the action was taken against, if any.
+    pub user: Option<String>,
+    /// The action taken.
+    pub action: String,
+    /// The duration of a timeout in seconds.
+    pub duration: Option<i32>,
+    /// The reason for the action.
+    pub reason: Option<String>,
+    /// The filter or word which triggered the action.
+    pub source: Option<String>,
+    /// The text of the message which triggered the action.
+    pub message: Option<String>,
+    /// The id of the message which triggered the action.
+    pub message_id: Option<String>,
+}
+
+/// Insert model for moderation log entries.
+#[derive(Insertable)]
+#[diesel(table_name = moderation_log)]
+pub struct InsertModerationLogEntry {
+    pub channel: OwnedChannel,
+    pub automated: bool,
+    pub user: Option<String>,
+    pub action: String,
+    pub duration: Option<i32>,
+    pub reason: Option<String>,
+    pub source: Option<String>,
+    pub message: Option<String>,
+    pub message_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct Quote {
+    /// The unique identifier of the quote.
+    pub id: i32,
+    /// The channel the quote belongs to.
+    pub channel: OwnedChannel,
+    /// When the quote was added.
+    pub added_at: NaiveDateTime,
+    /// The user that added the quote.
+    pub added_by: String,
+    /// The user being quoted.
+    pub author: String,
+    /// The game or category being streamed when the quote was added.
+    pub game: Option<String>,
+    /// The text of the quote.
+    pub text: String,
+}
+
+/// Insert model for quotes.
+#[derive(Insertable)]
+#[diesel(table_name = quotes)]
+pub struct InsertQuote {
+    pub channel: OwnedChannel,
+    pub added_by: String,
+    pub author: String,
+    pub game: Option<String>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default, diesel::AsChangeset)]
+#[diesel(table_name = quotes)]
+pub struct UpdateQuote<'a> {
+    pub author: Option<&'a str>,
+    pub text: Option<&'a str>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct RaffleWinner {
+    pub id: i32,
+    pub channel: OwnedChannel,
+    /// When the winner was drawn.
+    pub drawn_at: NaiveDateTime,
+    /// The user that won.
+    pub user: String,
+    /// The number of tickets the winner held.
+    pub tickets: i32,
+    /// The total number of tickets sold in the raffle.
+    pub total_tickets: i32,
+    /// The price of a single ticket.
+    pub ticket_price: i64,
+}
+
+/// Insert model for raffle winners.
+#[derive(Insertable)]
+#[diesel(table_name = raffle_winners)]
+pub struct InsertRaffleWinner {
+    pub channel: OwnedChannel,
+    pub user: String,
+    pub tickets: i32,
+    pub total_tickets: i32,
+    pub ticket_price: i64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct QueueEntry {
+    pub id: i32,
+    pub channel: OwnedChannel,
+    /// Login of the user in the queue.
+    pub user: String,
+    pub display_name: String,
+    /// When the user joined the queue.
+    pub joined_at: NaiveDateTime,
+    /// Users with a higher priority are ahead in the queue.
+    pub priority: i32,
+}
+
+/// Insert model for queue entries.
+#[derive(Insertable)]
+#[diesel(table_name = queue_entries)]
+pub struct InsertQueueEntry {
+    pub channel: OwnedChannel,
+    pub user: String,
+    pub display_name: String,
+    pub priority: i32,
+}
+
+/// The results of a closed poll.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct Poll {
+    pub id: i32,
+    pub channel: OwnedChannel,
+    pub question: String,
+    pub created_at: NaiveDateTime,
+    pub closed_at: NaiveDateTime,
+    /// If votes were weighted by the currency of the voter.
+    pub weighted: bool,
+}
+
+/// Insert model for polls.
+#[derive(Insertable)]
+#[diesel(table_name = polls)]
+pub struct InsertPoll {
+    pub channel: OwnedChannel,
+    pub question: String,
+    pub created_at: NaiveDateTime,
+    pub weighted: bool,
+}
+
+/// The result for a single option of a poll.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable)]
+pub struct PollOption {
+    pub poll_id: i32,
+    pub option: String,
+    pub description: Option<String>,
+    pub votes: i32,
+    pub weight: i64,
+}
+
+/// A named counter.
+#[derive(Debug, Clone, Queryable, Insertable, Serialize)]
+#[diesel(table_name = counters)]
+pub struct Counter {
+    pub channel: OwnedChannel,
+    pub name: String,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Insertable, Serialize, Deserialize)]
 pub struct BadWord {
     pub word: String,
+    #[serde(default)]
     pub why: Option<String>,
+    /// When a message contains several bad words, the most severe one
+    /// decides the action taken.
+    #[serde(default)]
+    pub severity: i32,
+    /// The action to take, see [`crate::words::Action`].
+    pub action: String,
+    /// The duration of a timeout in seconds.
+    #[serde(default)]
+    pub duration: Option<i32>,
+    /// Only act on the word while the stream is live.
+    #[serde(default)]
+    pub only_live: bool,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Queryable, Insertable)]
+pub struct BadWordException {
+    pub word: String,
+    pub exception: String,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Queryable)]
@@ -178,6 +396,47 @@ pub struct UpdatePromotion<'a> {
     pub disabled: Option<bool>,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, Queryable, Insertable)]
+pub struct Trigger {
+    /// The channel the trigger belongs to.
+    pub channel: OwnedChannel,
+    /// The name of the trigger.
+    pub name: String,
+    /// How the pattern is matched.
+    pub kind: String,
+    /// The pattern to match messages against.
+    pub pattern: String,
+    /// The template to respond with.
+    pub text: String,
+    /// Triggers with a higher priority are tested first.
+    pub priority: i32,
+    /// The cooldown in seconds between each time the trigger fires.
+    pub cooldown: i32,
+    /// If messages from moderators are ignored.
+    pub exempt_moderators: bool,
+    /// If messages from known bots are ignored.
+    pub exempt_bots: bool,
+    /// If the trigger only fires while the stream is live.
+    pub only_live: bool,
+    /// The group the trigger is part of, if any.
+    pub group: Option<String>,
+    /// If the trigger is disabled.
+    pub disabled: bool,
+}
+
+#[derive(Debug, Clone, Default, diesel::AsChangeset)]
+#[diesel(table_name = triggers)]
+pub struct UpdateTrigger<'a> {
+    pub kind: Option<&'a str>,
+    pub pattern: Option<&'a str>,
+    pub text: Option<&'a str>,
+    pub priority: Option<i32>,
+    pub cooldown: Option<i32>,
+    pub exempt_moderators: Option<bool>,
+    pub exempt_bots: Option<bool>,
+    pub only_live: Option<bool>,
+}
+
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Queryable, Insertable)]
 pub struct Theme {
     /// The channel the theme belongs to.
@@ -209,12 +468,17 @@ pub struct UpdateTheme<'a> {
 #[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Queryable, Insertable)]
 pub struct ScriptKey {
     pub channel: OwnedChannel,
+    /// The namespace of the script the key belongs to.
+    pub namespace: String,
     pub key: Vec<u8>,
     pub value: Vec<u8>,
+    /// When the key expires.
+    pub expires_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Clone, Default, diesel::AsChangeset)]
-#[diesel(table_name = script_keys)]
+#[diesel(table_name = script_keys, treat_none_as_null = true)]
 pub struct SetScriptKeyValue<'a> {
     pub value: &'a [u8],
+    pub expires_at: Option<NaiveDateTime>,
 }
@@ -0,0 +1,202 @@
+use std::fmt;
+
+use anyhow::Result;
+use common::{Channel, OwnedChannel};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::ModerationLogEntry;
+
+/// The default number of entries to list.
+const DEFAULT_LIMIT: i64 = 100;
+
+/// A moderation action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// The user was warned.
+    Warn,
+    /// A single message was deleted.
+    Delete,
+    /// The user was timed out.
+    Timeout,
+    /// The user was banned.
+    Ban,
+    /// The user was unbanned.
+    Unban,
+    /// All messages in chat were cleared.
+    Clear,
+    /// The message was flagged without any other action being taken.
+    Flag,
+}
+
+impl Action {
+    /// Get the action as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Warn => "warn",
+            Action::Delete => "delete",
+            Action::Timeout => "timeout",
+            Action::Ban => "ban",
+            Action::Unban => "unban",
+            Action::Clear => "clear",
+            Action::Flag => "flag",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+/// A moderation action to record.
+#[derive(Debug, Clone, Copy)]
+pub struct Entry<'a> {
+    /// The channel the action was taken in.
+    pub channel: &'a Channel,
+    /// If the action was taken by the bot.
+    pub automated: bool,
+    /// The user the action was taken against.
+    pub user: Option<&'a str>,
+    /// The action taken.
+    pub action: Action,
+    /// The duration of a timeout in seconds.
+    pub duration: Option<u64>,
+    /// The reason for the action.
+    pub reason: Option<&'a str>,
+    /// The filter or word which triggered the action.
+    pub source: Option<&'a str>,
+    /// The text of the message which triggered the action.
+    pub message: Option<&'a str>,
+    /// The id of the message which triggered the action.
+    pub message_id: Option<&'a str>,
+}
+
+impl<'a> Entry<'a> {
+    /// Construct a new entry for the given action.
+    pub fn new(channel: &'a Channel, automated: bool, action: Action) -> Self {
+        Self {
+            channel,
+            automated,
+            user: None,
+            action,
+            duration: None,
+            reason: None,
+            source: None,
+            message: None,
+            message_id: None,
+        }
+    }
+}
+
+/// Filter to apply when listing the moderation log.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Filter {
+    /// Only list entries in the given channel.
+    #[serde(default)]
+    pub channel: Option<OwnedChannel>,
+    /// Only list entries against the given user.
+    #[serde(default)]
+    pub user: Option<String>,
+    /// Only list entries with the given action.
+    #[serde(default)]
+    pub action: Option<Action>,
+    /// Only list entries which were or weren't taken by the bot.
+    #[serde(default)]
+    pub automated: Option<bool>,
+    /// Only list entries triggered by the given filter or word.
+    #[serde(default)]
+    pub source: Option<String>,
+    /// Only list entries older than the entry with the given id.
+    #[serde(default)]
+    pub before: Option<i32>,
+    /// The maximum number of entries to list.
+    #[serde(default)]
+    pub limit: Option<i64>,
+}
+
+#[derive(Clone)]
+pub struct ModerationLog {
+    db: crate::Database,
+}
+
+impl ModerationLog {
+    /// Open the moderation log database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Record the given moderation action.
+    pub async fn push(&self, entry: Entry<'_>) -> Result<()> {
+        use self::schema::moderation_log::dsl;
+
+        let entry = models::InsertModerationLogEntry {
+            channel: entry.channel.to_owned(),
+            automated: entry.automated,
+            user: entry.user.map(str::to_owned),
+            action: entry.action.as_str().to_owned(),
+            duration: entry.duration.map(|d| i32::try_from(d).unwrap_or(i32::MAX)),
+            reason: entry.reason.map(str::to_owned),
+            source: entry.source.map(str::to_owned),
+            message: entry.message.map(str::to_owned),
+            message_id: entry.message_id.map(str::to_owned),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::moderation_log)
+                    .values(&entry)
+                    .execute(c)?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// List entries matching the given filter, newest first.
+    pub async fn list(&self, filter: Filter) -> Result<Vec<ModerationLogEntry>> {
+        use self::schema::moderation_log::dsl;
+
+        self.db
+            .asyncify(move |c| {
+                let mut query = dsl::moderation_log.into_boxed();
+
+                if let Some(channel) = filter.channel {
+                    query = query.filter(dsl::channel.eq(channel));
+                }
+
+                if let Some(user) = filter.user {
+                    query = query.filter(dsl::user.eq(user.to_lowercase()));
+                }
+
+                if let Some(action) = filter.action {
+                    query = query.filter(dsl::action.eq(action.as_str()));
+                }
+
+                if let Some(automated) = filter.automated {
+                    query = query.filter(dsl::automated.eq(automated));
+                }
+
+                if let Some(source) = filter.source {
+                    query = query.filter(dsl::source.eq(source));
+                }
+
+                if let Some(before) = filter.before {
+                    query = query.filter(dsl::id.lt(before));
+                }
+
+                let limit = filter.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, 1000);
+
+                Ok(query
+                    .order(dsl::id.desc())
+                    .limit(limit)
+                    .load::<models::ModerationLogEntry>(c)?)
+            })
+            .await
+    }
+}
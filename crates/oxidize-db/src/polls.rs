@@ -0,0 +1,113 @@
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use common::Channel;
+use diesel::prelude::*;
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::{Poll, PollOption};
+
+/// The tally for a single option of a poll.
+#[derive(Debug, Clone)]
+pub struct Tally {
+    /// The keyword used to vote for the option.
+    pub option: String,
+    pub description: Option<String>,
+    /// The number of users that voted for the option.
+    pub votes: u32,
+    /// The total weight of the votes.
+    pub weight: i64,
+}
+
+/// Results of closed polls.
+#[derive(Clone)]
+pub struct Polls {
+    db: crate::Database,
+}
+
+impl Polls {
+    /// Open the polls database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Store the results of a closed poll.
+    pub async fn insert(
+        &self,
+        channel: &Channel,
+        question: &str,
+        created_at: DateTime<Utc>,
+        weighted: bool,
+        tallies: &[Tally],
+    ) -> Result<i32> {
+        use self::schema::poll_options::dsl as options;
+        use self::schema::polls::dsl;
+
+        let poll = models::InsertPoll {
+            channel: channel.to_owned(),
+            question: question.to_owned(),
+            created_at: created_at.naive_utc(),
+            weighted,
+        };
+
+        let tallies = tallies.to_vec();
+
+        self.db
+            .asyncify(move |c| {
+                c.transaction(move |c| {
+                    diesel::insert_into(dsl::polls).values(&poll).execute(c)?;
+
+                    let id = dsl::polls
+                        .select(dsl::id)
+                        .order(dsl::id.desc())
+                        .first::<i32>(c)?;
+
+                    for tally in tallies {
+                        let option = PollOption {
+                            poll_id: id,
+                            option: tally.option,
+                            description: tally.description,
+                            votes: i32::try_from(tally.votes)?,
+                            weight: tally.weight,
+                        };
+
+                        diesel::insert_into(options::poll_options)
+                            .values(&option)
+                            .execute(c)?;
+                    }
+
+                    Ok(id)
+                })
+            })
+            .await
+    }
+
+    /// Get the results of the most recently closed poll in the given channel.
+    pub async fn latest(&self, channel: &Channel) -> Result<Option<(Poll, Vec<PollOption>)>> {
+        use self::schema::poll_options::dsl as options;
+        use self::schema::polls::dsl;
+
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                let poll = dsl::polls
+                    .filter(dsl::channel.eq(&channel))
+                    .order(dsl::id.desc())
+                    .first::<Poll>(c)
+                    .optional()?;
+
+                let Some(poll) = poll else {
+                    return Ok(None);
+                };
+
+                let options = options::poll_options
+                    .filter(options::poll_id.eq(poll.id))
+                    .load::<PollOption>(c)?;
+
+                Ok(Some((poll, options)))
+            })
+            .await
+    }
+}
@@ -0,0 +1,199 @@
+use anyhow::Result;
+use common::Channel;
+use diesel::prelude::*;
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::QueueEntry;
+
+/// The priority of a user in the queue.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Priority {
+    /// Users who joined the queue normally.
+    Normal,
+    /// Subscribers, who are placed ahead of everyone who joined normally.
+    Subscriber,
+    /// Users who paid to skip ahead of everyone else.
+    Skipped,
+}
+
+impl Priority {
+    /// Convert into the value stored in the database.
+    fn to_db(self) -> i32 {
+        match self {
+            Priority::Normal => 0,
+            Priority::Subscriber => 1,
+            Priority::Skipped => 2,
+        }
+    }
+}
+
+/// A queue of viewers, like the line for a community game night.
+#[derive(Clone)]
+pub struct Queue {
+    db: crate::Database,
+}
+
+impl Queue {
+    /// Open the queue database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Add a user to the queue.
+    ///
+    /// Returns `false` if the user is already in the queue.
+    pub async fn join(
+        &self,
+        channel: &Channel,
+        user: &str,
+        display_name: &str,
+        priority: Priority,
+    ) -> Result<bool> {
+        use self::schema::queue_entries::dsl;
+
+        let entry = models::InsertQueueEntry {
+            channel: channel.to_owned(),
+            user: user.to_owned(),
+            display_name: display_name.to_owned(),
+            priority: priority.to_db(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                let existing = dsl::queue_entries
+                    .filter(
+                        dsl::channel
+                            .eq(&entry.channel)
+                            .and(dsl::user.eq(&entry.user)),
+                    )
+                    .select(dsl::id)
+                    .first::<i32>(c)
+                    .optional()?;
+
+                if existing.is_some() {
+                    return Ok(false);
+                }
+
+                diesel::insert_into(dsl::queue_entries)
+                    .values(&entry)
+                    .execute(c)?;
+
+                Ok(true)
+            })
+            .await
+    }
+
+    /// Remove a user from the queue.
+    pub async fn leave(&self, channel: &Channel, user: &str) -> Result<bool> {
+        use self::schema::queue_entries::dsl;
+
+        let channel = channel.to_owned();
+        let user = user.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::queue_entries.filter(dsl::channel.eq(&channel).and(dsl::user.eq(&user))),
+                )
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Raise the priority of a user in the queue.
+    ///
+    /// Returns `false` if the user isn't in the queue or already has the
+    /// given priority or higher.
+    pub async fn raise_priority(
+        &self,
+        channel: &Channel,
+        user: &str,
+        priority: Priority,
+    ) -> Result<bool> {
+        use self::schema::queue_entries::dsl;
+
+        let channel = channel.to_owned();
+        let user = user.to_owned();
+        let priority = priority.to_db();
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::update(
+                    dsl::queue_entries.filter(
+                        dsl::channel
+                            .eq(&channel)
+                            .and(dsl::user.eq(&user))
+                            .and(dsl::priority.lt(priority)),
+                    ),
+                )
+                .set(dsl::priority.eq(priority))
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// List everyone in the queue, in order.
+    pub async fn list(&self, channel: &Channel) -> Result<Vec<QueueEntry>> {
+        use self::schema::queue_entries::dsl;
+
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::queue_entries
+                    .filter(dsl::channel.eq(&channel))
+                    .order((dsl::priority.desc(), dsl::id.asc()))
+                    .load::<QueueEntry>(c)?)
+            })
+            .await
+    }
+
+    /// Get the position of a user in the queue, starting at 1.
+    pub async fn position(&self, channel: &Channel, user: &str) -> Result<Option<usize>> {
+        let entries = self.list(channel).await?;
+        Ok(entries.iter().position(|e| e.user == user).map(|p| p + 1))
+    }
+
+    /// Remove and return the first `count` users in the queue.
+    pub async fn take(&self, channel: &Channel, count: usize) -> Result<Vec<QueueEntry>> {
+        use self::schema::queue_entries::dsl;
+
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                let entries = dsl::queue_entries
+                    .filter(dsl::channel.eq(&channel))
+                    .order((dsl::priority.desc(), dsl::id.asc()))
+                    .limit(i64::try_from(count)?)
+                    .load::<QueueEntry>(c)?;
+
+                let ids = entries.iter().map(|e| e.id).collect::<Vec<_>>();
+                diesel::delete(dsl::queue_entries.filter(dsl::id.eq_any(ids))).execute(c)?;
+                Ok(entries)
+            })
+            .await
+    }
+
+    /// Remove everyone from the queue, returning how many were removed.
+    pub async fn clear(&self, channel: &Channel) -> Result<usize> {
+        use self::schema::queue_entries::dsl;
+
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(
+                    diesel::delete(dsl::queue_entries.filter(dsl::channel.eq(&channel)))
+                        .execute(c)?,
+                )
+            })
+            .await
+    }
+}
@@ -0,0 +1,192 @@
+use anyhow::Result;
+use common::Channel;
+use diesel::prelude::*;
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::Quote;
+
+diesel::sql_function!(fn random() -> Integer);
+
+#[derive(Clone)]
+pub struct Quotes {
+    db: crate::Database,
+}
+
+impl Quotes {
+    /// Open the quotes database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Add a quote, returning its id.
+    pub async fn insert(
+        &self,
+        channel: &Channel,
+        added_by: &str,
+        author: &str,
+        game: Option<&str>,
+        text: &str,
+    ) -> Result<i32> {
+        use self::schema::quotes::dsl;
+
+        let quote = models::InsertQuote {
+            channel: channel.to_owned(),
+            added_by: added_by.to_owned(),
+            author: author.to_owned(),
+            game: game.map(str::to_owned),
+            text: text.to_owned(),
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::quotes).values(&quote).execute(c)?;
+
+                let id = dsl::quotes
+                    .select(dsl::id)
+                    .order(dsl::id.desc())
+                    .first::<i32>(c)?;
+
+                Ok(id)
+            })
+            .await
+    }
+
+    /// Edit the author and text of the given quote.
+    pub async fn edit(
+        &self,
+        channel: &Channel,
+        id: i32,
+        author: Option<&str>,
+        text: Option<&str>,
+    ) -> Result<bool> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_owned();
+        let author = author.map(str::to_owned);
+        let text = text.map(str::to_owned);
+
+        self.db
+            .asyncify(move |c| {
+                let set = models::UpdateQuote {
+                    author: author.as_deref(),
+                    text: text.as_deref(),
+                };
+
+                let count = diesel::update(
+                    dsl::quotes.filter(dsl::channel.eq(&channel).and(dsl::id.eq(id))),
+                )
+                .set(&set)
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Delete the quote with the given id.
+    pub async fn delete(&self, channel: &Channel, id: i32) -> Result<bool> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::quotes.filter(dsl::channel.eq(&channel).and(dsl::id.eq(id))),
+                )
+                .execute(c)?;
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Get the quote with the given id.
+    pub async fn get(&self, channel: &Channel, id: i32) -> Result<Option<Quote>> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::quotes
+                    .filter(dsl::channel.eq(&channel).and(dsl::id.eq(id)))
+                    .first::<Quote>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Get a random quote.
+    pub async fn random(&self, channel: &Channel) -> Result<Option<Quote>> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::quotes
+                    .filter(dsl::channel.eq(&channel))
+                    .order(random())
+                    .first::<Quote>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// Search for quotes whose text or author contains the given query,
+    /// ignoring case.
+    pub async fn search(&self, channel: &Channel, query: &str) -> Result<Vec<Quote>> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_owned();
+        let pattern = format!("%{}%", escape_like(query));
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::quotes
+                    .filter(dsl::channel.eq(&channel))
+                    .filter(
+                        dsl::text
+                            .like(&pattern)
+                            .escape('\\')
+                            .or(dsl::author.like(&pattern).escape('\\')),
+                    )
+                    .order(dsl::id.asc())
+                    .load::<Quote>(c)?)
+            })
+            .await
+    }
+
+    /// List all quotes in the given channel.
+    pub async fn list(&self, channel: &Channel) -> Result<Vec<Quote>> {
+        use self::schema::quotes::dsl;
+
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::quotes
+                    .filter(dsl::channel.eq(&channel))
+                    .order(dsl::id.asc())
+                    .load::<Quote>(c)?)
+            })
+            .await
+    }
+}
+
+/// Escape the special characters of a `LIKE` pattern.
+fn escape_like(query: &str) -> String {
+    let mut out = String::with_capacity(query.len());
+
+    for c in query.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            out.push('\\');
+        }
+
+        out.push(c);
+    }
+
+    out
+}
@@ -0,0 +1,67 @@
+use anyhow::Result;
+use common::Channel;
+use diesel::prelude::*;
+
+use crate::models;
+use crate::schema;
+
+pub use self::models::RaffleWinner;
+
+/// History of raffle winners.
+#[derive(Clone)]
+pub struct Raffles {
+    db: crate::Database,
+}
+
+impl Raffles {
+    /// Open the raffles database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// Record the winner of a raffle.
+    pub async fn insert_winner(
+        &self,
+        channel: &Channel,
+        user: &str,
+        tickets: u32,
+        total_tickets: u32,
+        ticket_price: i64,
+    ) -> Result<()> {
+        use self::schema::raffle_winners::dsl;
+
+        let winner = models::InsertRaffleWinner {
+            channel: channel.to_owned(),
+            user: user.to_owned(),
+            tickets: i32::try_from(tickets)?,
+            total_tickets: i32::try_from(total_tickets)?,
+            ticket_price,
+        };
+
+        self.db
+            .asyncify(move |c| {
+                diesel::insert_into(dsl::raffle_winners)
+                    .values(&winner)
+                    .execute(c)?;
+                Ok(())
+            })
+            .await
+    }
+
+    /// List the most recent winners in the given channel, newest first.
+    pub async fn winners(&self, channel: &Channel, limit: i64) -> Result<Vec<RaffleWinner>> {
+        use self::schema::raffle_winners::dsl;
+
+        let channel = channel.to_owned();
+
+        self.db
+            .asyncify(move |c| {
+                Ok(dsl::raffle_winners
+                    .filter(dsl::channel.eq(&channel))
+                    .order(dsl::id.desc())
+                    .limit(limit)
+                    .load::<RaffleWinner>(c)?)
+            })
+            .await
+    }
+}
@@ -18,6 +18,10 @@ table! {
         text -> Text,
         group -> Nullable<Text>,
         disabled -> Bool,
+        cooldown -> Nullable<Integer>,
+        user_cooldown -> Nullable<Integer>,
+        cost -> Nullable<BigInt>,
+        requires -> Nullable<Text>,
     }
 }
 
@@ -28,6 +32,10 @@ table! {
         added_at -> Timestamp,
         user -> Text,
         text -> Text,
+        category -> Nullable<Text>,
+        done_at -> Nullable<Timestamp>,
+        stream_id -> Nullable<Text>,
+        stream_started_at -> Nullable<Timestamp>,
     }
 }
 
@@ -35,6 +43,17 @@ table! {
     bad_words (word) {
         word -> Text,
         why -> Nullable<Text>,
+        severity -> Integer,
+        action -> Text,
+        duration -> Nullable<Integer>,
+        only_live -> Bool,
+    }
+}
+
+table! {
+    bad_word_exceptions (word, exception) {
+        word -> Text,
+        exception -> Text,
     }
 }
 
@@ -81,6 +100,23 @@ table! {
     }
 }
 
+table! {
+    triggers (channel, name) {
+        channel -> Text,
+        name -> Text,
+        kind -> Text,
+        pattern -> Text,
+        text -> Text,
+        priority -> Integer,
+        cooldown -> Integer,
+        exempt_moderators -> Bool,
+        exempt_bots -> Bool,
+        only_live -> Bool,
+        group -> Nullable<Text>,
+        disabled -> Bool,
+    }
+}
+
 table! {
     themes (channel, name) {
         channel -> Text,
@@ -110,9 +146,91 @@ table! {
 }
 
 table! {
-    script_keys (channel, key) {
+    script_keys (channel, namespace, key) {
         channel -> Text,
+        namespace -> Text,
         key -> Binary,
         value -> Binary,
+        expires_at -> Nullable<Timestamp>,
+    }
+}
+
+table! {
+    moderation_log (id) {
+        id -> Integer,
+        channel -> Text,
+        added_at -> Timestamp,
+        automated -> Bool,
+        user -> Nullable<Text>,
+        action -> Text,
+        duration -> Nullable<Integer>,
+        reason -> Nullable<Text>,
+        source -> Nullable<Text>,
+        message -> Nullable<Text>,
+        message_id -> Nullable<Text>,
+    }
+}
+
+table! {
+    quotes (id) {
+        id -> Integer,
+        channel -> Text,
+        added_at -> Timestamp,
+        added_by -> Text,
+        author -> Text,
+        game -> Nullable<Text>,
+        text -> Text,
+    }
+}
+
+table! {
+    counters (channel, name) {
+        channel -> Text,
+        name -> Text,
+        count -> BigInt,
+    }
+}
+
+table! {
+    raffle_winners (id) {
+        id -> Integer,
+        channel -> Text,
+        drawn_at -> Timestamp,
+        user -> Text,
+        tickets -> Integer,
+        total_tickets -> Integer,
+        ticket_price -> BigInt,
+    }
+}
+
+table! {
+    queue_entries (id) {
+        id -> Integer,
+        channel -> Text,
+        user -> Text,
+        display_name -> Text,
+        joined_at -> Timestamp,
+        priority -> Integer,
+    }
+}
+
+table! {
+    polls (id) {
+        id -> Integer,
+        channel -> Text,
+        question -> Text,
+        created_at -> Timestamp,
+        closed_at -> Timestamp,
+        weighted -> Bool,
+    }
+}
+
+table! {
+    poll_options (poll_id, option) {
+        poll_id -> Integer,
+        option -> Text,
+        description -> Nullable<Text>,
+        votes -> Integer,
+        weight -> BigInt,
     }
 }
@@ -1,63 +1,81 @@
-use anyhow::Result;
+use std::time;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use diesel::prelude::*;
+use serde::Serialize;
 
 use common::{Channel, OwnedChannel};
 
+use crate::models;
+use crate::schema::script_keys::dsl;
+
+/// The namespace for keys which don't belong to a single script.
+///
+/// Keys stored before scripts had namespaces are migrated into it, scripts
+/// which want to keep using them have to declare it with
+/// `reg.namespace("shared")`.
+pub const SHARED_NAMESPACE: &str = "shared";
+
 #[derive(Clone)]
 pub struct ScriptStorage {
     channel: OwnedChannel,
+    namespace: String,
     db: crate::Database,
 }
 
 impl ScriptStorage {
-    /// Open the script storage database.
+    /// Open the script storage database, using the shared namespace.
     pub fn new(channel: &Channel, db: crate::Database) -> Self {
         Self {
             channel: channel.to_owned(),
+            namespace: SHARED_NAMESPACE.to_owned(),
             db,
         }
     }
 
+    /// Get a copy of the storage which stores keys in the given namespace.
+    pub fn namespaced(&self, namespace: &str) -> Self {
+        Self {
+            channel: self.channel.clone(),
+            namespace: namespace.to_owned(),
+            db: self.db.clone(),
+        }
+    }
+
     /// Set the given key.
     pub async fn set<K, V>(&self, key: K, value: V) -> Result<()>
     where
         K: 'static + Send + serde::Serialize,
         V: 'static + Send + serde::Serialize,
     {
-        use crate::schema::script_keys::dsl;
+        self.store(key, value, None).await
+    }
+
+    /// Set the given key, which expires once `ttl` has passed.
+    pub async fn set_expiring<K, V>(&self, key: K, value: V, ttl: time::Duration) -> Result<()>
+    where
+        K: 'static + Send + serde::Serialize,
+        V: 'static + Send + serde::Serialize,
+    {
+        let ttl = chrono::Duration::from_std(ttl)?;
+        let expires_at = Utc::now().naive_utc() + ttl;
+        self.store(key, value, Some(expires_at)).await
+    }
 
+    async fn store<K, V>(&self, key: K, value: V, expires_at: Option<NaiveDateTime>) -> Result<()>
+    where
+        K: 'static + Send + serde::Serialize,
+        V: 'static + Send + serde::Serialize,
+    {
         let channel = self.channel.clone();
+        let namespace = self.namespace.clone();
 
         self.db
             .asyncify(move |c| {
                 let key = serde_cbor::to_vec(&key)?;
                 let value = serde_cbor::to_vec(&value)?;
-
-                let filter =
-                    dsl::script_keys.filter(dsl::channel.eq(&channel).and(dsl::key.eq(&key)));
-
-                let first = filter.first::<crate::models::ScriptKey>(c).optional()?;
-
-                match first {
-                    None => {
-                        let script_key = crate::models::ScriptKey {
-                            channel,
-                            key,
-                            value,
-                        };
-
-                        diesel::insert_into(dsl::script_keys)
-                            .values(&script_key)
-                            .execute(c)?;
-
-                        Ok(())
-                    }
-                    Some(..) => {
-                        let set = crate::models::SetScriptKeyValue { value: &value };
-                        diesel::update(filter).set(&set).execute(c)?;
-                        Ok(())
-                    }
-                }
+                store(c, channel, namespace, key, value, expires_at)
             })
             .await
     }
@@ -68,18 +86,27 @@ impl ScriptStorage {
         K: 'static + Send + serde::Serialize,
         for<'de> V: 'static + Send + serde::Deserialize<'de>,
     {
-        use crate::schema::script_keys::dsl;
-
         let channel = self.channel.clone();
+        let namespace = self.namespace.clone();
 
         self.db
             .asyncify(move |c| {
                 let key = serde_cbor::to_vec(&key)?;
 
-                let filter =
-                    dsl::script_keys.filter(dsl::channel.eq(&channel).and(dsl::key.eq(&key)));
-
-                let first = filter.first::<crate::models::ScriptKey>(c).optional()?;
+                let first = dsl::script_keys
+                    .filter(
+                        dsl::channel
+                            .eq(&channel)
+                            .and(dsl::namespace.eq(&namespace))
+                            .and(dsl::key.eq(&key)),
+                    )
+                    .filter(
+                        dsl::expires_at
+                            .is_null()
+                            .or(dsl::expires_at.gt(Utc::now().naive_utc())),
+                    )
+                    .first::<models::ScriptKey>(c)
+                    .optional()?;
 
                 match first {
                     None => Ok(None),
@@ -91,4 +118,428 @@ impl ScriptStorage {
             })
             .await
     }
+
+    /// Delete the given key.
+    ///
+    /// Returns `true` if the key existed.
+    pub async fn delete<K>(&self, key: K) -> Result<bool>
+    where
+        K: 'static + Send + serde::Serialize,
+    {
+        let channel = self.channel.clone();
+        let namespace = self.namespace.clone();
+
+        self.db
+            .asyncify(move |c| {
+                let key = serde_cbor::to_vec(&key)?;
+                purge_expired(c)?;
+
+                let count = diesel::delete(
+                    dsl::script_keys.filter(
+                        dsl::channel
+                            .eq(&channel)
+                            .and(dsl::namespace.eq(&namespace))
+                            .and(dsl::key.eq(&key)),
+                    ),
+                )
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// List all keys which haven't expired.
+    pub async fn keys<K>(&self) -> Result<Vec<K>>
+    where
+        for<'de> K: 'static + Send + serde::Deserialize<'de>,
+    {
+        let channel = self.channel.clone();
+        let namespace = self.namespace.clone();
+
+        self.db
+            .asyncify(move |c| {
+                purge_expired(c)?;
+
+                let keys = dsl::script_keys
+                    .select(dsl::key)
+                    .filter(dsl::channel.eq(&channel).and(dsl::namespace.eq(&namespace)))
+                    .load::<Vec<u8>>(c)?;
+
+                let mut out = Vec::with_capacity(keys.len());
+
+                for key in keys {
+                    out.push(serde_cbor::from_slice(&key)?);
+                }
+
+                Ok(out)
+            })
+            .await
+    }
+
+    /// Increment the integer stored in the given key by `amount`, treating a
+    /// missing key as zero.
+    ///
+    /// The key keeps its expiry time, if it has one. Returns the new value.
+    pub async fn increment<K>(&self, key: K, amount: i64) -> Result<i64>
+    where
+        K: 'static + Send + serde::Serialize,
+    {
+        let channel = self.channel.clone();
+        let namespace = self.namespace.clone();
+
+        self.db
+            .asyncify(move |c| {
+                let key = serde_cbor::to_vec(&key)?;
+
+                c.transaction(|c| {
+                    purge_expired(c)?;
+
+                    let first = dsl::script_keys
+                        .filter(
+                            dsl::channel
+                                .eq(&channel)
+                                .and(dsl::namespace.eq(&namespace))
+                                .and(dsl::key.eq(&key)),
+                        )
+                        .first::<models::ScriptKey>(c)
+                        .optional()?;
+
+                    let (current, expires_at) = match first {
+                        Some(row) => {
+                            let current = serde_cbor::from_slice::<i64>(&row.value)
+                                .map_err(|_| anyhow!("stored value is not an integer"))?;
+                            (current, row.expires_at)
+                        }
+                        None => (0, None),
+                    };
+
+                    let Some(value) = current.checked_add(amount) else {
+                        bail!("integer overflow when incrementing");
+                    };
+
+                    store(
+                        c,
+                        channel,
+                        namespace,
+                        key,
+                        serde_cbor::to_vec(&value)?,
+                        expires_at,
+                    )?;
+
+                    Ok(value)
+                })
+            })
+            .await
+    }
+}
+
+/// A single key in script storage, as shown to the streamer.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScriptKeyEntry {
+    pub channel: OwnedChannel,
+    pub namespace: String,
+    /// The encoded key, in hex. Used to address the key when modifying it.
+    pub id: String,
+    pub key: serde_cbor::Value,
+    pub value: serde_cbor::Value,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+/// Administrative access to the data stored by all scripts.
+#[derive(Clone)]
+pub struct ScriptKeys {
+    db: crate::Database,
+}
+
+impl ScriptKeys {
+    /// Open the script keys database.
+    pub async fn load(db: crate::Database) -> Result<Self> {
+        Ok(Self { db })
+    }
+
+    /// List all keys which haven't expired, optionally limited to a channel
+    /// and a namespace.
+    pub async fn list(
+        &self,
+        channel: Option<&Channel>,
+        namespace: Option<&str>,
+    ) -> Result<Vec<ScriptKeyEntry>> {
+        let channel = channel.map(|c| c.to_owned());
+        let namespace = namespace.map(str::to_owned);
+
+        self.db
+            .asyncify(move |c| {
+                purge_expired(c)?;
+
+                let mut query = dsl::script_keys.into_boxed();
+
+                if let Some(channel) = channel {
+                    query = query.filter(dsl::channel.eq(channel));
+                }
+
+                if let Some(namespace) = namespace {
+                    query = query.filter(dsl::namespace.eq(namespace));
+                }
+
+                let rows = query
+                    .order((dsl::channel, dsl::namespace, dsl::key))
+                    .load::<models::ScriptKey>(c)?;
+
+                let mut out = Vec::with_capacity(rows.len());
+
+                for row in rows {
+                    out.push(ScriptKeyEntry {
+                        id: to_hex(&row.key),
+                        key: serde_cbor::from_slice(&row.key)?,
+                        value: serde_cbor::from_slice(&row.value)?,
+                        channel: row.channel,
+                        namespace: row.namespace,
+                        expires_at: row
+                            .expires_at
+                            .map(|at| DateTime::from_naive_utc_and_offset(at, Utc)),
+                    });
+                }
+
+                Ok(out)
+            })
+            .await
+    }
+
+    /// Set the value of the key with the given hex id, which expires once
+    /// `ttl` has passed if specified.
+    pub async fn set<V>(
+        &self,
+        channel: &Channel,
+        namespace: &str,
+        id: &str,
+        value: V,
+        ttl: Option<time::Duration>,
+    ) -> Result<()>
+    where
+        V: 'static + Send + serde::Serialize,
+    {
+        let channel = channel.to_owned();
+        let namespace = namespace.to_owned();
+        let key = from_hex(id)?;
+
+        let expires_at = match ttl {
+            Some(ttl) => Some(Utc::now().naive_utc() + chrono::Duration::from_std(ttl)?),
+            None => None,
+        };
+
+        self.db
+            .asyncify(move |c| {
+                let value = serde_cbor::to_vec(&value)?;
+                store(c, channel, namespace, key, value, expires_at)
+            })
+            .await
+    }
+
+    /// Delete the key with the given hex id.
+    ///
+    /// Returns `true` if the key existed.
+    pub async fn delete(&self, channel: &Channel, namespace: &str, id: &str) -> Result<bool> {
+        let channel = channel.to_owned();
+        let namespace = namespace.to_owned();
+        let key = from_hex(id)?;
+
+        self.db
+            .asyncify(move |c| {
+                let count = diesel::delete(
+                    dsl::script_keys.filter(
+                        dsl::channel
+                            .eq(&channel)
+                            .and(dsl::namespace.eq(&namespace))
+                            .and(dsl::key.eq(&key)),
+                    ),
+                )
+                .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+}
+
+/// Insert or update the given key.
+fn store(
+    c: &mut SqliteConnection,
+    channel: OwnedChannel,
+    namespace: String,
+    key: Vec<u8>,
+    value: Vec<u8>,
+    expires_at: Option<NaiveDateTime>,
+) -> Result<()> {
+    let filter = dsl::script_keys.filter(
+        dsl::channel
+            .eq(&channel)
+            .and(dsl::namespace.eq(&namespace))
+            .and(dsl::key.eq(&key)),
+    );
+
+    let first = filter.first::<models::ScriptKey>(c).optional()?;
+
+    match first {
+        None => {
+            let script_key = models::ScriptKey {
+                channel,
+                namespace,
+                key,
+                value,
+                expires_at,
+            };
+
+            diesel::insert_into(dsl::script_keys)
+                .values(&script_key)
+                .execute(c)?;
+        }
+        Some(..) => {
+            let set = models::SetScriptKeyValue {
+                value: &value,
+                expires_at,
+            };
+
+            diesel::update(filter).set(&set).execute(c)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Delete all keys which have expired.
+fn purge_expired(c: &mut SqliteConnection) -> Result<()> {
+    diesel::delete(dsl::script_keys.filter(dsl::expires_at.le(Utc::now().naive_utc())))
+        .execute(c)?;
+    Ok(())
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    use std::fmt::Write as _;
+
+    let mut out = String::with_capacity(bytes.len() * 2);
+
+    for b in bytes {
+        let _ = write!(out, "{:02x}", b);
+    }
+
+    out
+}
+
+fn from_hex(s: &str) -> Result<Vec<u8>> {
+    if s.len() % 2 != 0 || !s.is_ascii() {
+        bail!("bad key id `{}`", s);
+    }
+
+    let mut out = Vec::with_capacity(s.len() / 2);
+
+    for i in (0..s.len()).step_by(2) {
+        match u8::from_str_radix(&s[i..i + 2], 16) {
+            Ok(b) => out.push(b),
+            Err(..) => bail!("bad key id `{}`", s),
+        }
+    }
+
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time;
+
+    use anyhow::anyhow;
+    use common::Channel;
+    use diesel::prelude::*;
+    use diesel::sql_types::{Binary, Text};
+    use diesel_migrations::MigrationHarness;
+    use parking_lot::Mutex;
+
+    use super::{from_hex, to_hex, ScriptStorage};
+
+    #[test]
+    fn test_hex() {
+        assert_eq!("00ff10", to_hex(&[0x00, 0xff, 0x10]));
+        assert_eq!(vec![0x00, 0xff, 0x10], from_hex("00FF10").unwrap());
+        assert!(from_hex("abc").is_err());
+        assert!(from_hex("zz").is_err());
+    }
+
+    #[test]
+    fn test_namespaces() -> anyhow::Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+
+        runtime.block_on(async {
+            let db = crate::Database::memory()?;
+            let storage = ScriptStorage::new(Channel::new("#test"), db);
+            let a = storage.namespaced("a");
+            let b = storage.namespaced("b");
+
+            a.set("key", 1i64).await?;
+            assert_eq!(Some(1i64), a.get::<_, i64>("key").await?);
+            assert_eq!(None, b.get::<_, i64>("key").await?);
+
+            assert_eq!(3, a.increment("key", 2).await?);
+            assert_eq!(-1, b.increment("key", -1).await?);
+            assert_eq!(vec![String::from("key")], a.keys::<String>().await?);
+
+            assert!(a.delete("key").await?);
+            assert!(!a.delete("key").await?);
+            assert_eq!(Some(-1i64), b.get::<_, i64>("key").await?);
+
+            b.set_expiring("key", 1i64, time::Duration::ZERO).await?;
+            assert_eq!(None, b.get::<_, i64>("key").await?);
+            assert!(b.keys::<String>().await?.is_empty());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn test_keys_from_before_namespaces() -> anyhow::Result<()> {
+        let mut c = SqliteConnection::establish(":memory:")?;
+
+        // Migrate up to, but not including, the migration which introduced
+        // namespaces.
+        loop {
+            let pending = c
+                .pending_migrations(crate::MIGRATIONS)
+                .map_err(|e| anyhow!("{}", e))?;
+
+            let Some(next) = pending.first() else {
+                break;
+            };
+
+            if next.name().to_string().ends_with("_script_key_namespaces") {
+                break;
+            }
+
+            c.run_migration(&**next).map_err(|e| anyhow!("{}", e))?;
+        }
+
+        diesel::sql_query("INSERT INTO script_keys (channel, key, value) VALUES (?, ?, ?)")
+            .bind::<Text, _>("#test")
+            .bind::<Binary, _>(serde_cbor::to_vec(&"count")?)
+            .bind::<Binary, _>(serde_cbor::to_vec(&41i64)?)
+            .execute(&mut c)?;
+
+        c.run_pending_migrations(crate::MIGRATIONS)
+            .map_err(|e| anyhow!("{}", e))?;
+
+        let db = crate::Database {
+            pool: Arc::new(Mutex::new(c)),
+        };
+
+        let runtime = tokio::runtime::Builder::new_current_thread().build()?;
+
+        runtime.block_on(async {
+            let storage = ScriptStorage::new(Channel::new("#test"), db);
+            assert_eq!(Some(41i64), storage.get::<_, i64>("count").await?);
+            assert_eq!(42, storage.increment("count", 1).await?);
+            assert_eq!(
+                None,
+                storage.namespaced("other").get::<_, i64>("count").await?
+            );
+            Ok(())
+        })
+    }
 }
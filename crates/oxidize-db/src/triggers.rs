@@ -0,0 +1,524 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Instant;
+
+use anyhow::{anyhow, bail, Context, Result};
+use common::{Channel, Duration};
+use diesel::prelude::*;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize, Serializer};
+use tokio::sync::RwLock;
+
+/// Local database wrapper.
+#[derive(Clone)]
+struct Database(crate::Database);
+
+impl Database {
+    private_database_group_fns!(triggers, Trigger, crate::Key);
+
+    /// Insert a trigger or edit the pattern and text of an existing one,
+    /// returning the stored trigger.
+    async fn edit(
+        &self,
+        key: &crate::Key,
+        pattern: &Pattern,
+        text: &str,
+    ) -> Result<crate::models::Trigger> {
+        use crate::schema::triggers::dsl;
+
+        let key = key.clone();
+        let kind = pattern.kind.as_str();
+        let pattern = pattern.source.clone();
+        let text = text.to_string();
+
+        self.0
+            .asyncify(move |c| {
+                let filter = dsl::triggers
+                    .filter(dsl::channel.eq(&key.channel).and(dsl::name.eq(&key.name)));
+
+                match filter.first::<crate::models::Trigger>(c).optional()? {
+                    None => {
+                        let trigger = crate::models::Trigger {
+                            channel: key.channel.clone(),
+                            name: key.name.clone(),
+                            kind: kind.to_owned(),
+                            pattern,
+                            text,
+                            priority: 0,
+                            cooldown: 0,
+                            exempt_moderators: true,
+                            exempt_bots: true,
+                            only_live: false,
+                            group: None,
+                            disabled: false,
+                        };
+
+                        diesel::insert_into(dsl::triggers)
+                            .values(&trigger)
+                            .execute(c)?;
+                        Ok(trigger)
+                    }
+                    Some(mut trigger) => {
+                        let mut set = crate::models::UpdateTrigger::default();
+                        set.kind = Some(kind);
+                        set.pattern = Some(&pattern);
+                        set.text = Some(&text);
+                        diesel::update(filter).set(&set).execute(c)?;
+
+                        trigger.kind = kind.to_owned();
+                        trigger.pattern = pattern;
+                        trigger.text = text;
+                        Ok(trigger)
+                    }
+                }
+            })
+            .await
+    }
+
+    /// Update the options of a trigger, returning the stored trigger if it
+    /// exists.
+    async fn update(
+        &self,
+        key: &crate::Key,
+        update: &Update,
+    ) -> Result<Option<crate::models::Trigger>> {
+        use crate::schema::triggers::dsl;
+
+        let key = key.clone();
+        let update = update.clone();
+
+        self.0
+            .asyncify(move |c| {
+                let filter = dsl::triggers
+                    .filter(dsl::channel.eq(&key.channel).and(dsl::name.eq(&key.name)));
+
+                let set = crate::models::UpdateTrigger {
+                    priority: update.priority,
+                    cooldown: update
+                        .cooldown
+                        .map(|d| i32::try_from(d.num_seconds()).unwrap_or(i32::MAX)),
+                    exempt_moderators: update.exempt_moderators,
+                    exempt_bots: update.exempt_bots,
+                    only_live: update.only_live,
+                    ..Default::default()
+                };
+
+                if diesel::update(filter).set(&set).execute(c)? == 0 {
+                    return Ok(None);
+                }
+
+                Ok(filter.first::<crate::models::Trigger>(c).optional()?)
+            })
+            .await
+    }
+}
+
+#[derive(Clone)]
+pub struct Triggers {
+    inner: Arc<RwLock<HashMap<crate::Key, Arc<Trigger>>>>,
+    db: Database,
+}
+
+impl Triggers {
+    database_group_fns!(Trigger, crate::Key);
+
+    /// Construct a new triggers store with a db.
+    pub async fn load(db: crate::Database) -> Result<Triggers> {
+        let db = Database(db);
+
+        let mut inner = HashMap::new();
+
+        for trigger in db.list().await? {
+            let trigger = Trigger::from_db(&trigger)?;
+            inner.insert(trigger.key.clone(), Arc::new(trigger));
+        }
+
+        Ok(Triggers {
+            inner: Arc::new(RwLock::new(inner)),
+            db,
+        })
+    }
+
+    /// Insert a trigger, or edit the pattern and response of an existing one.
+    pub async fn edit(
+        &self,
+        channel: &Channel,
+        name: &str,
+        pattern: Pattern,
+        template: template::Template,
+    ) -> Result<()> {
+        let key = crate::Key::new(channel, name);
+
+        let mut inner = self.inner.write().await;
+        let trigger = self.db.edit(&key, &pattern, template.source()).await?;
+
+        if trigger.disabled {
+            inner.remove(&key);
+        } else {
+            inner.insert(key, Arc::new(Trigger::from_db(&trigger)?));
+        }
+
+        Ok(())
+    }
+
+    /// Update the options of the given trigger.
+    ///
+    /// Returns `false` if there is no such trigger.
+    pub async fn update(&self, channel: &Channel, name: &str, update: Update) -> Result<bool> {
+        let key = crate::Key::new(channel, name);
+
+        let mut inner = self.inner.write().await;
+
+        let Some(trigger) = self.db.update(&key, &update).await? else {
+            return Ok(false);
+        };
+
+        if !trigger.disabled {
+            inner.insert(key, Arc::new(Trigger::from_db(&trigger)?));
+        }
+
+        Ok(true)
+    }
+
+    /// Find the trigger to fire in response to the given message, starting
+    /// its cooldown.
+    ///
+    /// Matching triggers are tested in order of priority, skipping the ones
+    /// which are cooling down.
+    pub async fn fire(&self, channel: &Channel, message: Message<'_>) -> Option<Arc<Trigger>> {
+        let words = normalize(message.text);
+
+        let inner = self.inner.read().await;
+
+        let mut matches = inner
+            .values()
+            .filter(|t| t.key.channel == *channel)
+            .filter(|t| t.applies(&message) && t.pattern.is_match(&words, message.text))
+            .collect::<Vec<_>>();
+
+        matches.sort_by(|a, b| {
+            b.priority
+                .cmp(&a.priority)
+                .then_with(|| a.key.name.cmp(&b.key.name))
+        });
+
+        for trigger in matches {
+            if trigger.try_start_cooldown() {
+                return Some(Arc::clone(trigger));
+            }
+        }
+
+        None
+    }
+}
+
+/// A message to test against triggers.
+#[derive(Debug, Clone, Copy)]
+pub struct Message<'a> {
+    /// The text of the message.
+    pub text: &'a str,
+    /// If the stream is live.
+    pub live: bool,
+    /// If the message was sent by a moderator.
+    pub moderator: bool,
+    /// If the message was sent by a known bot.
+    pub bot: bool,
+}
+
+/// Options to update on a trigger. Fields which are not set are left as is.
+#[derive(Debug, Default, Clone, Deserialize)]
+pub struct Update {
+    #[serde(default)]
+    pub priority: Option<i32>,
+    #[serde(default)]
+    pub cooldown: Option<Duration>,
+    #[serde(default)]
+    pub exempt_moderators: Option<bool>,
+    #[serde(default)]
+    pub exempt_bots: Option<bool>,
+    #[serde(default)]
+    pub only_live: Option<bool>,
+}
+
+/// How the pattern of a trigger is matched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Kind {
+    /// Matches if all words in the pattern appear in sequence.
+    Phrase,
+    /// Matches if any word in the pattern appears.
+    Keyword,
+    /// Matches a case-insensitive regular expression.
+    Regex,
+}
+
+impl Kind {
+    /// Get the kind as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Kind::Phrase => "phrase",
+            Kind::Keyword => "keyword",
+            Kind::Regex => "regex",
+        }
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl FromStr for Kind {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "phrase" => Kind::Phrase,
+            "keyword" => Kind::Keyword,
+            "regex" => Kind::Regex,
+            other => bail!(
+                "bad kind `{}`, expected one of: phrase, keyword, or regex",
+                other
+            ),
+        })
+    }
+}
+
+/// The pattern of a trigger.
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    kind: Kind,
+    source: String,
+    matcher: Matcher,
+}
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Words(Vec<String>),
+    Regex(regex::Regex),
+}
+
+impl Pattern {
+    /// Construct a new pattern of the given kind.
+    pub fn new(kind: Kind, source: &str) -> Result<Self> {
+        let matcher = match kind {
+            Kind::Phrase | Kind::Keyword => {
+                let words = normalize(source);
+
+                if words.is_empty() {
+                    bail!("pattern must contain at least one word");
+                }
+
+                Matcher::Words(words)
+            }
+            Kind::Regex => Matcher::Regex(
+                regex::RegexBuilder::new(source)
+                    .case_insensitive(true)
+                    .build()?,
+            ),
+        };
+
+        Ok(Self {
+            kind,
+            source: source.to_owned(),
+            matcher,
+        })
+    }
+
+    /// Get how the pattern is matched.
+    pub fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// Get the pattern as it was written.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Test if the pattern matches the given message, with `words` being the
+    /// normalized words of the message.
+    fn is_match(&self, words: &[String], message: &str) -> bool {
+        match &self.matcher {
+            Matcher::Words(pattern) => match self.kind {
+                Kind::Keyword => pattern.iter().any(|p| words.contains(p)),
+                _ => words
+                    .windows(pattern.len())
+                    .any(|w| w == pattern.as_slice()),
+            },
+            Matcher::Regex(regex) => regex.is_match(message),
+        }
+    }
+}
+
+impl Serialize for Pattern {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        use serde::ser::SerializeStruct as _;
+
+        let mut s = serializer.serialize_struct("Pattern", 2)?;
+        s.serialize_field("kind", &self.kind)?;
+        s.serialize_field("pattern", &self.source)?;
+        s.end()
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(fmt, "{} `{}`", self.kind, self.source)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Trigger {
+    pub key: crate::Key,
+    pub pattern: Pattern,
+    pub template: template::Template,
+    pub priority: i32,
+    pub cooldown: Duration,
+    pub exempt_moderators: bool,
+    pub exempt_bots: bool,
+    pub only_live: bool,
+    pub group: Option<String>,
+    pub disabled: bool,
+    /// When the trigger last fired.
+    #[serde(skip)]
+    fired_at: Arc<Mutex<Option<Instant>>>,
+}
+
+impl Trigger {
+    pub(crate) const NAME: &'static str = "trigger";
+
+    /// Load a trigger from the database.
+    pub(crate) fn from_db(trigger: &crate::models::Trigger) -> Result<Trigger> {
+        let template = template::Template::compile(&trigger.text)
+            .with_context(|| anyhow!("failed to compile trigger `{:?}` from db", trigger))?;
+
+        let kind = str::parse::<Kind>(&trigger.kind)?;
+        let pattern = Pattern::new(kind, &trigger.pattern)
+            .with_context(|| anyhow!("bad pattern for trigger `{:?}` in db", trigger))?;
+
+        Ok(Trigger {
+            key: crate::Key::new(&trigger.channel, &trigger.name),
+            pattern,
+            template,
+            priority: trigger.priority,
+            cooldown: Duration::seconds(u64::try_from(trigger.cooldown).unwrap_or_default()),
+            exempt_moderators: trigger.exempt_moderators,
+            exempt_bots: trigger.exempt_bots,
+            only_live: trigger.only_live,
+            group: trigger.group.clone(),
+            disabled: trigger.disabled,
+            fired_at: Default::default(),
+        })
+    }
+
+    /// Render the response of the trigger.
+    pub fn render<T>(&self, data: &T) -> Result<String>
+    where
+        T: Serialize,
+    {
+        self.template.render_to_string(data)
+    }
+
+    /// Test if the trigger applies to the given message, regardless of its
+    /// text.
+    fn applies(&self, message: &Message<'_>) -> bool {
+        if self.only_live && !message.live {
+            return false;
+        }
+
+        if self.exempt_moderators && message.moderator {
+            return false;
+        }
+
+        !(self.exempt_bots && message.bot)
+    }
+
+    /// Start the cooldown of the trigger, unless it's already cooling down.
+    fn try_start_cooldown(&self) -> bool {
+        let mut fired_at = self.fired_at.lock();
+        let now = Instant::now();
+
+        if let Some(fired_at) = *fired_at {
+            if now.duration_since(fired_at) < self.cooldown.as_std() {
+                return false;
+            }
+        }
+
+        *fired_at = Some(now);
+        true
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            fmt,
+            "pattern = {pattern}, template = \"{template}\", priority = {priority}, cooldown = {cooldown}, exempt moderators = {exempt_moderators}, exempt bots = {exempt_bots}, only live = {only_live}, group = {group}, disabled = {disabled}",
+            pattern = self.pattern,
+            template = self.template,
+            priority = self.priority,
+            cooldown = self.cooldown,
+            exempt_moderators = self.exempt_moderators,
+            exempt_bots = self.exempt_bots,
+            only_live = self.only_live,
+            group = self.group.as_deref().unwrap_or("*none*"),
+            disabled = self.disabled,
+        )
+    }
+}
+
+/// Split text into lowercase words, ignoring any punctuation.
+fn normalize(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .map(|word| {
+            word.chars()
+                .filter(|c| c.is_alphanumeric())
+                .flat_map(char::to_lowercase)
+                .collect::<String>()
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{normalize, Kind, Pattern};
+
+    fn is_match(kind: Kind, pattern: &str, message: &str) -> bool {
+        let pattern = Pattern::new(kind, pattern).unwrap();
+        pattern.is_match(&normalize(message), message)
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(vec!["hello", "world"], normalize("Hello, World!"));
+        assert_eq!(vec!["dont", "panic"], normalize("  don't  ... PANIC "));
+    }
+
+    #[test]
+    fn test_patterns() {
+        assert!(is_match(
+            Kind::Phrase,
+            "what game",
+            "So, WHAT game is this?"
+        ));
+        assert!(!is_match(Kind::Phrase, "what game", "game what"));
+        assert!(!is_match(Kind::Phrase, "what game", "whatgame"));
+
+        assert!(is_match(
+            Kind::Keyword,
+            "discord server",
+            "Is there a Discord?"
+        ));
+        assert!(!is_match(Kind::Keyword, "discord", "discordant"));
+
+        assert!(is_match(Kind::Regex, r"^!?lurk", "LURKING"));
+        assert!(Pattern::new(Kind::Keyword, "...").is_err());
+    }
+}
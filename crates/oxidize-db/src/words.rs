@@ -1,44 +1,431 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::str::FromStr;
 use std::sync::Arc;
 
-use anyhow::Result;
+use anyhow::{bail, Result};
 use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
 use tokio::sync::{RwLock, RwLockReadGuard};
 
+use crate::models::BadWord;
+
+/// The action to take when a bad word is found.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Action {
+    /// Delete the message.
+    Delete,
+    /// Time out the user.
+    Timeout,
+    /// Ban the user.
+    Ban,
+    /// Only record the message in the moderation log.
+    Log,
+}
+
+impl Action {
+    /// Get the action as a string.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Delete => "delete",
+            Action::Timeout => "timeout",
+            Action::Ban => "ban",
+            Action::Log => "log",
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "delete" => Action::Delete,
+            "timeout" => Action::Timeout,
+            "ban" => Action::Ban,
+            "log" => Action::Log,
+            other => bail!(
+                "bad action `{}`, expected one of: delete, timeout, ban, or log",
+                other
+            ),
+        })
+    }
+}
+
+/// A bad word along with its exceptions.
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    #[serde(flatten)]
+    pub word: BadWord,
+    pub exceptions: Vec<String>,
+}
+
+/// Construct a bad word with the default action.
+fn new_bad_word(word: String, why: Option<String>) -> BadWord {
+    BadWord {
+        word,
+        why,
+        severity: 0,
+        action: Action::Delete.as_str().to_owned(),
+        duration: None,
+        only_live: false,
+    }
+}
+
 /// Tokenize the given word.
 pub(crate) fn tokenize(word: &str) -> String {
-    let word = word.to_lowercase();
+    let word = normalize(word);
     inflector::string::singularize::to_singular(&word)
 }
 
+/// Normalize the given word, undoing common ways of obfuscating it.
+///
+/// This lowercases the word, maps Unicode confusables and leetspeak to the
+/// letters they resemble, and strips any punctuation inserted into it.
+fn normalize(word: &str) -> String {
+    let mut out = String::with_capacity(word.len());
+
+    for c in word.chars().flat_map(char::to_lowercase) {
+        if is_combining_mark(c) {
+            continue;
+        }
+
+        out.push(confusable(c).unwrap_or(c));
+    }
+
+    // Only treat digits and symbols as letters if the word has letters in
+    // it, so that numbers don't turn into words.
+    let leet = out.chars().any(char::is_alphabetic);
+
+    out.chars()
+        .map(|c| match leetspeak(c) {
+            Some(l) if leet => l,
+            _ => c,
+        })
+        .filter(|c| c.is_alphanumeric())
+        .collect()
+}
+
+/// Collapse repeated characters in a tokenized word, returning the collapsed
+/// word and the length of each run of characters in it.
+fn collapse(word: &str) -> (String, Vec<usize>) {
+    let mut out = String::with_capacity(word.len());
+    let mut runs = Vec::new();
+    let mut last = None;
+
+    for c in word.chars() {
+        if last == Some(c) {
+            if let Some(run) = runs.last_mut() {
+                *run += 1;
+            }
+
+            continue;
+        }
+
+        out.push(c);
+        runs.push(1);
+        last = Some(c);
+    }
+
+    (out, runs)
+}
+
+/// Map a Unicode confusable to the ASCII letter it resembles.
+fn confusable(c: char) -> Option<char> {
+    let c = match c {
+        // Fullwidth forms.
+        '\u{FF01}'..='\u{FF5E}' => return char::from_u32(c as u32 - 0xFEE0),
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'а' | 'α' => 'a',
+        'в' | 'β' => 'b',
+        'ç' | 'с' => 'c',
+        'ԁ' => 'd',
+        'è' | 'é' | 'ê' | 'ë' | 'е' | 'ё' | 'ε' => 'e',
+        'һ' | 'н' => 'h',
+        'ì' | 'í' | 'î' | 'ï' | 'і' | 'ι' => 'i',
+        'ј' => 'j',
+        'к' | 'κ' => 'k',
+        'м' | 'μ' => 'm',
+        'ñ' => 'n',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'о' | 'ο' => 'o',
+        'р' | 'ρ' => 'p',
+        'ԛ' => 'q',
+        'ѕ' => 's',
+        'т' | 'τ' => 't',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ԝ' => 'w',
+        'х' | 'χ' => 'x',
+        'ý' | 'ÿ' | 'у' | 'υ' => 'y',
+        'ζ' => 'z',
+        _ => return None,
+    };
+
+    Some(c)
+}
+
+/// Map a leetspeak character to the letter it stands in for.
+fn leetspeak(c: char) -> Option<char> {
+    let c = match c {
+        '4' | '@' => 'a',
+        '8' => 'b',
+        '3' | '€' => 'e',
+        '9' => 'g',
+        '1' | '!' => 'i',
+        '|' => 'l',
+        '0' => 'o',
+        '5' | '$' => 's',
+        '7' | '+' => 't',
+        _ => return None,
+    };
+
+    Some(c)
+}
+
+/// Test if the given character is a combining mark.
+fn is_combining_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{0483}'..='\u{0489}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
 #[derive(Debug, Default)]
 struct Inner {
-    hashed: HashMap<eudex::Hash, Arc<Word>>,
-    exact: HashMap<String, Arc<Word>>,
+    /// Words by the phonetic hash of their collapsed form. Different words
+    /// might share a hash, so each bucket can hold several.
+    hashed: HashMap<eudex::Hash, Vec<Arc<Word>>>,
+    /// Words by their collapsed form.
+    exact: HashMap<String, Vec<Arc<Word>>>,
+    /// Tokenized words which should never match a given word.
+    exceptions: HashMap<String, HashSet<String>>,
 }
 
 impl Inner {
     /// Insert a bad word.
-    fn insert(&mut self, word: &str, why: Option<&str>) -> Result<()> {
-        let word = Word {
-            word: tokenize(word),
-            why: why.map(template::Template::compile).transpose()?,
-        };
+    fn insert(&mut self, bad_word: &BadWord) -> Result<()> {
+        let why = bad_word
+            .why
+            .as_deref()
+            .map(template::Template::compile)
+            .transpose()?;
+        let action = str::parse::<Action>(&bad_word.action)?;
+        let word = tokenize(&bad_word.word);
+
+        if word.is_empty() {
+            return Ok(());
+        }
+
+        self.remove_tokenized(&word);
+
+        let (collapsed, runs) = collapse(&word);
+
+        let word = Arc::new(Word {
+            word,
+            runs,
+            why,
+            severity: bad_word.severity,
+            action,
+            duration: bad_word.duration.and_then(|d| u64::try_from(d).ok()),
+            only_live: bad_word.only_live,
+        });
 
-        let word = Arc::new(word);
         self.hashed
-            .insert(eudex::Hash::new(&word.word), Arc::clone(&word));
-        self.exact.insert(word.word.to_string(), Arc::clone(&word));
+            .entry(eudex::Hash::new(&collapsed))
+            .or_default()
+            .push(Arc::clone(&word));
+        self.exact.entry(collapsed).or_default().push(word);
         Ok(())
     }
 
-    /// Insert a bad word.
+    /// Remove a bad word.
     fn remove(&mut self, word: &str) {
         let word = tokenize(word);
+        self.remove_tokenized(&word);
+        self.exceptions.remove(&word);
+    }
+
+    /// Remove an already tokenized word, leaving any words it collides with
+    /// in place.
+    fn remove_tokenized(&mut self, word: &str) {
+        let (collapsed, _) = collapse(word);
+
+        let hash = eudex::Hash::new(&collapsed);
+
+        if let Some(words) = self.hashed.get_mut(&hash) {
+            words.retain(|w| w.word != word);
+
+            if words.is_empty() {
+                self.hashed.remove(&hash);
+            }
+        }
+
+        if let Some(words) = self.exact.get_mut(&collapsed) {
+            words.retain(|w| w.word != word);
+
+            if words.is_empty() {
+                self.exact.remove(&collapsed);
+            }
+        }
+    }
+
+    /// Insert an exception for a bad word.
+    fn insert_exception(&mut self, word: &str, exception: &str) {
+        self.exceptions
+            .entry(tokenize(word))
+            .or_default()
+            .insert(tokenize(exception));
+    }
+
+    /// Remove an exception for a bad word.
+    fn remove_exception(&mut self, word: &str, exception: &str) {
+        let word = tokenize(word);
+
+        if let Some(exceptions) = self.exceptions.get_mut(&word) {
+            exceptions.remove(&tokenize(exception));
+
+            if exceptions.is_empty() {
+                self.exceptions.remove(&word);
+            }
+        }
+    }
+
+    /// Test if the given word is exempt from matching the given bad word.
+    fn is_exception(&self, word: &Word, token: &str) -> bool {
+        match self.exceptions.get(&word.word) {
+            Some(exceptions) => exceptions.contains(token),
+            None => false,
+        }
+    }
+
+    /// Test the given word, ignoring words which only apply while the stream
+    /// is live unless it is.
+    fn test(&self, word: &str, live: bool) -> Option<&Arc<Word>> {
+        let token = tokenize(word);
+
+        if token.is_empty() {
+            return None;
+        }
+
+        let (collapsed, runs) = collapse(&token);
+
+        let exact = self
+            .exact
+            .get(&collapsed)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        for w in exact {
+            if w.applies(live) && w.is_covered_by(&runs) && !self.is_exception(w, &token) {
+                return Some(w);
+            }
+        }
+
+        if let Some(words) = self.hashed.get(&eudex::Hash::new(&collapsed)) {
+            for w in words {
+                // Words with the same letters have already been tested above.
+                if exact.iter().any(|e| Arc::ptr_eq(e, w)) {
+                    continue;
+                }
+
+                if w.applies(live) && !self.is_exception(w, &token) {
+                    return Some(w);
+                }
+            }
+        }
+
+        self.test_inside(&token, &collapsed, &runs, live)
+    }
+
+    /// Test for bad words hidden inside of a longer token, like "cunt" in
+    /// "scunthorpe".
+    ///
+    /// A match is ignored if an exception for the word covers it.
+    fn test_inside(
+        &self,
+        token: &str,
+        collapsed: &str,
+        runs: &[usize],
+        live: bool,
+    ) -> Option<&Arc<Word>> {
+        let chars = collapsed.chars().collect::<Vec<_>>();
+
+        // Offset into the token of where each run of characters starts.
+        let mut starts = Vec::with_capacity(runs.len() + 1);
+        let mut offset = 0;
+
+        for run in runs {
+            starts.push(offset);
+            offset += run;
+        }
 
-        // TODO: there might be hash conflicts. Deal with them.
-        self.hashed.remove(&eudex::Hash::new(&word));
-        self.exact.remove(&word);
+        starts.push(offset);
+
+        for (key, words) in &self.exact {
+            let key = key.chars().collect::<Vec<_>>();
+
+            if key.is_empty() || key.len() > chars.len() {
+                continue;
+            }
+
+            for p in 0..=chars.len() - key.len() {
+                if chars[p..p + key.len()] != key[..] {
+                    continue;
+                }
+
+                let found = &runs[p..p + key.len()];
+
+                for w in words {
+                    if !w.applies(live) || w.runs.iter().zip(found).any(|(a, b)| a > b) {
+                        continue;
+                    }
+
+                    // The characters in the token covered by the word.
+                    let start = starts[p + 1] - w.runs[0];
+                    let end = starts[p + key.len() - 1] + w.runs[key.len() - 1];
+
+                    if !self.is_covered_by_exception(w, token, start, end) {
+                        return Some(w);
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Test if the characters `start..end` of the token are covered by an
+    /// exception for the given word.
+    fn is_covered_by_exception(&self, word: &Word, token: &str, start: usize, end: usize) -> bool {
+        let Some(exceptions) = self.exceptions.get(&word.word) else {
+            return false;
+        };
+
+        let token = token.chars().collect::<Vec<_>>();
+
+        exceptions.iter().any(|exception| {
+            let exception = exception.chars().collect::<Vec<_>>();
+
+            if exception.is_empty() || exception.len() > token.len() {
+                return false;
+            }
+
+            (0..=token.len() - exception.len()).any(|s| {
+                s <= start
+                    && end <= s + exception.len()
+                    && token[s..s + exception.len()] == exception[..]
+            })
+        })
     }
 }
 
@@ -55,6 +442,33 @@ impl Database {
             .await
     }
 
+    /// Get a single word from the backend.
+    async fn get(&self, word: &str) -> Result<Option<BadWord>> {
+        use crate::schema::bad_words::dsl;
+
+        let word = word.to_string();
+
+        self.0
+            .asyncify(move |c| {
+                Ok(dsl::bad_words
+                    .filter(dsl::word.eq(&word))
+                    .first::<BadWord>(c)
+                    .optional()?)
+            })
+            .await
+    }
+
+    /// List all exceptions in backend.
+    async fn list_exceptions(&self) -> Result<Vec<crate::models::BadWordException>> {
+        use crate::schema::bad_word_exceptions::dsl;
+
+        self.0
+            .asyncify(move |c| {
+                Ok(dsl::bad_word_exceptions.load::<crate::models::BadWordException>(c)?)
+            })
+            .await
+    }
+
     /// Insert or update an existing word.
     async fn edit(&self, word: &str, why: Option<&str>) -> Result<()> {
         use crate::schema::bad_words::dsl;
@@ -69,7 +483,7 @@ impl Database {
 
                 match b {
                     None => {
-                        let bad_word = crate::models::BadWord { word, why };
+                        let bad_word = new_bad_word(word, why);
 
                         diesel::insert_into(dsl::bad_words)
                             .values(&bad_word)
@@ -87,8 +501,24 @@ impl Database {
             .await
     }
 
-    /// Delete the given word from the backend.
+    /// Insert or replace a word with all of its fields.
+    async fn update(&self, bad_word: BadWord) -> Result<()> {
+        use crate::schema::bad_words::dsl;
+
+        self.0
+            .asyncify(move |c| {
+                diesel::replace_into(dsl::bad_words)
+                    .values(&bad_word)
+                    .execute(c)?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// Delete the given word and its exceptions from the backend.
     async fn delete(&self, word: &str) -> Result<bool> {
+        use crate::schema::bad_word_exceptions::dsl as exceptions;
         use crate::schema::bad_words::dsl;
 
         let word = word.to_string();
@@ -97,6 +527,49 @@ impl Database {
             .asyncify(move |c| {
                 let count =
                     diesel::delete(dsl::bad_words.filter(dsl::word.eq(&word))).execute(c)?;
+
+                diesel::delete(exceptions::bad_word_exceptions.filter(exceptions::word.eq(&word)))
+                    .execute(c)?;
+
+                Ok(count == 1)
+            })
+            .await
+    }
+
+    /// Insert an exception for the given word.
+    async fn insert_exception(&self, word: &str, exception: &str) -> Result<()> {
+        use crate::schema::bad_word_exceptions::dsl;
+
+        let exception = crate::models::BadWordException {
+            word: word.to_string(),
+            exception: exception.to_string(),
+        };
+
+        self.0
+            .asyncify(move |c| {
+                diesel::insert_or_ignore_into(dsl::bad_word_exceptions)
+                    .values(&exception)
+                    .execute(c)?;
+
+                Ok(())
+            })
+            .await
+    }
+
+    /// Delete an exception for the given word.
+    async fn delete_exception(&self, word: &str, exception: &str) -> Result<bool> {
+        use crate::schema::bad_word_exceptions::dsl;
+
+        let word = word.to_string();
+        let exception = exception.to_string();
+
+        self.0
+            .asyncify(move |c| {
+                let filter = dsl::bad_word_exceptions
+                    .filter(dsl::word.eq(&word))
+                    .filter(dsl::exception.eq(&exception));
+
+                let count = diesel::delete(filter).execute(c)?;
                 Ok(count == 1)
             })
             .await
@@ -116,7 +589,11 @@ impl Words {
         let mut inner = Inner::default();
 
         for word in db.list().await? {
-            inner.insert(&word.word, word.why.as_deref())?;
+            inner.insert(&word)?;
+        }
+
+        for exception in db.list_exceptions().await? {
+            inner.insert_exception(&exception.word, &exception.exception);
         }
 
         Ok(Words {
@@ -125,18 +602,62 @@ impl Words {
         })
     }
 
-    /// Insert a word into the bad words list.
-    #[allow(unused)]
-    pub(crate) async fn edit(&self, word: &str, why: Option<&str>) -> Result<()> {
+    /// List all words along with their exceptions.
+    pub async fn list(&self) -> Result<Vec<Entry>> {
+        let mut exceptions = HashMap::<_, Vec<_>>::new();
+
+        for exception in self.db.list_exceptions().await? {
+            exceptions
+                .entry(exception.word)
+                .or_default()
+                .push(exception.exception);
+        }
+
+        let mut entries = Vec::new();
+
+        for word in self.db.list().await? {
+            let exceptions = exceptions.remove(&word.word).unwrap_or_default();
+            entries.push(Entry { word, exceptions });
+        }
+
+        entries.sort_by(|a, b| a.word.word.cmp(&b.word.word));
+        Ok(entries)
+    }
+
+    /// Get a single word.
+    pub async fn get(&self, word: &str) -> Result<Option<BadWord>> {
+        self.db.get(word).await
+    }
+
+    /// Insert a word into the bad words list, or update the reason given for
+    /// an existing one.
+    pub async fn edit(&self, word: &str, why: Option<&str>) -> Result<()> {
         self.db.edit(word, why).await?;
+
+        if let Some(bad_word) = self.db.get(word).await? {
+            let mut inner = self.inner.write().await;
+            inner.insert(&bad_word)?;
+        }
+
+        Ok(())
+    }
+
+    /// Insert or replace a word with all of its fields.
+    pub async fn update(&self, bad_word: BadWord) -> Result<()> {
+        str::parse::<Action>(&bad_word.action)?;
+
+        if let Some(why) = &bad_word.why {
+            template::Template::compile(why)?;
+        }
+
+        self.db.update(bad_word.clone()).await?;
         let mut inner = self.inner.write().await;
-        inner.insert(word, why)?;
+        inner.insert(&bad_word)?;
         Ok(())
     }
 
     /// Remove a word from the bad words list.
-    #[allow(unused)]
-    pub(crate) async fn delete(&self, word: &str) -> Result<bool> {
+    pub async fn delete(&self, word: &str) -> Result<bool> {
         if !self.db.delete(word).await? {
             return Ok(false);
         }
@@ -146,6 +667,25 @@ impl Words {
         Ok(true)
     }
 
+    /// Add a word which should never match the given bad word.
+    pub async fn insert_exception(&self, word: &str, exception: &str) -> Result<()> {
+        self.db.insert_exception(word, exception).await?;
+        let mut inner = self.inner.write().await;
+        inner.insert_exception(word, exception);
+        Ok(())
+    }
+
+    /// Remove an exception for the given bad word.
+    pub async fn delete_exception(&self, word: &str, exception: &str) -> Result<bool> {
+        if !self.db.delete_exception(word, exception).await? {
+            return Ok(false);
+        }
+
+        let mut inner = self.inner.write().await;
+        inner.remove_exception(word, exception);
+        Ok(true)
+    }
+
     /// Build a tester.
     pub async fn tester(&self) -> Tester<'_> {
         let inner = self.inner.read().await;
@@ -161,23 +701,149 @@ pub struct Tester<'a> {
 
 impl Tester<'_> {
     /// Test the given word.
-    pub fn test(&self, word: &str) -> Option<Arc<Word>> {
-        let word = tokenize(word);
-
-        if let Some(w) = self.inner.hashed.get(&eudex::Hash::new(&word)) {
-            return Some(Arc::clone(w));
-        }
+    ///
+    /// Words which only apply while the stream is live are ignored unless
+    /// `live` is set.
+    pub fn test(&self, word: &str, live: bool) -> Option<Arc<Word>> {
+        self.inner.test(word, live).cloned()
+    }
 
-        if let Some(w) = self.inner.exact.get(&word) {
-            return Some(Arc::clone(w));
+    /// Test every word in the given message, returning the most severe bad
+    /// word found.
+    pub fn test_message(&self, message: &str, live: bool) -> Option<Arc<Word>> {
+        let mut found = None::<&Arc<Word>>;
+
+        for token in message.split_whitespace() {
+            let word = match self.inner.test(token, live) {
+                Some(word) => Some(word),
+                // Punctuation might be separating words rather than
+                // obfuscating one, so also test each part on its own.
+                None if token.contains(|c: char| c.is_ascii_punctuation()) => {
+                    common::words::trimmed(token)
+                        .flat_map(|part| self.inner.test(part, live))
+                        .max_by_key(|w| w.severity)
+                }
+                None => None,
+            };
+
+            match (word, found) {
+                (Some(word), Some(f)) if word.severity <= f.severity => {}
+                (Some(word), _) => found = Some(word),
+                (None, _) => {}
+            }
         }
 
-        None
+        found.cloned()
     }
 }
 
 #[derive(Debug)]
 pub struct Word {
     pub(crate) word: String,
+    /// Length of each run of repeated characters in the word.
+    runs: Vec<usize>,
     pub why: Option<template::Template>,
+    /// The most severe word in a message decides the action taken.
+    pub severity: i32,
+    /// The action to take.
+    pub action: Action,
+    /// The duration of a timeout in seconds.
+    pub duration: Option<u64>,
+    /// Only act on the word while the stream is live.
+    pub only_live: bool,
+}
+
+impl Word {
+    /// Get the word being matched.
+    pub fn word(&self) -> &str {
+        &self.word
+    }
+
+    /// Test if the word applies given the state of the stream.
+    fn applies(&self, live: bool) -> bool {
+        live || !self.only_live
+    }
+
+    /// Test if a token with the given runs of characters covers this word,
+    /// so that "fuuuck" matches "fuck" while "as" doesn't match "ass".
+    fn is_covered_by(&self, runs: &[usize]) -> bool {
+        self.runs.len() == runs.len() && self.runs.iter().zip(runs).all(|(a, b)| a <= b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{collapse, new_bad_word, normalize, Inner};
+
+    fn insert(inner: &mut Inner, word: &str) {
+        inner.insert(&new_bad_word(word.to_owned(), None)).unwrap();
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!("hello", normalize("H3LL0"));
+        assert_eq!("shit", normalize("$h!t"));
+        assert_eq!("fuck", normalize("f.u.c.k"));
+        assert_eq!("cafe", normalize("cafe\u{0301}"));
+        assert_eq!("scam", normalize("ѕсаm"));
+        assert_eq!("noob", normalize("ｎｏｏｂ"));
+        assert_eq!("1337", normalize("1337"));
+    }
+
+    #[test]
+    fn test_collapse() {
+        assert_eq!((String::from("fuck"), vec![1, 3, 1, 1]), collapse("fuuuck"));
+        assert_eq!((String::new(), vec![]), collapse(""));
+    }
+
+    #[test]
+    fn test_matching() {
+        let mut inner = Inner::default();
+        insert(&mut inner, "fuck");
+        insert(&mut inner, "cunt");
+
+        assert!(inner.test("FUUUCK", true).is_some());
+        assert!(inner.test("c-u-n-t", true).is_some());
+        assert!(inner.test("сuuunt", true).is_some());
+
+        // Bad words are found inside of longer words, until an exception
+        // covers them.
+        assert!(inner.test("Scunthorpe", true).is_some());
+        inner.insert_exception("cunt", "Scunthorpe");
+        assert!(inner.test("Scunthorpe", true).is_none());
+        assert!(inner.test("cunt", true).is_some());
+        assert!(inner.test("cuntscunthorpe", true).is_some());
+
+        inner.remove_exception("cunt", "scunthorpe");
+        assert!(inner.exceptions.is_empty());
+        assert!(inner.test("scunthorpe", true).is_some());
+
+        // Runs of characters still have to cover the word.
+        insert(&mut inner, "ass");
+        assert!(inner.test("classy", true).is_some());
+        assert!(inner.test("basic", true).is_none());
+    }
+
+    #[test]
+    fn test_only_live() {
+        let mut bad_word = new_bad_word(String::from("spoiler"), None);
+        bad_word.only_live = true;
+
+        let mut inner = Inner::default();
+        inner.insert(&bad_word).unwrap();
+
+        assert!(inner.test("spoiler", true).is_some());
+        assert!(inner.test("spoiler", false).is_none());
+    }
+
+    #[test]
+    fn test_colliding_words() {
+        let mut inner = Inner::default();
+        insert(&mut inner, "kunt");
+        insert(&mut inner, "cunt");
+        inner.remove("cunt");
+
+        let word = inner.test("kunt", true).expect("word should still match");
+        assert_eq!("kunt", word.word());
+    }
 }
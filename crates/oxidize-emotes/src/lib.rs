@@ -348,57 +348,28 @@ impl Emotes {
 
     /// Get all user emotes.
     fn message_emotes_twitch(&self, tags: &irc::Tags, message: &str) -> Result<EmoteByCode> {
-        let emotes = match tags.emotes.as_ref() {
-            Some(emotes) => match emotes.as_str() {
-                "" => return Ok(Default::default()),
-                emotes => emotes,
-            },
-            None => return Ok(Default::default()),
-        };
-
         let mut out = EmoteByCode::default();
 
         // scratch buffer for all the characters in the message.
         // is only filled if needed lazily.
         let mut message_chars = None::<Vec<char>>;
 
-        // 300354391:8-16/28087:0-6
-        for emote in emotes.split('/') {
-            let mut p = emote.split(':');
-
-            let id = match p.next() {
-                Some(id) => id,
-                None => continue,
-            };
-
-            let span = match p.next() {
-                Some(rest) => first_span(rest),
-                None => continue,
-            };
-
+        for (id, s, e) in tags.emote_ranges() {
             let message = message_chars.get_or_insert_with(|| message.chars().collect());
 
-            let word: String = match span {
-                Some((s, e)) => message[s..=e].iter().collect(),
+            let word: String = match message.get(s..=e) {
+                Some(word) => word.iter().collect(),
                 None => continue,
             };
 
+            if out.contains_key(&word) {
+                continue;
+            }
+
             out.insert(word, Self::twitch_emote(id));
         }
 
-        return Ok(out);
-
-        fn first_span(rest: &str) -> Option<(usize, usize)> {
-            let mut it = rest.split(',').next()?.split('-');
-
-            let s = it.next()?;
-            let s = str::parse::<usize>(s).ok()?;
-
-            let e = it.next()?;
-            let e = str::parse::<usize>(e).ok()?;
-
-            Some((s, e))
-        }
+        Ok(out)
     }
 
     /// Get all user emotes.
@@ -1,5 +1,7 @@
 use chrono::{DateTime, Utc};
+use common::{Channel, OwnedChannel};
 use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
 use std::collections::VecDeque;
 use std::sync::Arc;
 use tokio::sync::{RwLock, RwLockReadGuard};
@@ -26,9 +28,9 @@ pub enum Event {
 
 impl bus::Message for Event {
     /// The ID of a bussed message.
-    fn id(&self) -> Option<&'static str> {
+    fn id(&self) -> Option<Cow<'static, str>> {
         match *self {
-            Event::Enabled { .. } => Some("enabled"),
+            Event::Enabled { .. } => Some(Cow::Borrowed("enabled")),
             _ => None,
         }
     }
@@ -151,6 +153,7 @@ impl MessageLog {
     /// Push a message to the back of the log.
     pub async fn push_back(
         &self,
+        channel: &Channel,
         tags: &common::irc::Tags,
         name: &str,
         text: &str,
@@ -188,10 +191,12 @@ impl MessageLog {
             name: name.to_string(),
             display_name: display_name.to_string(),
             color: tags.color.clone(),
+            moderator: tags.is_moderator(),
         };
 
         let m = Message {
             timestamp: Utc::now(),
+            channel: channel.to_owned(),
             id: id.to_string(),
             user,
             text: text.to_string(),
@@ -213,14 +218,69 @@ pub struct User {
     name: String,
     display_name: String,
     color: Option<String>,
+    /// If the user was a moderator or the broadcaster of the channel when the
+    /// message was sent.
+    #[serde(default)]
+    moderator: bool,
+}
+
+impl User {
+    /// The ID of the user.
+    pub fn user_id(&self) -> &str {
+        &self.user_id
+    }
+
+    /// The login of the user.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Test if the user was a moderator or the broadcaster of the channel
+    /// the message was sent in.
+    pub fn is_moderator(&self) -> bool {
+        self.moderator
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Message {
     timestamp: DateTime<Utc>,
+    channel: OwnedChannel,
     id: String,
     user: User,
     text: String,
     rendered: Option<emotes::Rendered>,
     deleted: bool,
 }
+
+impl Message {
+    /// When the message was received.
+    pub fn timestamp(&self) -> &DateTime<Utc> {
+        &self.timestamp
+    }
+
+    /// The channel the message was sent in.
+    pub fn channel(&self) -> &Channel {
+        &self.channel
+    }
+
+    /// The ID of the message.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The user who sent the message.
+    pub fn user(&self) -> &User {
+        &self.user
+    }
+
+    /// The text of the message.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Test if the message has been deleted.
+    pub fn is_deleted(&self) -> bool {
+        self.deleted
+    }
+}
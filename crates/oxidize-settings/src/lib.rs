@@ -92,6 +92,10 @@ pub enum Error {
 /// Separator in configuration hierarchy.
 const SEP: char = '/';
 
+/// Prefix under which values which only apply to a single channel are
+/// stored.
+const CHANNELS: &str = "channels";
+
 /// Indication that a value has been updated.
 type Update = Event<serde_json::Value>;
 
@@ -369,6 +373,9 @@ where
     db: db::Database,
     /// Maps setting prefixes to subscriptions.
     subscriptions: HashMap<Box<str>, broadcast::Sender<Update>>,
+    /// Subscriptions to values which only apply to a single channel, created
+    /// on demand.
+    channel_subscriptions: std::sync::Mutex<HashMap<Box<str>, broadcast::Sender<Update>>>,
     /// Schema for every corresponding type.
     schema: Schema<S>,
     /// Information about all prefixes.
@@ -386,9 +393,19 @@ where
     S: Scope,
 {
     scope: Box<str>,
+    /// The channel the settings are scoped to, if any.
+    channel: Option<Box<str>>,
     inner: Arc<Inner<S>>,
 }
 
+/// The state of a value which is scoped to a channel.
+struct ChannelState {
+    /// If the channel overrides the global value.
+    overridden: bool,
+    /// The global value.
+    global: Option<serde_json::Value>,
+}
+
 impl<S> Settings<S>
 where
     S: Scope,
@@ -400,9 +417,11 @@ where
 
         Self {
             scope: Default::default(),
+            channel: None,
             inner: Arc::new(Inner {
                 db,
                 subscriptions,
+                channel_subscriptions: Default::default(),
                 schema,
                 prefixes,
                 drivers,
@@ -514,6 +533,7 @@ where
         let prefix = self.key(prefix);
         let inner = self.inner.clone();
         let prefix = prefix.to_string();
+        let channel = self.channel.clone();
 
         self.inner
             .db
@@ -538,7 +558,7 @@ where
                         None => continue,
                     };
 
-                    let value = match values.get(key) {
+                    let value = match lookup_value(&values, channel.as_deref(), key) {
                         Some(value) => serde_json::from_str(value)?,
                         None if schema.ty.optional => serde_json::Value::Null,
                         None => continue,
@@ -573,7 +593,7 @@ where
             None => return Ok(None),
         };
 
-        let value = self.inner_get(&key).await?;
+        let (value, _) = self.lookup(&key).await?;
         Ok(Some(SettingRef { schema, key, value }))
     }
 
@@ -583,7 +603,8 @@ where
         T: Serialize + de::DeserializeOwned,
     {
         let key = self.key(key);
-        self.inner_get(&key).await
+        let (value, _) = self.lookup(&key).await?;
+        Ok(value)
     }
 
     /// Insert the given setting without sending an update notification to other components.
@@ -591,8 +612,8 @@ where
     where
         T: Serialize,
     {
-        let key = self.key(key);
-        self.inner_set(key.as_ref(), value, false).await
+        let key = self.storage_key(key);
+        self.inner_set(&key, value, false).await
     }
 
     /// Insert the given setting.
@@ -600,14 +621,14 @@ where
     where
         T: Serialize,
     {
-        let key = self.key(key);
-        self.inner_set(key.as_ref(), value, true).await
+        let key = self.storage_key(key);
+        self.inner_set(&key, value, true).await
     }
 
     /// Insert the given setting as raw JSON.
     pub async fn set_json(&self, key: &str, value: serde_json::Value) -> Result<(), Error> {
-        let key = self.key(key);
-        self.inner_set_json(key.as_ref(), value, true).await
+        let key = self.storage_key(key);
+        self.inner_set_json(&key, value, true).await
     }
 
     /// Inner implementation of set_json which doesn't do key translation.
@@ -667,6 +688,7 @@ where
         use db::schema::settings::dsl;
 
         let inner = self.inner.clone();
+        let channel = self.channel.clone();
 
         self.inner
             .db
@@ -681,7 +703,7 @@ where
                     .collect::<HashMap<_, _>>();
 
                 for (key, schema) in &inner.schema.types {
-                    let value = match values.get(key) {
+                    let value = match lookup_value(&values, channel.as_deref(), key) {
                         Some(value) => serde_json::from_str(value)?,
                         None if schema.ty.optional => serde_json::Value::Null,
                         None => continue,
@@ -700,11 +722,27 @@ where
     }
 
     /// Clear the given setting. Returning `true` if it was removed.
+    ///
+    /// For settings scoped to a channel this only clears the value of the
+    /// channel, so that the global value applies again.
     pub async fn clear(&self, key: &str) -> Result<bool, Error> {
-        let key = self.key(key);
+        let key = self.storage_key(key);
         self.inner_clear(&key).await
     }
 
+    /// Scope the settings to the given channel.
+    ///
+    /// Values set through the returned settings only apply to the given
+    /// channel, and values which haven't been set for the channel fall back
+    /// to the global value.
+    pub fn channel(&self, channel: &str) -> Settings<S> {
+        Settings {
+            scope: self.scope.clone(),
+            channel: Some(channel.into()),
+            inner: self.inner.clone(),
+        }
+    }
+
     /// Create a scoped setting.
     pub fn scoped(&self, s: &str) -> Settings<S> {
         let mut it = s.split('/').filter(|s| !s.is_empty());
@@ -741,6 +779,7 @@ where
 
         Settings {
             scope: scope.into(),
+            channel: self.channel.clone(),
             inner: self.inner.clone(),
         }
     }
@@ -902,31 +941,80 @@ where
         self.inner_set_json(key, value, notify).await
     }
 
+    /// Get the value of the given key, falling back to the global value if
+    /// the settings are scoped to a channel which doesn't override it.
+    async fn lookup<T>(&self, key: &str) -> Result<(Option<T>, Option<ChannelState>), Error>
+    where
+        T: Serialize + de::DeserializeOwned,
+    {
+        let Some(channel_key) = self.channel_key(key) else {
+            return Ok((self.inner_get(key).await?, None));
+        };
+
+        let channel = self.inner_get::<serde_json::Value>(&channel_key).await?;
+        let global = self.inner_get::<serde_json::Value>(key).await?;
+
+        let value = match channel.as_ref().or(global.as_ref()) {
+            Some(value) => decode(key, value.clone()),
+            None => None,
+        };
+
+        let state = ChannelState {
+            overridden: channel.is_some(),
+            global,
+        };
+
+        Ok((value, Some(state)))
+    }
+
     /// Subscribe for events on the given key.
-    async fn make_stream<T>(&self, key: &str, default: T) -> Stream<T>
+    async fn make_stream<T>(&self, key: &str, default: T, state: Option<ChannelState>) -> Stream<T>
     where
         T: Clone + Serialize + de::DeserializeOwned,
     {
         Stream {
             default,
-            option_stream: self.make_option_stream(key).await,
+            option_stream: self.make_option_stream(key, state).await,
         }
     }
 
     /// Subscribe for any events on the given key.
-    async fn make_option_stream<T>(&self, key: &str) -> OptionStream<T>
+    async fn make_option_stream<T>(&self, key: &str, state: Option<ChannelState>) -> OptionStream<T>
     where
         T: Serialize + de::DeserializeOwned,
     {
-        let rx = if let Some(sender) = self.inner.subscriptions.get(key) {
+        let global = if let Some(sender) = self.inner.subscriptions.get(key) {
             sender.subscribe()
         } else {
             panic!("no schema registered for key `{key}`");
         };
 
+        let (rx, fallback) = match (self.channel_key(key), state) {
+            (Some(channel_key), Some(state)) => {
+                let rx = self
+                    .inner
+                    .channel_subscriptions
+                    .lock()
+                    .unwrap()
+                    .entry(channel_key.into())
+                    .or_insert_with(|| broadcast::channel(1).0)
+                    .subscribe();
+
+                let fallback = Fallback {
+                    rx: global,
+                    value: state.global,
+                    overridden: state.overridden,
+                };
+
+                (rx, Some(fallback))
+            }
+            _ => (global, None),
+        };
+
         OptionStream {
             key: key.into(),
             rx,
+            fallback,
             marker: marker::PhantomData,
         }
     }
@@ -935,10 +1023,36 @@ where
     ///
     /// Cleans up the existing subscription if the other side is closed.
     async fn try_send(&self, key: &str, event: Update) {
+        // NB: intentionally ignore errors. There's nothing to be done in
+        // case there are any.
         if let Some(b) = self.inner.subscriptions.get(key) {
-            // NB: intentionally ignore errors. There's nothing to be done in
-            // case there are any.
             let _ = b.send(event);
+            return;
+        }
+
+        let mut channel_subscriptions = self.inner.channel_subscriptions.lock().unwrap();
+
+        if let Some(b) = channel_subscriptions.get(key) {
+            if b.send(event).is_err() {
+                channel_subscriptions.remove(key);
+            }
+        }
+    }
+
+    /// The key under which the value of the given key is stored for the
+    /// channel the settings are scoped to.
+    fn channel_key(&self, key: &str) -> Option<String> {
+        let channel = self.channel.as_deref()?;
+        Some(format!("{CHANNELS}{SEP}{channel}{SEP}{key}"))
+    }
+
+    /// The key under which values set through these settings are stored.
+    fn storage_key<'a>(&'a self, key: &'a str) -> Key<'a> {
+        let key = self.key(key);
+
+        match self.channel_key(&key) {
+            Some(channel_key) => Key::Owned(channel_key.into()),
+            None => key,
         }
     }
 
@@ -1052,35 +1166,51 @@ where
         T: Clone,
         F: FnOnce() -> T,
     {
-        let value = match self.settings.inner_get::<T>(&self.key).await? {
+        let (value, mut state) = self.settings.lookup::<T>(&self.key).await?;
+
+        let value = match value {
             Some(value) => value,
             None => {
+                // NB: defaults are always stored globally.
                 let value = value();
                 self.settings.inner_set(&self.key, &value, true).await?;
+
+                if let Some(state) = &mut state {
+                    state.global = Some(serde_json::to_value(&value)?);
+                }
+
                 value
             }
         };
 
-        let stream = self.settings.make_stream(&self.key, value.clone()).await;
+        let stream = self
+            .settings
+            .make_stream(&self.key, value.clone(), state)
+            .await;
         Ok((stream, value))
     }
 
     /// Make the setting optional.
     pub async fn optional(self) -> Result<(OptionStream<T>, Option<T>), Error> {
-        let value = self.settings.inner_get::<T>(&self.key).await?;
+        let (value, mut state) = self.settings.lookup::<T>(&self.key).await?;
 
         let value = match value {
             Some(value) => Some(value),
             None => match self.default_value {
                 Some(value) => {
                     self.settings.inner_set(&self.key, &value, true).await?;
+
+                    if let Some(state) = &mut state {
+                        state.global = Some(serde_json::to_value(&value)?);
+                    }
+
                     Some(value)
                 }
                 None => None,
             },
         };
 
-        let stream = self.settings.make_option_stream(&self.key).await;
+        let stream = self.settings.make_option_stream(&self.key, state).await;
         Ok((stream, value))
     }
 
@@ -1128,36 +1258,121 @@ where
 pub struct OptionStream<T> {
     key: Box<str>,
     rx: broadcast::Receiver<Update>,
+    /// Updates to the global value, for settings scoped to a channel.
+    fallback: Option<Fallback>,
     marker: marker::PhantomData<T>,
 }
 
+/// The global value of a setting scoped to a channel.
+struct Fallback {
+    rx: broadcast::Receiver<Update>,
+    /// The current global value.
+    value: Option<serde_json::Value>,
+    /// If the channel overrides the global value.
+    overridden: bool,
+}
+
 impl<T> OptionStream<T>
 where
     T: de::DeserializeOwned,
 {
     /// Recv the next update to the setting associated with the stream.
     pub async fn recv(&mut self) -> Option<T> {
-        let item = match self.rx.recv().await {
-            Ok(item) => item,
-            Err(error) => {
-                tracing::warn!(key = self.key, "Stream reader errored: {error}");
-                return None;
-            }
+        let Self {
+            key, rx, fallback, ..
+        } = self;
+        let key: &str = key;
+
+        let Some(fallback) = fallback else {
+            let item = match rx.recv().await {
+                Ok(item) => item,
+                Err(error) => {
+                    tracing::warn!(key = key, "Stream reader errored: {error}");
+                    return None;
+                }
+            };
+
+            return match item {
+                Event::Clear => None,
+                Event::Set(value) => decode(key, value),
+            };
         };
 
-        match item {
-            Event::Clear => None,
-            Event::Set(value) => match serde_json::from_value(value) {
-                Ok(value) => Some(value),
-                Err(error) => {
-                    tracing::warn!(key = self.key, "Bad value for key: {error}");
-                    None
+        loop {
+            tokio::select! {
+                item = rx.recv() => {
+                    let item = match item {
+                        Ok(item) => item,
+                        Err(error) => {
+                            tracing::warn!(key = key, "Stream reader errored: {error}");
+                            return None;
+                        }
+                    };
+
+                    return match item {
+                        Event::Clear => {
+                            fallback.overridden = false;
+                            decode(key, fallback.value.clone()?)
+                        }
+                        Event::Set(value) => {
+                            fallback.overridden = true;
+                            decode(key, value)
+                        }
+                    };
                 }
-            },
+                item = fallback.rx.recv() => {
+                    match item {
+                        Ok(Event::Clear) => {
+                            fallback.value = None;
+                        }
+                        Ok(Event::Set(value)) => {
+                            fallback.value = Some(value);
+                        }
+                        Err(error) => {
+                            tracing::warn!(key = key, "Stream reader errored: {error}");
+                            continue;
+                        }
+                    }
+
+                    if !fallback.overridden {
+                        return decode(key, fallback.value.clone()?);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Decode the value of the given key, warning if it's malformed.
+fn decode<T>(key: &str, value: serde_json::Value) -> Option<T>
+where
+    T: de::DeserializeOwned,
+{
+    match serde_json::from_value::<Option<T>>(value) {
+        Ok(value) => value,
+        Err(error) => {
+            tracing::warn!(key = key, "Bad value for key: {error}");
+            None
         }
     }
 }
 
+/// Look up the raw value of the given key, preferring the value of the given
+/// channel.
+fn lookup_value<'a>(
+    values: &'a HashMap<String, String>,
+    channel: Option<&str>,
+    key: &str,
+) -> Option<&'a String> {
+    if let Some(channel) = channel {
+        if let Some(value) = values.get(&format!("{CHANNELS}{SEP}{channel}{SEP}{key}")) {
+            return Some(value);
+        }
+    }
+
+    values.get(key)
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 #[derive(Default)]
@@ -0,0 +1,168 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use tokio::sync::RwLockReadGuard;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+use crate::{Fragment, EMPTY};
+
+#[derive(Debug, Deserialize)]
+struct PutBadWord {
+    #[serde(default)]
+    why: Option<String>,
+    #[serde(default)]
+    severity: i32,
+    action: db::words::Action,
+    /// The duration of a timeout in seconds.
+    #[serde(default)]
+    duration: Option<i32>,
+    #[serde(default)]
+    only_live: bool,
+}
+
+/// Bad words endpoints.
+#[derive(Clone)]
+pub(crate) struct BadWords(async_injector::Ref<db::Words>);
+
+impl BadWords {
+    pub(crate) fn route(
+        bad_words: async_injector::Ref<db::Words>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = BadWords(bad_words);
+
+        let list = warp::get()
+            .and(path::end())
+            .and_then({
+                let api = api.clone();
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        let edit = warp::put()
+            .and(path!(Fragment).and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |word: Fragment, body: PutBadWord| {
+                    let api = api.clone();
+                    async move {
+                        api.edit(word.as_str(), body)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let delete = warp::delete()
+            .and(path!(Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |word: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.delete(word.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let insert_exception = warp::put()
+            .and(path!(Fragment / "exceptions" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |word: Fragment, exception: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.insert_exception(word.as_str(), exception.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        let delete_exception = warp::delete()
+            .and(path!(Fragment / "exceptions" / Fragment).and(path::end()))
+            .and_then({
+                move |word: Fragment, exception: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.delete_exception(word.as_str(), exception.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            })
+            .boxed();
+
+        warp::path("bad-words")
+            .and(
+                list.or(edit)
+                    .or(delete)
+                    .or(insert_exception)
+                    .or(delete_exception),
+            )
+            .boxed()
+    }
+
+    /// Access underlying bad words abstraction.
+    async fn bad_words(&self) -> Result<RwLockReadGuard<'_, db::Words>> {
+        match self.0.read().await {
+            Some(out) => Ok(out),
+            None => Err(anyhow!("bad words not configured")),
+        }
+    }
+
+    /// List all bad words.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let entries = self.bad_words().await?.list().await?;
+        Ok(warp::reply::json(&entries))
+    }
+
+    /// Insert or replace a bad word.
+    async fn edit(&self, word: &str, body: PutBadWord) -> Result<impl warp::Reply> {
+        let bad_word = db::models::BadWord {
+            word: word.to_owned(),
+            why: body.why,
+            severity: body.severity,
+            action: body.action.as_str().to_owned(),
+            duration: body.duration,
+            only_live: body.only_live,
+        };
+
+        self.bad_words().await?.update(bad_word).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Delete a bad word.
+    async fn delete(&self, word: &str) -> Result<impl warp::Reply> {
+        self.bad_words().await?.delete(word).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Add an exception to a bad word.
+    async fn insert_exception(&self, word: &str, exception: &str) -> Result<impl warp::Reply> {
+        self.bad_words()
+            .await?
+            .insert_exception(word, exception)
+            .await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Remove an exception from a bad word.
+    async fn delete_exception(&self, word: &str, exception: &str) -> Result<impl warp::Reply> {
+        self.bad_words()
+            .await?
+            .delete_exception(word, exception)
+            .await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::{bail, Result};
+use common::Channel;
+use serde::Deserialize;
+use tokio::sync::RwLockReadGuard;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+use crate::{Fragment, EMPTY};
+
+#[derive(Deserialize)]
+struct PutCounter {
+    count: i64,
+}
+
+/// Counters endpoint.
+#[derive(Clone)]
+pub(crate) struct Counters {
+    counters: async_injector::Ref<db::Counters>,
+    global_bus: bus::Bus<bus::Global>,
+}
+
+impl Counters {
+    pub(crate) fn route(
+        counters: async_injector::Ref<db::Counters>,
+        global_bus: bus::Bus<bus::Global>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Counters {
+            counters,
+            global_bus,
+        };
+
+        let list = warp::get()
+            .and(path!("counters" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_channel())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let edit = warp::put()
+            .and(path!("counters" / Fragment / Fragment).and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, name: Fragment, body: PutCounter| {
+                    let api = api.clone();
+                    async move {
+                        api.edit(channel.as_channel(), name.as_str(), body.count)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let delete = warp::delete()
+            .and(path!("counters" / Fragment / Fragment).and(path::end()))
+            .and_then({
+                move |channel: Fragment, name: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.delete(channel.as_channel(), name.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        list.or(edit).or(delete).boxed()
+    }
+
+    /// Access underlying counters abstraction.
+    async fn counters(&self) -> Result<RwLockReadGuard<'_, db::Counters>> {
+        match self.counters.read().await {
+            Some(out) => Ok(out),
+            None => bail!("counters not configured"),
+        }
+    }
+
+    /// Get the list of all counters.
+    async fn list(&self, channel: &Channel) -> Result<impl warp::Reply> {
+        let counters = self.counters().await?.list(channel).await;
+        Ok(warp::reply::json(&counters))
+    }
+
+    /// Set the value of the given counter.
+    async fn edit(&self, channel: &Channel, name: &str, count: i64) -> Result<impl warp::Reply> {
+        if !db::counters::is_valid_name(name) {
+            bail!("counter names may only contain letters, digits, and underscores");
+        }
+
+        self.counters().await?.set(channel, name, count).await?;
+        self.publish(channel, name, Some(count)).await;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Delete the given counter.
+    async fn delete(&self, channel: &Channel, name: &str) -> Result<impl warp::Reply> {
+        if self.counters().await?.delete(channel, name).await? {
+            self.publish(channel, name, None).await;
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Notify overlays that a counter changed.
+    async fn publish(&self, channel: &Channel, name: &str, count: Option<i64>) {
+        self.global_bus
+            .send(bus::Global::Counter {
+                channel: channel.to_owned(),
+                name: name.to_lowercase(),
+                count,
+            })
+            .await;
+    }
+}
@@ -1,8 +1,17 @@
 #![allow(clippy::too_many_arguments)]
 
+mod bad_words;
 mod cache;
 mod chat;
+mod counters;
+mod moderation_log;
+mod queue;
+mod quotes;
+#[cfg(feature = "scripting")]
+mod script_data;
+mod scripts;
 mod settings;
+mod triggers;
 
 use std::borrow::Cow;
 use std::collections::HashMap;
@@ -26,9 +35,18 @@ use tracing::Instrument;
 use warp::{body, filters, path, Filter};
 
 use self::assets::Asset;
+use self::bad_words::BadWords;
 use self::cache::Cache;
 use self::chat::Chat;
+use self::counters::Counters;
+use self::moderation_log::ModerationLog;
+use self::queue::Queue;
+use self::quotes::Quotes;
+#[cfg(feature = "scripting")]
+use self::script_data::ScriptData;
+use self::scripts::Scripts;
 use self::settings::Settings;
+use self::triggers::Triggers;
 
 /// URL of public web interface.
 pub const URL: &str = "http://localhost:12345";
@@ -268,6 +286,22 @@ impl Commands {
                 }
             });
 
+        let edit_restrictions = warp::put()
+            .and(path!("commands" / Fragment / Fragment / "restrictions").and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, name: Fragment, body: db::commands::Restrictions| {
+                    let api = api.clone();
+
+                    async move {
+                        api.edit_restrictions(channel.as_channel(), name.as_str(), body)
+                            .await
+                            .map_err(custom_reject)
+                    }
+                }
+            });
+
         let edit = warp::put()
             .and(path!("commands" / Fragment / Fragment).and(path::end()))
             .and(body::json())
@@ -282,7 +316,12 @@ impl Commands {
                 }
             });
 
-        return list.or(delete).or(edit).or(edit_disabled).boxed();
+        return list
+            .or(delete)
+            .or(edit)
+            .or(edit_disabled)
+            .or(edit_restrictions)
+            .boxed();
 
         #[derive(Deserialize)]
         pub(crate) struct PutCommand {
@@ -315,6 +354,33 @@ impl Commands {
         Ok(warp::reply::json(&EMPTY))
     }
 
+    /// Edit the restrictions of the given command.
+    async fn edit_restrictions(
+        &self,
+        channel: &Channel,
+        name: &str,
+        restrictions: db::commands::Restrictions,
+    ) -> Result<impl warp::Reply> {
+        if let Some(requires) = &restrictions.requires {
+            str::parse::<auth::ScopeOrRole>(requires)?;
+        }
+
+        if matches!(restrictions.cost, Some(cost) if cost < 0) {
+            bail!("cost must not be negative");
+        }
+
+        if !self
+            .commands()
+            .await?
+            .edit_restrictions(channel, name, restrictions)
+            .await?
+        {
+            bail!("no such command");
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
     /// Set the given command's disabled status.
     async fn edit_disabled(
         &self,
@@ -795,6 +861,101 @@ impl Auth {
     }
 }
 
+/// Request to mark an after stream as done or not done.
+#[derive(Deserialize)]
+struct AfterStreamDone {
+    done: bool,
+}
+
+/// The format to export after streams in.
+#[derive(Default, Deserialize)]
+enum ExportFormat {
+    #[default]
+    #[serde(rename = "markdown")]
+    Markdown,
+    #[serde(rename = "json")]
+    Json,
+}
+
+/// Query for exporting after streams.
+#[derive(Deserialize)]
+struct AfterStreamsExport {
+    #[serde(default)]
+    format: ExportFormat,
+    #[serde(default)]
+    category: Option<String>,
+    #[serde(default)]
+    done: Option<bool>,
+    #[serde(default)]
+    stream_id: Option<String>,
+}
+
+/// Render after streams as a Markdown task list, grouped by the stream they
+/// were added during.
+fn after_streams_markdown(after_streams: &[db::models::AfterStream]) -> String {
+    use std::fmt::Write as _;
+
+    let mut out = String::from("# After Streams\n");
+    let mut current = None;
+
+    for a in after_streams {
+        let session = a.stream_id.as_deref().zip(a.stream_started_at);
+
+        if current != Some(session) {
+            match session {
+                Some((_, started_at)) => {
+                    let _ = write!(
+                        out,
+                        "\n## Stream started {}\n\n",
+                        started_at.format("%Y-%m-%d %H:%M UTC")
+                    );
+                }
+                None => {
+                    out.push_str("\n## Outside of streams\n\n");
+                }
+            }
+
+            current = Some(session);
+        }
+
+        let done = if a.done_at.is_some() { "x" } else { " " };
+        let _ = write!(out, "- [{}] ", done);
+
+        if let Some(category) = &a.category {
+            let _ = write!(out, "**{}** ", escape_markdown(category));
+        }
+
+        let _ = writeln!(
+            out,
+            "{} (@{}, {})",
+            escape_markdown(&a.text),
+            escape_markdown(&a.user),
+            a.added_at.format("%Y-%m-%d %H:%M")
+        );
+    }
+
+    out
+}
+
+/// Escape text so that it's shown as-is when rendered as Markdown.
+fn escape_markdown(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '#' | '|' | '[' | ']' | '<' | '>' | '~' | '!' => {
+                out.push('\\');
+                out.push(c);
+            }
+            // NB: a line break would end the list item.
+            '\n' | '\r' => out.push(' '),
+            c => out.push(c),
+        }
+    }
+
+    out
+}
+
 /// API to manage device.
 #[derive(Clone)]
 struct Api {
@@ -906,8 +1067,11 @@ impl Api {
     }
 
     /// Get the list of available after streams.
-    async fn get_after_streams(&self) -> Result<impl warp::Reply> {
-        let after_streams = self.after_streams().await?.list().await?;
+    async fn get_after_streams(
+        &self,
+        filter: db::after_streams::Filter,
+    ) -> Result<impl warp::Reply> {
+        let after_streams = self.after_streams().await?.list_filtered(filter).await?;
         Ok(warp::reply::json(&after_streams))
     }
 
@@ -917,6 +1081,43 @@ impl Api {
         Ok(warp::reply::json(&EMPTY))
     }
 
+    /// Mark an after stream as done or not done.
+    async fn after_stream_done(&self, id: i32, body: AfterStreamDone) -> Result<impl warp::Reply> {
+        if !self.after_streams().await?.set_done(id, body.done).await? {
+            bail!("no after stream with id {}", id);
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Export after streams, so that they can be imported elsewhere.
+    async fn export_after_streams(
+        &self,
+        query: AfterStreamsExport,
+    ) -> Result<warp::reply::Response> {
+        use warp::Reply as _;
+
+        let filter = db::after_streams::Filter {
+            category: query.category,
+            done: query.done,
+            stream_id: query.stream_id,
+        };
+
+        let after_streams = self.after_streams().await?.list_filtered(filter).await?;
+
+        let reply = match query.format {
+            ExportFormat::Json => warp::reply::json(&after_streams).into_response(),
+            ExportFormat::Markdown => warp::reply::with_header(
+                after_streams_markdown(&after_streams),
+                "content-type",
+                "text/markdown; charset=utf-8",
+            )
+            .into_response(),
+        };
+
+        Ok(reply)
+    }
+
     /// Import balances.
     async fn import_balances(
         self,
@@ -1002,6 +1203,8 @@ pub async fn run(
     message_bus: bus::Bus<messagelog::Event>,
     global_bus: bus::Bus<bus::Global>,
     youtube_bus: bus::Bus<bus::YouTube>,
+    countdown_bus: bus::Bus<bus::Countdown>,
+    script_bus: bus::Bus<bus::Script>,
     command_bus: bus::Bus<bus::Command>,
     auth: auth::Auth,
     latest: ::settings::Var<Option<api::github::Release>>,
@@ -1066,13 +1269,42 @@ pub async fn run(
             .boxed();
 
         let route = route
-            .or(warp::get().and(warp::path("after-streams")).and_then({
-                let api = api.clone();
-                move || {
+            .or(warp::put()
+                .and(path!("after-stream" / i32 / "done"))
+                .and(warp::body::json())
+                .and_then({
                     let api = api.clone();
-                    async move { api.get_after_streams().await.map_err(custom_reject) }
-                }
-            }))
+                    move |id, body: AfterStreamDone| {
+                        let api = api.clone();
+                        async move { api.after_stream_done(id, body).await.map_err(custom_reject) }
+                    }
+                }))
+            .boxed();
+
+        let route = route
+            .or(warp::get()
+                .and(path!("after-streams" / "export"))
+                .and(warp::query::<AfterStreamsExport>())
+                .and_then({
+                    let api = api.clone();
+                    move |query: AfterStreamsExport| {
+                        let api = api.clone();
+                        async move { api.export_after_streams(query).await.map_err(custom_reject) }
+                    }
+                }))
+            .boxed();
+
+        let route = route
+            .or(warp::get()
+                .and(path!("after-streams"))
+                .and(warp::query::<db::after_streams::Filter>())
+                .and_then({
+                    let api = api.clone();
+                    move |filter: db::after_streams::Filter| {
+                        let api = api.clone();
+                        async move { api.get_after_streams(filter).await.map_err(custom_reject) }
+                    }
+                }))
             .boxed();
 
         let route = route
@@ -1117,6 +1349,15 @@ pub async fn run(
         let route = route.or(Themes::route(injector.var().await));
         let route = route.or(Settings::route(injector.var().await));
         let route = route.or(Cache::route(injector.var().await));
+        let route = route.or(ModerationLog::route(injector.var().await));
+        let route = route.or(BadWords::route(injector.var().await));
+        let route = route.or(Triggers::route(injector.var().await));
+        let route = route.or(Quotes::route(injector.var().await));
+        let route = route.or(Counters::route(injector.var().await, global_bus.clone()));
+        let route = route.or(Queue::route(injector.var().await, global_bus.clone()));
+        let route = route.or(Scripts::route(script_bus.clone()));
+        #[cfg(feature = "scripting")]
+        let route = route.or(ScriptData::route(injector.var().await));
         let route = route.or(Chat::route(command_bus, message_log));
 
         // TODO: move endpoint into abstraction thingie.
@@ -1150,10 +1391,20 @@ pub async fn run(
         .and(warp::path!("ws" / "youtube"))
         .and(send_bus(youtube_bus).recover(recover));
 
+    let ws_countdowns = warp::get()
+        .and(warp::path!("ws" / "countdowns"))
+        .and(send_bus(countdown_bus).recover(recover));
+
+    let ws_scripts = warp::get()
+        .and(warp::path!("ws" / "scripts"))
+        .and(send_bus(script_bus).recover(recover));
+
     let routes = api.recover(recover);
     let routes = routes.or(ws_messages.recover(recover));
     let routes = routes.or(ws_overlay.recover(recover));
     let routes = routes.or(ws_youtube.recover(recover));
+    let routes = routes.or(ws_countdowns.recover(recover));
+    let routes = routes.or(ws_scripts.recover(recover));
 
     let fallback = Asset::get("index.html");
     let fallback = fallback.map(|f| f.data);
@@ -1365,3 +1616,59 @@ where
         ws.send(m).await?;
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::{after_streams_markdown, escape_markdown};
+
+    #[test]
+    fn test_escape_markdown() {
+        assert_eq!(escape_markdown("fix the *lag*"), "fix the \\*lag\\*");
+        assert_eq!(escape_markdown("# | [a](b)"), "\\# \\| \\[a\\](b)");
+        assert_eq!(escape_markdown("two\nlines"), "two lines");
+    }
+
+    #[test]
+    fn test_after_streams_markdown() -> Result<()> {
+        let after_stream = db::models::AfterStream {
+            id: 1,
+            channel: None,
+            added_at: "2024-04-20T19:30:00".parse()?,
+            user: String::from("cool_user"),
+            text: String::from("play **the** song | again\n# now"),
+            category: Some(String::from("game_ideas")),
+            done_at: Some("2024-04-21T10:00:00".parse()?),
+            stream_id: Some(String::from("1234")),
+            stream_started_at: Some("2024-04-20T19:00:00".parse()?),
+        };
+
+        let outside = db::models::AfterStream {
+            id: 2,
+            added_at: "2024-04-22T12:00:00".parse()?,
+            user: String::from("setbac"),
+            text: String::from("remember `this`"),
+            category: None,
+            done_at: None,
+            stream_id: None,
+            stream_started_at: None,
+            ..after_stream.clone()
+        };
+
+        assert_eq!(
+            after_streams_markdown(&[after_stream, outside]),
+            "# After Streams\n\
+             \n\
+             ## Stream started 2024-04-20 19:00 UTC\n\
+             \n\
+             - [x] **game\\_ideas** play \\*\\*the\\*\\* song \\| again \\# now (@cool\\_user, 2024-04-20 19:30)\n\
+             \n\
+             ## Outside of streams\n\
+             \n\
+             - [ ] remember \\`this\\` (@setbac, 2024-04-22 12:00)\n"
+        );
+
+        Ok(())
+    }
+}
@@ -0,0 +1,43 @@
+use anyhow::{anyhow, Result};
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+/// Moderation log endpoints.
+#[derive(Clone)]
+pub(crate) struct ModerationLog(async_injector::Ref<db::ModerationLog>);
+
+impl ModerationLog {
+    pub(crate) fn route(
+        moderation_log: async_injector::Ref<db::ModerationLog>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = ModerationLog(moderation_log);
+
+        let list = warp::get()
+            .and(warp::query::<db::moderation_log::Filter>().and(path::end()))
+            .and_then({
+                move |filter: db::moderation_log::Filter| {
+                    let api = api.clone();
+                    async move { api.list(filter).await.map_err(super::custom_reject) }
+                }
+            })
+            .boxed();
+
+        warp::path("moderation-log").and(list).boxed()
+    }
+
+    /// Access underlying moderation log abstraction.
+    async fn moderation_log(&self) -> Result<RwLockReadGuard<'_, db::ModerationLog>> {
+        match self.0.read().await {
+            Some(out) => Ok(out),
+            None => Err(anyhow!("moderation log not configured")),
+        }
+    }
+
+    /// List moderation log entries matching the given filter.
+    async fn list(&self, filter: db::moderation_log::Filter) -> Result<impl warp::Reply> {
+        let entries = self.moderation_log().await?.list(filter).await?;
+        Ok(warp::reply::json(&entries))
+    }
+}
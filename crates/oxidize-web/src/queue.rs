@@ -0,0 +1,122 @@
+use anyhow::{bail, Result};
+use common::Channel;
+use tokio::sync::RwLockReadGuard;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+use crate::{Fragment, EMPTY};
+
+/// Viewer queue endpoint.
+#[derive(Clone)]
+pub(crate) struct Queue {
+    queue: async_injector::Ref<db::Queue>,
+    global_bus: bus::Bus<bus::Global>,
+}
+
+impl Queue {
+    pub(crate) fn route(
+        queue: async_injector::Ref<db::Queue>,
+        global_bus: bus::Bus<bus::Global>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Queue { queue, global_bus };
+
+        let list = warp::get()
+            .and(path!("queue" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_channel())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let clear = warp::delete()
+            .and(path!("queue" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.clear(channel.as_channel())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let remove = warp::delete()
+            .and(path!("queue" / Fragment / Fragment).and(path::end()))
+            .and_then({
+                move |channel: Fragment, user: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.remove(channel.as_channel(), user.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        list.or(clear).or(remove).boxed()
+    }
+
+    /// Access underlying queue abstraction.
+    async fn queue(&self) -> Result<RwLockReadGuard<'_, db::Queue>> {
+        match self.queue.read().await {
+            Some(out) => Ok(out),
+            None => bail!("queue not configured"),
+        }
+    }
+
+    /// List everyone in the queue, in order.
+    async fn list(&self, channel: &Channel) -> Result<impl warp::Reply> {
+        let entries = self.queue().await?.list(channel).await?;
+        Ok(warp::reply::json(&entries))
+    }
+
+    /// Remove everyone from the queue.
+    async fn clear(&self, channel: &Channel) -> Result<impl warp::Reply> {
+        let queue = self.queue().await?;
+        queue.clear(channel).await?;
+        self.publish(&queue, channel).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Remove a single user from the queue.
+    async fn remove(&self, channel: &Channel, user: &str) -> Result<impl warp::Reply> {
+        let queue = self.queue().await?;
+
+        if queue.leave(channel, user).await? {
+            self.publish(&queue, channel).await?;
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Notify overlays of the current state of the queue.
+    async fn publish(&self, queue: &db::Queue, channel: &Channel) -> Result<()> {
+        let users = queue
+            .list(channel)
+            .await?
+            .into_iter()
+            .map(|e| bus::QueueUser {
+                user: e.user,
+                display_name: e.display_name,
+            })
+            .collect();
+
+        self.global_bus
+            .send(bus::Global::Queue {
+                channel: channel.to_owned(),
+                users,
+            })
+            .await;
+
+        Ok(())
+    }
+}
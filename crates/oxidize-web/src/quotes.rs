@@ -0,0 +1,181 @@
+use anyhow::{bail, Result};
+use common::Channel;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLockReadGuard;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+use crate::{Fragment, EMPTY};
+
+#[derive(Deserialize)]
+struct ListQuery {
+    /// Only list quotes whose text or author contains this.
+    #[serde(default)]
+    q: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PostQuote {
+    author: String,
+    text: String,
+    /// Who added the quote, defaults to `web`.
+    #[serde(default)]
+    added_by: Option<String>,
+    #[serde(default)]
+    game: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PutQuote {
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    text: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Created {
+    id: i32,
+}
+
+/// Quotes endpoint.
+#[derive(Clone)]
+pub(crate) struct Quotes(async_injector::Ref<db::Quotes>);
+
+impl Quotes {
+    pub(crate) fn route(
+        quotes: async_injector::Ref<db::Quotes>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Quotes(quotes);
+
+        let list = warp::get()
+            .and(path!("quotes" / Fragment).and(path::end()))
+            .and(warp::query::<ListQuery>())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, query: ListQuery| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_channel(), query)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let insert = warp::post()
+            .and(path!("quotes" / Fragment).and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, body: PostQuote| {
+                    let api = api.clone();
+                    async move {
+                        api.insert(channel.as_channel(), body)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let edit = warp::put()
+            .and(path!("quotes" / Fragment / i32).and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, id: i32, body: PutQuote| {
+                    let api = api.clone();
+                    async move {
+                        api.edit(channel.as_channel(), id, body)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let delete = warp::delete()
+            .and(path!("quotes" / Fragment / i32).and(path::end()))
+            .and_then({
+                move |channel: Fragment, id: i32| {
+                    let api = api.clone();
+                    async move {
+                        api.delete(channel.as_channel(), id)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        list.or(insert).or(edit).or(delete).boxed()
+    }
+
+    /// Access underlying quotes abstraction.
+    async fn quotes(&self) -> Result<RwLockReadGuard<'_, db::Quotes>> {
+        match self.0.read().await {
+            Some(out) => Ok(out),
+            None => bail!("quotes not configured"),
+        }
+    }
+
+    /// List or search quotes.
+    async fn list(&self, channel: &Channel, query: ListQuery) -> Result<impl warp::Reply> {
+        let quotes = self.quotes().await?;
+
+        let quotes = match query.q.as_deref().map(str::trim) {
+            Some(q) if !q.is_empty() => quotes.search(channel, q).await?,
+            _ => quotes.list(channel).await?,
+        };
+
+        Ok(warp::reply::json(&quotes))
+    }
+
+    /// Add a quote.
+    async fn insert(&self, channel: &Channel, body: PostQuote) -> Result<impl warp::Reply> {
+        if body.author.trim().is_empty() || body.text.trim().is_empty() {
+            bail!("quotes must have an author and text");
+        }
+
+        let added_by = body.added_by.as_deref().unwrap_or("web");
+
+        let id = self
+            .quotes()
+            .await?
+            .insert(
+                channel,
+                added_by,
+                body.author.trim(),
+                body.game.as_deref(),
+                body.text.trim(),
+            )
+            .await?;
+
+        Ok(warp::reply::json(&Created { id }))
+    }
+
+    /// Edit the author or text of a quote.
+    async fn edit(&self, channel: &Channel, id: i32, body: PutQuote) -> Result<impl warp::Reply> {
+        if body.author.is_none() && body.text.is_none() {
+            bail!("nothing to edit");
+        }
+
+        let edited = self
+            .quotes()
+            .await?
+            .edit(channel, id, body.author.as_deref(), body.text.as_deref())
+            .await?;
+
+        if !edited {
+            bail!("no such quote");
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Delete a quote.
+    async fn delete(&self, channel: &Channel, id: i32) -> Result<impl warp::Reply> {
+        self.quotes().await?.delete(channel, id).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
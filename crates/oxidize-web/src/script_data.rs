@@ -0,0 +1,140 @@
+use anyhow::{bail, Result};
+use common::{Channel, Duration};
+use serde::Deserialize;
+use tokio::sync::RwLockReadGuard;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+use crate::{Fragment, EMPTY};
+
+#[derive(Deserialize)]
+struct ListQuery {
+    /// Only list keys in the given channel.
+    #[serde(default)]
+    channel: Option<String>,
+    /// Only list keys in the given namespace.
+    #[serde(default)]
+    namespace: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct PutScriptKey {
+    value: serde_json::Value,
+    /// Expire the key after the given duration, like `10m`.
+    #[serde(default)]
+    ttl: Option<Duration>,
+}
+
+/// Script data endpoint.
+#[derive(Clone)]
+pub(crate) struct ScriptData(async_injector::Ref<db::ScriptKeys>);
+
+impl ScriptData {
+    pub(crate) fn route(
+        script_keys: async_injector::Ref<db::ScriptKeys>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = ScriptData(script_keys);
+
+        let list = warp::get()
+            .and(path!("script-data").and(path::end()))
+            .and(warp::query::<ListQuery>())
+            .and_then({
+                let api = api.clone();
+                move |query: ListQuery| {
+                    let api = api.clone();
+                    async move { api.list(query).await.map_err(super::custom_reject) }
+                }
+            });
+
+        let edit = warp::put()
+            .and(path!("script-data" / Fragment / Fragment / Fragment).and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, namespace: Fragment, id: Fragment, body: PutScriptKey| {
+                    let api = api.clone();
+                    async move {
+                        api.edit(channel.as_channel(), namespace.as_str(), id.as_str(), body)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let delete = warp::delete()
+            .and(path!("script-data" / Fragment / Fragment / Fragment).and(path::end()))
+            .and_then({
+                move |channel: Fragment, namespace: Fragment, id: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.delete(channel.as_channel(), namespace.as_str(), id.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        list.or(edit).or(delete).boxed()
+    }
+
+    /// Access underlying script keys abstraction.
+    async fn script_keys(&self) -> Result<RwLockReadGuard<'_, db::ScriptKeys>> {
+        match self.0.read().await {
+            Some(out) => Ok(out),
+            None => bail!("script data not configured"),
+        }
+    }
+
+    /// List stored script data.
+    async fn list(&self, query: ListQuery) -> Result<impl warp::Reply> {
+        let entries = self
+            .script_keys()
+            .await?
+            .list(
+                query.channel.as_deref().map(Channel::new),
+                query.namespace.as_deref(),
+            )
+            .await?;
+
+        Ok(warp::reply::json(&entries))
+    }
+
+    /// Set the value of the given key.
+    async fn edit(
+        &self,
+        channel: &Channel,
+        namespace: &str,
+        id: &str,
+        body: PutScriptKey,
+    ) -> Result<impl warp::Reply> {
+        self.script_keys()
+            .await?
+            .set(
+                channel,
+                namespace,
+                id,
+                body.value,
+                body.ttl.map(|ttl| ttl.as_std()),
+            )
+            .await?;
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Delete the given key.
+    async fn delete(
+        &self,
+        channel: &Channel,
+        namespace: &str,
+        id: &str,
+    ) -> Result<impl warp::Reply> {
+        self.script_keys()
+            .await?
+            .delete(channel, namespace, id)
+            .await?;
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+}
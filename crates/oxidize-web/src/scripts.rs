@@ -0,0 +1,42 @@
+use anyhow::Result;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+/// Scripts endpoint.
+#[derive(Clone)]
+pub(crate) struct Scripts {
+    script_bus: bus::Bus<bus::Script>,
+}
+
+impl Scripts {
+    pub(crate) fn route(
+        script_bus: bus::Bus<bus::Script>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Scripts { script_bus };
+
+        let list = warp::get()
+            .and(path!("scripts").and(path::end()))
+            .and_then({
+                move || {
+                    let api = api.clone();
+                    async move { api.list().await.map_err(super::custom_reject) }
+                }
+            });
+
+        list.boxed()
+    }
+
+    /// List the state of all loaded scripts.
+    async fn list(&self) -> Result<impl warp::Reply> {
+        let mut scripts = Vec::new();
+
+        for message in self.script_bus.latest().await {
+            if let bus::Script::Scripts { scripts: states } = message {
+                scripts.extend(states);
+            }
+        }
+
+        Ok(warp::reply::json(&scripts))
+    }
+}
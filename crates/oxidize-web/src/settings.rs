@@ -10,8 +10,18 @@ pub(crate) struct PutSetting {
     value: serde_json::Value,
 }
 
+/// Access settings as they apply to the given channel, instead of the global
+/// settings.
+#[derive(serde::Deserialize)]
+struct ChannelQuery {
+    #[serde(default)]
+    channel: Option<String>,
+}
+
 #[derive(serde::Deserialize)]
 struct SettingsQuery {
+    #[serde(default)]
+    channel: Option<String>,
     #[serde(default)]
     key: Option<String>,
     #[serde(default)]
@@ -43,52 +53,64 @@ impl Settings {
             .boxed();
 
         let get = warp::get()
-            .and(warp::path("settings").and(path::tail()).and_then({
-                let api = api.clone();
-                move |key: path::Tail| {
-                    let api = api.clone();
+            .and(
+                warp::path("settings")
+                    .and(path::tail())
+                    .and(warp::query::<ChannelQuery>())
+                    .and_then({
+                        let api = api.clone();
+                        move |key: path::Tail, query: ChannelQuery| {
+                            let api = api.clone();
 
-                    async move {
-                        let key =
-                            str::parse::<Fragment>(key.as_str()).map_err(super::custom_reject)?;
-                        api.get_setting(key.as_str())
-                            .await
-                            .map_err(super::custom_reject)
-                    }
-                }
-            }))
+                            async move {
+                                let key = str::parse::<Fragment>(key.as_str())
+                                    .map_err(super::custom_reject)?;
+                                api.get_setting(query.channel.as_deref(), key.as_str())
+                                    .await
+                                    .map_err(super::custom_reject)
+                            }
+                        }
+                    }),
+            )
             .boxed();
 
         let delete = warp::delete()
-            .and(warp::path("settings").and(path::tail()).and_then({
-                let api = api.clone();
+            .and(
+                warp::path("settings")
+                    .and(path::tail())
+                    .and(warp::query::<ChannelQuery>())
+                    .and_then({
+                        let api = api.clone();
 
-                move |key: path::Tail| {
-                    let api = api.clone();
+                        move |key: path::Tail, query: ChannelQuery| {
+                            let api = api.clone();
 
-                    async move {
-                        let key =
-                            str::parse::<Fragment>(key.as_str()).map_err(super::custom_reject)?;
-                        api.delete_setting(key.as_str())
-                            .await
-                            .map_err(super::custom_reject)
-                    }
-                }
-            }))
+                            async move {
+                                let key = str::parse::<Fragment>(key.as_str())
+                                    .map_err(super::custom_reject)?;
+                                api.delete_setting(query.channel.as_deref(), key.as_str())
+                                    .await
+                                    .map_err(super::custom_reject)
+                            }
+                        }
+                    }),
+            )
             .boxed();
 
         let edit = warp::put()
             .and(
                 warp::path("settings")
-                    .and(path::tail().and(body::json()))
+                    .and(path::tail())
+                    .and(warp::query::<ChannelQuery>())
+                    .and(body::json())
                     .and_then({
-                        move |key: path::Tail, body: PutSetting| {
+                        move |key: path::Tail, query: ChannelQuery, body: PutSetting| {
                             let api = api.clone();
 
                             async move {
                                 let key = str::parse::<Fragment>(key.as_str())
                                     .map_err(super::custom_reject)?;
-                                api.edit_setting(key.as_str(), body.value)
+                                api.edit_setting(query.channel.as_deref(), key.as_str(), body.value)
                                     .await
                                     .map_err(super::custom_reject)
                             }
@@ -108,12 +130,26 @@ impl Settings {
         }
     }
 
+    /// Access settings, scoped to the given channel if specified.
+    async fn channel_settings(
+        &self,
+        channel: Option<&str>,
+    ) -> Result<::settings::Settings<::auth::Scope>> {
+        let settings = self.settings().await?;
+
+        Ok(match channel {
+            Some(channel) => settings.channel(channel),
+            None => settings.clone(),
+        })
+    }
+
     /// Get the list of all settings in the bot.
     async fn get_settings(&self, query: SettingsQuery) -> Result<impl warp::Reply> {
+        let settings = self.channel_settings(query.channel.as_deref()).await?;
+
         let mut settings = match query.prefix {
             Some(prefix) => {
                 let mut out = Vec::new();
-                let settings = self.settings().await?;
 
                 for prefix in prefix.split(',') {
                     out.extend(settings.list_by_prefix(prefix).await?);
@@ -121,7 +157,7 @@ impl Settings {
 
                 out
             }
-            None => self.settings().await?.list().await?,
+            None => settings.list().await?,
         };
 
         if let Some(key) = query.key {
@@ -157,15 +193,15 @@ impl Settings {
     }
 
     /// Delete the given setting by key.
-    async fn delete_setting(&self, key: &str) -> Result<impl warp::Reply> {
-        let settings = self.settings().await?;
+    async fn delete_setting(&self, channel: Option<&str>, key: &str) -> Result<impl warp::Reply> {
+        let settings = self.channel_settings(channel).await?;
         settings.clear(key).await?;
         Ok(warp::reply::json(&EMPTY))
     }
 
     /// Get the given setting by key.
-    async fn get_setting(&self, key: &str) -> Result<impl warp::Reply> {
-        let settings = self.settings().await?;
+    async fn get_setting(&self, channel: Option<&str>, key: &str) -> Result<impl warp::Reply> {
+        let settings = self.channel_settings(channel).await?;
         let setting: Option<::settings::Setting<auth::Scope>> = settings
             .setting::<serde_json::Value>(key)
             .await?
@@ -174,8 +210,13 @@ impl Settings {
     }
 
     /// Delete the given setting by key.
-    async fn edit_setting(&self, key: &str, value: serde_json::Value) -> Result<impl warp::Reply> {
-        let settings = self.settings().await?;
+    async fn edit_setting(
+        &self,
+        channel: Option<&str>,
+        key: &str,
+        value: serde_json::Value,
+    ) -> Result<impl warp::Reply> {
+        let settings = self.channel_settings(channel).await?;
         settings.set_json(key, value).await?;
         Ok(warp::reply::json(&EMPTY))
     }
@@ -0,0 +1,144 @@
+use anyhow::{bail, Result};
+use common::Channel;
+use db::triggers::{Kind, Pattern, Update};
+use serde::Deserialize;
+use tokio::sync::RwLockReadGuard;
+use warp::body;
+use warp::filters;
+use warp::path;
+use warp::Filter;
+
+use crate::{DisabledBody, Fragment, EMPTY};
+
+#[derive(Deserialize)]
+struct PutTrigger {
+    kind: Kind,
+    pattern: String,
+    template: template::Template,
+    #[serde(flatten)]
+    update: Update,
+}
+
+/// Triggers endpoint.
+#[derive(Clone)]
+pub(crate) struct Triggers(async_injector::Ref<db::Triggers>);
+
+impl Triggers {
+    pub(crate) fn route(
+        triggers: async_injector::Ref<db::Triggers>,
+    ) -> filters::BoxedFilter<(impl warp::Reply,)> {
+        let api = Triggers(triggers);
+
+        let list = warp::get()
+            .and(path!("triggers" / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.list(channel.as_channel())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let delete = warp::delete()
+            .and(path!("triggers" / Fragment / Fragment).and(path::end()))
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, name: Fragment| {
+                    let api = api.clone();
+                    async move {
+                        api.delete(channel.as_channel(), name.as_str())
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let edit = warp::put()
+            .and(path!("triggers" / Fragment / Fragment).and(path::end()))
+            .and(body::json())
+            .and_then({
+                let api = api.clone();
+                move |channel: Fragment, name: Fragment, body: PutTrigger| {
+                    let api = api.clone();
+                    async move {
+                        api.edit(channel.as_channel(), name.as_str(), body)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        let edit_disabled = warp::post()
+            .and(path!("triggers" / Fragment / Fragment / "disabled").and(path::end()))
+            .and(body::json())
+            .and_then({
+                move |channel: Fragment, name: Fragment, body: DisabledBody| {
+                    let api = api.clone();
+                    async move {
+                        api.edit_disabled(channel.as_channel(), name.as_str(), body.disabled)
+                            .await
+                            .map_err(super::custom_reject)
+                    }
+                }
+            });
+
+        list.or(delete).or(edit).or(edit_disabled).boxed()
+    }
+
+    /// Access underlying triggers abstraction.
+    async fn triggers(&self) -> Result<RwLockReadGuard<'_, db::Triggers>> {
+        match self.0.read().await {
+            Some(out) => Ok(out),
+            None => bail!("triggers not configured"),
+        }
+    }
+
+    /// Get the list of all triggers.
+    async fn list(&self, channel: &Channel) -> Result<impl warp::Reply> {
+        let triggers = self.triggers().await?.list_all(channel).await?;
+        Ok(warp::reply::json(&triggers))
+    }
+
+    /// Insert or edit the given trigger.
+    async fn edit(
+        &self,
+        channel: &Channel,
+        name: &str,
+        body: PutTrigger,
+    ) -> Result<impl warp::Reply> {
+        let pattern = Pattern::new(body.kind, &body.pattern)?;
+
+        let triggers = self.triggers().await?;
+        triggers.edit(channel, name, pattern, body.template).await?;
+        triggers.update(channel, name, body.update).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Set the given trigger's disabled status.
+    async fn edit_disabled(
+        &self,
+        channel: &Channel,
+        name: &str,
+        disabled: bool,
+    ) -> Result<impl warp::Reply> {
+        let triggers = self.triggers().await?;
+
+        if disabled {
+            triggers.disable(channel, name).await?;
+        } else {
+            triggers.enable(channel, name).await?;
+        }
+
+        Ok(warp::reply::json(&EMPTY))
+    }
+
+    /// Delete the given trigger.
+    async fn delete(&self, channel: &Channel, name: &str) -> Result<impl warp::Reply> {
+        self.triggers().await?.delete(channel, name).await?;
+        Ok(warp::reply::json(&EMPTY))
+    }
+}